@@ -22,7 +22,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 2. Quick Discovery auf erstem Device
     println!("\n2. Running quick discovery on '{}'...", devices[0]);
 
-    match iodisco::discovery::scan_device(Some(&devices[0])) {
+    match iodisco::discovery::scan_device(Some(&devices[0]), "quick", None) {
         Ok(result) => {
             println!("   ✅ Discovery successful!");
 
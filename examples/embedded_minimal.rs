@@ -1,7 +1,5 @@
 //! Example for embedded systems with minimal features
-//! Build with: cargo build --example embedded_minimal --no-default-features --features mali
-
-use iodisco;
+//! Build with: cargo build --example embedded_minimal --no-default-features --features std,mali
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Minimal initialization
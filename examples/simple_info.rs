@@ -1,7 +1,5 @@
 //! Simple example showing basic GPU information retrieval
 
-use iodisco;
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📱 iodisco GPU Information Example");
     println!("==================================\n");
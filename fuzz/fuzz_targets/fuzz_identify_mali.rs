@@ -0,0 +1,13 @@
+//! Fuzz Mali GPU identification with arbitrary 32-bit GPU identifiers
+
+#![no_main]
+
+use iodisco::mappings::{identify_mali_gpu, parse_mali_core_count, parse_mali_gpu_id, parse_mali_variant};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|gpu_id: u32| {
+    let _ = identify_mali_gpu(gpu_id);
+    let _ = parse_mali_gpu_id(gpu_id);
+    let _ = parse_mali_core_count(gpu_id);
+    let _ = parse_mali_variant(gpu_id);
+});
@@ -0,0 +1,12 @@
+//! Fuzz IoctlProfile deserialization with arbitrary input bytes
+
+#![no_main]
+
+use iodisco::profiles::IoctlProfile;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = serde_json::from_str::<IoctlProfile>(text);
+    }
+});
@@ -1,8 +1,158 @@
-use iodisco;
+//! Minimal GPU identification binary for api-only builds
+//!
+//! Prints `GPU: <vendor> <model>` by default; `--format extended` prints
+//! every known `GpuInfo` field as `field: value`, one per line.
+
+use std::fmt;
+
+use iodisco::{GpuInfo, GpuInfoError};
+
+const USAGE: &str = "Usage: gpuinfo [--format short|extended]";
 
 fn main() {
-    match iodisco::get_gpu_info_static() {
-        Ok(gpu) => println!("GPU: {} {}", gpu.vendor, gpu.model),
-        Err(e) => eprintln!("Error: {}", e),
+    match run(std::env::args().skip(1), iodisco::api::get_gpu_info_static) {
+        Ok(output) => print!("{}", output),
+        Err(failure) => {
+            eprintln!("Error: {}", failure);
+            if let Failure::Usage(_) = failure {
+                eprintln!("{}", USAGE);
+            }
+            std::process::exit(failure.exit_code());
+        }
+    }
+}
+
+/// Why gpuinfo gave up
+#[derive(Debug)]
+enum Failure {
+    /// Bad command line
+    Usage(String),
+    /// The GPU couldn't be identified
+    Gpu(GpuInfoError),
+}
+
+impl Failure {
+    /// Process exit code: 2 for usage errors, 1 when the GPU lookup fails
+    fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Usage(_) => 2,
+            Failure::Gpu(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Usage(message) => f.write_str(message),
+            Failure::Gpu(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Output for the given arguments, using `get_info` to identify the GPU
+fn run(
+    args: impl Iterator<Item = String>,
+    get_info: impl FnOnce() -> Result<GpuInfo, GpuInfoError>,
+) -> Result<String, Failure> {
+    let extended = parse_format(args).map_err(Failure::Usage)?;
+    let gpu = get_info().map_err(Failure::Gpu)?;
+    if extended {
+        Ok(format_extended(&gpu))
+    } else {
+        Ok(format!("GPU: {} {}\n", gpu.vendor, gpu.model))
+    }
+}
+
+/// Whether `--format extended` was requested
+fn parse_format(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut extended = false;
+    while let Some(arg) = args.next() {
+        let format = match arg.strip_prefix("--format") {
+            Some("") => args.next().ok_or("--format needs a value")?,
+            Some(value) if value.starts_with('=') => value[1..].to_string(),
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        };
+        extended = match format.as_str() {
+            "short" => false,
+            "extended" => true,
+            other => return Err(format!("unknown format '{}'", other)),
+        };
+    }
+    Ok(extended)
+}
+
+/// Every known field as `field: value`, one per line
+fn format_extended(gpu: &GpuInfo) -> String {
+    let hex = |value: u32| format!("0x{:08x}", value);
+    let fields = [
+        ("vendor", Some(gpu.vendor.clone())),
+        ("model", Some(gpu.model.clone())),
+        ("architecture", gpu.architecture.clone()),
+        ("arch_version", gpu.arch_version.clone()),
+        ("driver_version", gpu.driver_version.clone()),
+        ("gpu_id", gpu.gpu_id.map(hex)),
+        ("cores", gpu.cores.map(|v| v.to_string())),
+        ("core_mask", gpu.core_mask.map(hex)),
+        ("l2_cache_count", gpu.l2_cache_count.map(|v| v.to_string())),
+        ("l2_cache_size", gpu.l2_cache_size.map(|v| v.to_string())),
+        ("bus_width", gpu.bus_width.map(|v| v.to_string())),
+        ("engines_per_core", gpu.engines_per_core.map(|v| v.to_string())),
+        ("fp32_fmas_per_core", gpu.fp32_fmas_per_core.map(|v| v.to_string())),
+        ("fp16_fmas_per_core", gpu.fp16_fmas_per_core.map(|v| v.to_string())),
+        ("texels_per_core", gpu.texels_per_core.map(|v| v.to_string())),
+        ("pixels_per_core", gpu.pixels_per_core.map(|v| v.to_string())),
+        ("features", Some(gpu.features.join(", ")).filter(|f| !f.is_empty())),
+    ];
+    fields.into_iter()
+        .filter_map(|(name, value)| Some(format!("{}: {}\n", name, value?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter().map(|a| a.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    fn gpu() -> Result<GpuInfo, GpuInfoError> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "vendor": "Mali",
+            "model": "Mali-G78",
+            "architecture": "Valhall",
+            "gpu_id": 0x9086_0000u32,
+            "cores": 20,
+            "features": ["FP16", "ASTC"],
+            "detected_ioctls": [],
+            "metadata": null,
+        })).unwrap())
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(run(args(&[]), gpu).unwrap(), "GPU: Mali Mali-G78\n");
+        assert_eq!(run(args(&["--format=short"]), gpu).unwrap(), "GPU: Mali Mali-G78\n");
+        assert_eq!(
+            run(args(&["--format", "extended"]), gpu).unwrap(),
+            "vendor: Mali\n\
+             model: Mali-G78\n\
+             architecture: Valhall\n\
+             gpu_id: 0x90860000\n\
+             cores: 20\n\
+             features: FP16, ASTC\n"
+        );
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let usage = run(args(&["--format", "long"]), gpu).unwrap_err();
+        assert_eq!((usage.exit_code(), usage.to_string().as_str()), (2, "unknown format 'long'"));
+        assert_eq!(run(args(&["--format"]), gpu).unwrap_err().exit_code(), 2);
+        assert_eq!(run(args(&["-v"]), gpu).unwrap_err().exit_code(), 2);
+
+        let no_gpu = run(args(&["--format=extended"]), || Err(GpuInfoError::NoDevice)).unwrap_err();
+        assert_eq!((no_gpu.exit_code(), no_gpu.to_string().as_str()), (1, "No GPU device found"));
     }
 }
@@ -1,7 +1,11 @@
 //! Command-line interface for iodisco
 
-use clap::{Parser, Subcommand, ValueEnum};
-use iodisco;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
+use iodisco::discovery::{DiscoveryConfig, DiscoveryOptions, DiscoveryResult, DiscoverySnapshot, Interpretation, IoctlDiscovery, IoctlResult, JsonOutput};
+use iodisco::profiles::{IoctlDefinition, IoctlProfile};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::time::Instant;
 
 #[derive(Parser)]
 #[command(name = "iodisco")]
@@ -21,7 +25,7 @@ enum Commands {
     /// Get GPU information (quick profile matching)
     Info {
         /// Specific device path (auto-detect if not specified)
-        #[arg(short, long)]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
         device: Option<String>,
 
         /// Output format
@@ -32,7 +36,7 @@ enum Commands {
     /// Discover IOCTLs on unknown GPUs
     Discover {
         /// Specific device path
-        #[arg(short, long)]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
         device: Option<String>,
 
         /// Export results to JSON file
@@ -46,30 +50,139 @@ enum Commands {
         /// Skip detailed analysis
         #[arg(long)]
         skip_details: bool,
+
+        /// Only plan the scan, do not issue any IOCTL calls
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Append every IOCTL result to this file while scanning
+        #[arg(long)]
+        stream_output: Option<String>,
+
+        /// Format of --stream-output: one JSON object per result as it is found, or one array at the end
+        #[arg(long, value_enum, default_value_t = StreamFormat::Ndjson)]
+        stream_format: StreamFormat,
     },
 
     /// Generate profile template from discovery results
     GenerateProfile {
         /// Input JSON file from discovery
-        #[arg(short, long)]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
         from: String,
 
         /// Output profile file
         #[arg(short, long, default_value = "new_profile.json")]
         output: String,
 
+        /// GPU vendor name
+        #[arg(long)]
+        vendor: Option<String>,
+
+        /// GPU model name
+        #[arg(long)]
+        model: Option<String>,
+
         /// Device model name
         #[arg(long)]
         device_model: Option<String>,
+
+        /// Never prompt, use defaults for missing values
+        #[arg(long)]
+        non_interactive: bool,
+    },
+
+    /// List, validate, export or import IOCTL profiles
+    Profiles {
+        /// Print all loaded profiles
+        #[arg(long)]
+        list: bool,
+
+        /// Validate all loaded profiles
+        #[arg(long)]
+        validate: bool,
+
+        /// Write each profile as a separate JSON file into this directory
+        #[arg(long, value_name = "DIR")]
+        export: Option<String>,
+
+        /// Load a custom profile file and check it against the loaded profiles
+        #[arg(long, value_name = "FILE")]
+        import: Option<String>,
+
+        /// Check the sha256sum of a profile file, or print its checksum if it has none
+        #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        verify: Option<String>,
     },
 
     /// List available GPU devices
     Devices,
 
+    /// Measure IOCTL latency and throughput on a device
+    Bench {
+        /// Specific device path (auto-detect if not specified)
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        device: Option<String>,
+
+        /// IOCTL command number (hex, e.g. 0xC0048000)
+        #[arg(short, long, value_parser = parse_hex_u32)]
+        cmd: u32,
+
+        /// Number of calls to make
+        #[arg(short, long, default_value_t = 1000)]
+        iterations: u32,
+    },
+
+    /// Compare two saved GPU info JSON files, e.g. before and after an OTA update
+    Compare {
+        /// GPU info JSON from before the change
+        #[arg(long)]
+        before: String,
+
+        /// GPU info JSON from after the change
+        #[arg(long)]
+        after: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = CompareFormat::Text)]
+        format: CompareFormat,
+    },
+
+    /// Print a shell completion script to stdout
+    #[command(long_about = "Print a shell completion script to stdout\n\n\
+        Installation:\n  \
+        bash:       iodisco shell-complete bash > /etc/bash_completion.d/iodisco\n  \
+        zsh:        iodisco shell-complete zsh > \"${fpath[1]}/_iodisco\"\n  \
+        fish:       iodisco shell-complete fish > ~/.config/fish/completions/iodisco.fish\n  \
+        powershell: iodisco shell-complete powershell >> $PROFILE")]
+    ShellComplete {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
     /// Show version information
     Version,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl From<Shell> for clap_complete::Shell {
+    fn from(shell: Shell) -> Self {
+        match shell {
+            Shell::Bash => Self::Bash,
+            Shell::Zsh => Self::Zsh,
+            Shell::Fish => Self::Fish,
+            Shell::Powershell => Self::PowerShell,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
 enum Verbosity {
     Minimal,
@@ -78,10 +191,34 @@ enum Verbosity {
     Debug,
 }
 
+impl From<Verbosity> for iodisco::discovery::Verbosity {
+    fn from(verbosity: Verbosity) -> Self {
+        match verbosity {
+            Verbosity::Minimal => iodisco::discovery::Verbosity::Minimal,
+            Verbosity::Normal => iodisco::discovery::Verbosity::Normal,
+            Verbosity::Detailed => iodisco::discovery::Verbosity::Detailed,
+            Verbosity::Debug => iodisco::discovery::Verbosity::Debug,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
 enum OutputFormat {
     Text,
     Json,
+    Sysfs,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum StreamFormat {
+    Ndjson,
+    Json,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum CompareFormat {
+    Text,
+    Json,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -95,6 +232,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Ok(info) => match format {
                     OutputFormat::Text => print_gpu_info_text(&info),
                     OutputFormat::Json => print_gpu_info_json(&info)?,
+                    OutputFormat::Sysfs => print!("{}", info.to_sysfs_format()),
                 },
                 Err(e) => {
                     eprintln!("Error: {}", e);
@@ -105,11 +243,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        Commands::Discover { device, json_output, max_results, skip_details } => {
+        Commands::Discover { device, json_output, max_results, skip_details, dry_run, stream_output, stream_format } => {
             println!("🔍 Starting IOCTL discovery...");
 
-            // TODO: Pass options to discovery
-            let result = iodisco::discovery::scan_device(device.as_deref())?;
+            let config = DiscoveryConfig {
+                verbosity: cli.verbosity.into(),
+                max_results: *max_results,
+                skip_details: *skip_details,
+                dry_run: *dry_run,
+                ..Default::default()
+            };
+            let result = match stream_output {
+                Some(path) if *stream_format == StreamFormat::Ndjson => {
+                    scan_device_streaming(device.as_deref(), config, path)?
+                }
+                _ => iodisco::discovery::scan_device(device.as_deref(), config, None)?,
+            };
+
+            if let (Some(path), StreamFormat::Json) = (stream_output, stream_format) {
+                serde_json::to_writer_pretty(File::create(path)?, result.results())?;
+            }
+
+            if *dry_run {
+                println!("📝 Dry run: {} IOCTL calls would be issued", result.get_call_count());
+            }
 
             result.print_results();
 
@@ -121,24 +278,146 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("\n💡 Consider submitting your results to improve iodisco!");
         }
 
-        Commands::GenerateProfile { from, output, device_model } => {
+        Commands::GenerateProfile { from, output, vendor, model, device_model, non_interactive } => {
             println!("📝 Generating profile template...");
-            // TODO: Implement profile generation from JSON
-            println!("Feature coming soon!");
+
+            let export: JsonOutput = serde_json::from_reader(File::open(from)?)?;
+
+            let vendor = resolve_field("Vendor", vendor, "Unknown", *non_interactive)?;
+            let model = resolve_field("Model", model, "Unknown Model", *non_interactive)?;
+            let device_model = resolve_field("Device model", device_model, "Unknown Device", *non_interactive)?;
+
+            let profile = build_profile(&export, vendor, model, device_model);
+            if profile.detection_ioctls.is_empty() {
+                eprintln!("⚠️  No working IOCTLs found in {}", from);
+            }
+
+            serde_json::to_writer_pretty(File::create(output)?, &profile)?;
+            println!("✅ Profile written to: {} ({} detection IOCTLs)", output, profile.detection_ioctls.len());
+        }
+
+        Commands::Profiles { list, validate, export, import, verify } => {
+            let profiles = load_all_profiles();
+            let nothing_selected = !*validate && export.is_none() && import.is_none() && verify.is_none();
+
+            if *list || nothing_selected {
+                println!("📚 Loaded {} profile(s):", profiles.len());
+                for profile in &profiles {
+                    println!("  • {} {} (v{}, {} detection IOCTLs){}",
+                            profile.vendor, profile.model, profile.version,
+                            profile.detection_ioctls.len(),
+                            if profile.deprecated { " [deprecated]" } else { "" });
+                }
+            }
+
+            if *validate {
+                let mut failures = 0;
+                for profile in &profiles {
+                    match profile.validate() {
+                        Ok(()) => println!("  ✅ {} {}", profile.vendor, profile.model),
+                        Err(e) => {
+                            failures += 1;
+                            println!("  ❌ {} {}: {}", profile.vendor, profile.model, e);
+                        }
+                    }
+                }
+                println!("{} of {} profile(s) valid", profiles.len() - failures, profiles.len());
+            }
+
+            if let Some(dir) = export {
+                std::fs::create_dir_all(dir)?;
+                for profile in &profiles {
+                    let path = std::path::Path::new(dir).join(profile_file_name(profile));
+                    serde_json::to_writer_pretty(File::create(&path)?, profile)?;
+                    println!("  📝 {}", path.display());
+                }
+                println!("✅ Exported {} profile(s) to: {}", profiles.len(), dir);
+            }
+
+            if let Some(file) = verify {
+                match IoctlProfile::from_json_file(file) {
+                    Ok(profile) if profile.sha256sum.is_some() => {
+                        println!("✅ {} {}: checksum matches", profile.vendor, profile.model);
+                    }
+                    Ok(profile) => {
+                        println!("⚠️  {} has no sha256sum, checksum is {}", file, profile.checksum());
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {}: {}", file, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            if let Some(file) = import {
+                let profile = IoctlProfile::from_json_file(file)?;
+                profile.validate()?;
+
+                let conflicts = find_profile_conflicts(&profile, &profiles);
+                if conflicts.is_empty() {
+                    println!("✅ {} {} merges without conflicts", profile.vendor, profile.model);
+                } else {
+                    println!("❌ {} {} conflicts with loaded profiles:", profile.vendor, profile.model);
+                    for conflict in &conflicts {
+                        println!("  • {}", conflict);
+                    }
+                }
+            }
         }
 
         Commands::Devices => {
-            let devices = iodisco::scan_devices();
+            let devices = iodisco::discovery::find_gpu_device_info();
             if devices.is_empty() {
                 println!("❌ No GPU devices found.");
             } else {
                 println!("📱 Found {} GPU device(s):", devices.len());
                 for device in devices {
-                    println!("  • {}", device);
+                    println!("  • {}", device.path);
+                    for related in &device.related_devices {
+                        println!("      ↳ {}", related);
+                    }
+                }
+            }
+
+            let sockets = iodisco::discovery::find_gpu_ipc_sockets();
+            if !sockets.is_empty() {
+                println!("🔌 GPU IPC sockets (informational):");
+                for socket in sockets {
+                    println!("  • {}", socket);
                 }
             }
         }
 
+        Commands::Bench { device, cmd, iterations } => {
+            let device = match device {
+                Some(path) => path.clone(),
+                None => iodisco::discovery::find_gpu_device().ok_or("No GPU device found")?,
+            };
+            println!("⏱️  Benchmarking 0x{:08x} on {} ({} calls)...", cmd, device, iterations);
+
+            let result = iodisco::discovery::BenchmarkRunner::run(&device, *cmd, *iterations)?;
+            println!("Calls/second: {:.0}", result.calls_per_second);
+            println!("Latency min:  {} µs", result.min_latency_us);
+            println!("Latency p50:  {} µs", result.p50_latency_us);
+            println!("Latency p99:  {} µs", result.p99_latency_us);
+            println!("Latency max:  {} µs", result.max_latency_us);
+        }
+
+        Commands::Compare { before, after, format } => {
+            let before_info = iodisco::GpuInfo::from_json_file(before)?;
+            let after_info = iodisco::GpuInfo::from_json_file(after)?;
+            let diff = before_info.compare_to(&after_info);
+
+            match format {
+                CompareFormat::Text => print_gpu_info_diff_text(&diff),
+                CompareFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+            }
+        }
+
+        Commands::ShellComplete { shell } => {
+            clap_complete::generate(clap_complete::Shell::from(*shell), &mut Cli::command(), "iodisco", &mut io::stdout());
+        }
+
         Commands::Version => {
             println!("iodisco v{}", iodisco::version());
             println!("Library for GPU IOCTL discovery");
@@ -148,6 +427,89 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Scan `device` like `scan_device`, appending each found IOCTL to `path` as one JSON line
+///
+/// The types come from `scan_types_for_device`, so DRI and KGSL nodes get the
+/// same types as a regular scan. Results are written as they arrive, so an
+/// interrupted scan still leaves everything found so far in the file.
+fn scan_device_streaming(device: Option<&str>, config: DiscoveryConfig, path: &str) -> Result<DiscoveryResult, Box<dyn std::error::Error>> {
+    let device = match device {
+        Some(device) => device.to_string(),
+        None => iodisco::discovery::find_gpu_device().ok_or("No GPU device found")?,
+    };
+    let options = DiscoveryOptions::from(config);
+    let started = Instant::now();
+    let mut discovery = IoctlDiscovery::open(&device, options.clone())?;
+    let mut stream = OpenOptions::new().create(true).append(true).open(path)?;
+
+    let (types, _) = iodisco::discovery::scan_types_for_device(&discovery, &device);
+    let mut results = Vec::new();
+    for &ty in types {
+        for result in discovery.scan_type_stream(ty) {
+            match result {
+                Ok(result) => stream_result(&mut stream, result, &mut results)?,
+                Err(e) => {
+                    eprintln!("Warning: scanning type 0x{:02x} on {}: {}", ty, device, e);
+                    break;
+                }
+            }
+        }
+    }
+
+    let snapshot = DiscoverySnapshot {
+        device,
+        options,
+        results,
+        call_count: discovery.get_call_count(),
+        elapsed: started.elapsed(),
+    };
+    Ok(snapshot.to_discovery_result())
+}
+
+/// Write `result` to `out` as one JSON line and keep it, unless the IOCTL doesn't exist
+fn stream_result(out: &mut impl Write, result: IoctlResult, kept: &mut Vec<IoctlResult>) -> io::Result<()> {
+    if matches!(result.interpretation, Interpretation::NotExist) {
+        return Ok(());
+    }
+    serde_json::to_writer(&mut *out, &result)?;
+    writeln!(out)?;
+    kept.push(result);
+    Ok(())
+}
+
+/// Parse a command number given as hex (with or without `0x`)
+fn parse_hex_u32(value: &str) -> Result<u32, String> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    u32::from_str_radix(digits, 16).map_err(|e| format!("invalid hex number '{}': {}", value, e))
+}
+
+fn print_gpu_info_diff_text(diff: &iodisco::GpuInfoDiff) {
+    if diff.is_empty() {
+        println!("✅ No differences");
+        return;
+    }
+
+    println!("🔀 GPU Information Changes:");
+    println!("===========================");
+    let mut fields: Vec<_> = diff.changed_fields.iter().collect();
+    fields.sort_by_key(|(field, _)| field.as_str());
+    for (field, (old, new)) in fields {
+        let marker = match diff.assessments.get(field) {
+            Some(iodisco::FieldChange::Regression) => " ⚠️  regression",
+            Some(iodisco::FieldChange::Improvement) => " ✅ improvement",
+            _ => "",
+        };
+        println!("{}: {} → {}{}", field, old, new, marker);
+    }
+
+    for feature in &diff.added_features {
+        println!("+ feature {}", feature);
+    }
+    for feature in &diff.removed_features {
+        println!("- feature {} ⚠️  regression", feature);
+    }
+}
+
 fn print_gpu_info_text(info: &iodisco::GpuInfo) {
     println!("📊 GPU Information:");
     println!("===================");
@@ -181,4 +543,131 @@ fn print_gpu_info_json(info: &iodisco::GpuInfo) -> Result<(), Box<dyn std::error
     let json = serde_json::to_string_pretty(info)?;
     println!("{}", json);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Use the flag value if given, otherwise prompt (or fall back to the default)
+fn resolve_field(label: &str, value: &Option<String>, default: &str, non_interactive: bool) -> io::Result<String> {
+    if let Some(value) = value {
+        return Ok(value.clone());
+    }
+    if non_interactive {
+        return Ok(default.to_string());
+    }
+
+    print!("{} [{}]: ", label, default);
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    let line = line.trim();
+
+    Ok(if line.is_empty() { default.to_string() } else { line.to_string() })
+}
+
+/// Build an IoctlProfile from the top-5 working IOCTLs of a discovery export
+fn build_profile(export: &JsonOutput, vendor: String, model: String, device_model: String) -> IoctlProfile {
+    let mut working: Vec<_> = export.results.iter()
+        .filter(|r| !r.is_potentially_dangerous)
+        .filter(|r| matches!(r.interpretation, Interpretation::Success | Interpretation::Exists))
+        .collect();
+
+    // Prefer successful calls over merely existing ones
+    working.sort_by_key(|r| !r.is_successful());
+
+    let detection_ioctls = working.iter()
+        .take(5)
+        .map(|r| IoctlDefinition {
+            name: format!("unknown_{:02x}_{:02x}", r.ty, r.nr),
+            cmd: r.cmd,
+            buffer_size: r.discovered_size.unwrap_or(r.size),
+            parser: "parse_generic".to_string(),
+            params: serde_json::json!({}),
+        })
+        .collect();
+
+    IoctlProfile {
+        vendor,
+        model,
+        version: "1.0.0".to_string(),
+        min_kernel_version: None,
+        compatible_kernels: Vec::new(),
+        deprecated: false,
+        superseded_by: None,
+        parent_profile: None,
+        sample_confidence: None,
+        sha256sum: None,
+        detection_ioctls,
+        version_ioctl: None,
+        gpu_info_ioctl: None,
+        features_ioctl: None,
+        metadata: serde_json::json!({
+            "device": device_model,
+            "confirmed_by": "iodisco_discovery",
+            "generated_at": export.metadata.timestamp,
+            "iodisco_version": export.metadata.iodisco_version,
+            "total_results": export.statistics.total,
+            "successful_results": export.statistics.successful,
+        }),
+    }
+}
+
+/// Load all embedded profiles of every vendor
+fn load_all_profiles() -> Vec<IoctlProfile> {
+    let mut profiles = iodisco::load_mali_profiles();
+    profiles.extend(iodisco::load_adreno_profiles());
+    profiles
+}
+
+/// File name used when exporting a profile, e.g. "mali_mali_g720.json"
+fn profile_file_name(profile: &IoctlProfile) -> String {
+    let stem: String = format!("{}_{}", profile.vendor, profile.model)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    format!("{}.json", stem)
+}
+
+/// Describe everything that would make a new profile ambiguous next to the loaded ones
+fn find_profile_conflicts(profile: &IoctlProfile, loaded: &[IoctlProfile]) -> Vec<String> {
+    let detection_cmds = |p: &IoctlProfile| {
+        let mut cmds: Vec<u32> = p.detection_ioctls.iter().map(|d| d.cmd).collect();
+        cmds.sort_unstable();
+        cmds
+    };
+    let new_cmds = detection_cmds(profile);
+
+    let mut conflicts = Vec::new();
+    for existing in loaded {
+        if existing.vendor == profile.vendor && existing.model == profile.model {
+            conflicts.push(format!("same vendor/model as loaded profile {} {}", existing.vendor, existing.model));
+        } else if detection_cmds(existing) == new_cmds {
+            conflicts.push(format!("identical detection IOCTLs as {} {}", existing.vendor, existing.model));
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_result_writes_found_ioctls() {
+        // _IOWR(0x80, nr, 4 bytes)
+        let result = |nr: u8, interpretation| {
+            IoctlResult::new(0xC0048000 | nr as u32, 3, 0x80, nr, 4, (-1, 22), None, interpretation, None, false)
+        };
+        let mut out = Vec::new();
+        let mut kept = Vec::new();
+        stream_result(&mut out, result(0x00, Interpretation::Exists), &mut kept).unwrap();
+        stream_result(&mut out, result(0x01, Interpretation::NotExist), &mut kept).unwrap();
+        stream_result(&mut out, result(0x03, Interpretation::Exists), &mut kept).unwrap();
+
+        let lines: Vec<IoctlResult> = String::from_utf8(out).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.iter().map(|r| r.cmd).collect::<Vec<_>>(), vec![0xC0048000, 0xC0048003]);
+        assert_eq!(kept.len(), 2);
+    }
+}
@@ -2,8 +2,13 @@
 
 use crate::error::DiscoveryError;
 use serde::{Serialize, Deserialize};
-use std::io;
+use std::collections::HashMap;
+use std::time::Duration;
 
+/// Maximum age of a cached GPU info file before it is refreshed
+const GPU_INFO_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Alias for API compatibility
 pub type GpuInfoError = DiscoveryError;
 
 /// Structured GPU information
@@ -47,6 +52,32 @@ pub struct GpuInfo {
     pub texels_per_core: Option<u16>,
     /// Pixels per cycle per core
     pub pixels_per_core: Option<u16>,
+    /// Responses of IOCTLs without a known parser, by command; base64 in JSON
+    #[serde(default, skip_serializing_if = "HashMap::is_empty", with = "base64_buffers")]
+    pub raw_ioctl_data: HashMap<u32, Vec<u8>>,
+}
+
+/// Serde helper writing `raw_ioctl_data` buffers as base64 strings
+mod base64_buffers {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::collections::{BTreeMap, HashMap};
+
+    pub fn serialize<S: Serializer>(data: &HashMap<u32, Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<u32, String> = data.iter().map(|(&cmd, buffer)| (cmd, STANDARD.encode(buffer))).collect();
+        serializer.collect_map(sorted)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<u32, Vec<u8>>, D::Error> {
+        HashMap::<u32, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(cmd, text)| {
+                STANDARD.decode(&text)
+                    .map(|buffer| (cmd, buffer))
+                    .map_err(|e| D::Error::custom(format!("invalid base64 data for IOCTL 0x{:08x}: {}", cmd, e)))
+            })
+            .collect()
+    }
 }
 
 /// Information about a detected IOCTL
@@ -66,14 +97,750 @@ pub struct DetectedIoctl {
     pub return_value: Option<i32>,
 }
 
+/// Theoretical throughput of a GPU, normalized to a 1 GHz clock
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpuPerformanceSpec {
+    /// FP32 GFLOPS per GHz of GPU clock (one FMA counts as two FLOPs)
+    pub fp32_gflops_per_ghz: f64,
+    /// FP16 GFLOPS per GHz of GPU clock (0 if unknown)
+    pub fp16_gflops_per_ghz: f64,
+    /// Texels per cycle for the whole GPU (0 if unknown)
+    pub texels_per_cycle: u32,
+    /// Pixels per cycle for the whole GPU (0 if unknown)
+    pub pixels_per_cycle: u32,
+}
+
+/// PCI vendor ID used by Vulkan drivers for ARM Mali GPUs
+pub const VULKAN_VENDOR_ID_ARM: u32 = 0x13B5;
+
+/// PCI vendor ID used by Vulkan drivers for Qualcomm Adreno GPUs
+pub const VULKAN_VENDOR_ID_QUALCOMM: u32 = 0x5143;
+
+/// Subset of `VkPhysicalDeviceType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VulkanDeviceType {
+    /// `VK_PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU`
+    IntegratedGpu,
+    /// `VK_PHYSICAL_DEVICE_TYPE_DISCRETE_GPU`
+    DiscreteGpu,
+}
+
+/// Values to cross-reference against `VkPhysicalDeviceProperties`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VulkanDeviceHint {
+    /// Expected `deviceName`
+    pub device_name: String,
+    /// Expected `vendorID` (PCI vendor ID)
+    pub vendor_id: u32,
+    /// Expected `deviceID`
+    pub device_id: u32,
+    /// Expected `deviceType`
+    pub device_type: VulkanDeviceType,
+}
+
+/// Shader core mask of a Mali GPU, see [`GpuInfo::core_mask_parsed`]
+///
+/// Binned or heterogeneous configurations can leave gaps, e.g. `0x150055`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaliCoreMask(pub u32);
+
+impl MaliCoreMask {
+    /// Number of enabled cores
+    pub fn active_cores(&self) -> u8 {
+        self.0.count_ones() as u8
+    }
+
+    /// Index of the highest enabled core, 0 for an empty mask
+    pub fn max_core_index(&self) -> u8 {
+        (31 - self.0.max(1).leading_zeros()) as u8
+    }
+
+    /// Indices of all enabled cores, lowest first
+    pub fn core_indices(&self) -> Vec<u8> {
+        (0..32u8).filter(|&i| self.0 & (1 << i) != 0).collect()
+    }
+
+    /// True when the enabled cores have no gaps between them
+    pub fn is_contiguous(&self) -> bool {
+        let shifted = self.0 >> self.0.trailing_zeros().min(31);
+        shifted & shifted.wrapping_add(1) == 0
+    }
+}
+
+/// Whether a changed field got better or worse, see [`GpuInfo::compare_to`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldChange {
+    /// A capacity or throughput value went up
+    Improvement,
+    /// A capacity or throughput value went down
+    Regression,
+    /// The field has no better or worse direction
+    Neutral,
+}
+
+/// Differences between two `GpuInfo` snapshots, e.g. before and after an OTA update
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GpuInfoDiff {
+    /// Changed fields as (old, new) values, `-` standing for unset
+    pub changed_fields: HashMap<String, (String, String)>,
+    /// Assessment of every entry in `changed_fields`
+    pub assessments: HashMap<String, FieldChange>,
+    /// Features only present in the newer snapshot
+    pub added_features: Vec<String>,
+    /// Features only present in the older snapshot
+    pub removed_features: Vec<String>,
+}
+
+impl GpuInfoDiff {
+    /// True when both snapshots describe the same GPU state
+    pub fn is_empty(&self) -> bool {
+        self.changed_fields.is_empty() && self.added_features.is_empty() && self.removed_features.is_empty()
+    }
+
+    /// Changed field names with a `Regression` assessment, sorted
+    pub fn regressions(&self) -> Vec<&str> {
+        self.fields_with(FieldChange::Regression)
+    }
+
+    /// Changed field names with an `Improvement` assessment, sorted
+    pub fn improvements(&self) -> Vec<&str> {
+        self.fields_with(FieldChange::Improvement)
+    }
+
+    fn fields_with(&self, change: FieldChange) -> Vec<&str> {
+        let mut fields: Vec<&str> = self.assessments.iter()
+            .filter(|(_, c)| **c == change)
+            .map(|(f, _)| f.as_str())
+            .collect();
+        fields.sort_unstable();
+        fields
+    }
+}
+
+/// Double-quote `value` for POSIX shells
+pub(crate) fn shell_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Key prefix used for Android system properties
+const ANDROID_PROP_PREFIX: &str = "ro.hardware.gpu.";
+
+/// Published location of the schema returned by [`GpuInfo::json_schema`]
+pub const GPU_INFO_SCHEMA_URL: &str =
+    "https://raw.githubusercontent.com/devrimdevelopment/iodisco/main/gpu_info.schema.json";
+
+impl GpuInfo {
+    /// Load GPU information from a JSON file
+    pub fn from_json_file(path: &str) -> Result<Self, DiscoveryError> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| DiscoveryError::Discovery(format!("Failed to read {}: {}", path, e)))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Save GPU information to a JSON file
+    ///
+    /// The file references [`GPU_INFO_SCHEMA_URL`] through a `$schema` key.
+    pub fn save_to_json_file(&self, path: &str) -> Result<(), DiscoveryError> {
+        let mut value = serde_json::to_value(self)?;
+        if let serde_json::Value::Object(map) = &mut value {
+            map.insert("$schema".to_string(), GPU_INFO_SCHEMA_URL.into());
+        }
+        let data = serde_json::to_string_pretty(&value)?;
+        std::fs::write(path, data)
+            .map_err(|e| DiscoveryError::Discovery(format!("Failed to write {}: {}", path, e)))
+    }
+
+    /// JSON Schema (draft 7) describing the serialized form of `GpuInfo`
+    pub fn json_schema() -> serde_json::Value {
+        let nullable = |ty: &str, max: Option<u64>| match max {
+            Some(max) => serde_json::json!({ "type": [ty, "null"], "minimum": 0, "maximum": max }),
+            None => serde_json::json!({ "type": [ty, "null"] }),
+        };
+        let hex_or_integer = serde_json::json!({
+            "oneOf": [
+                { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                { "type": "string", "pattern": "^(0[xX][0-9a-fA-F]+|[0-9]+)$" },
+            ]
+        });
+
+        serde_json::json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "$id": GPU_INFO_SCHEMA_URL,
+            "title": "GpuInfo",
+            "description": "GPU information exported by iodisco",
+            "type": "object",
+            "required": ["vendor", "model", "features", "detected_ioctls", "metadata"],
+            "additionalProperties": false,
+            "properties": {
+                "$schema": { "type": "string", "format": "uri" },
+                "vendor": { "type": "string", "description": "GPU vendor (Mali, Adreno, etc.)" },
+                "model": { "type": "string", "description": "GPU model name" },
+                "architecture": nullable("string", None),
+                "driver_version": nullable("string", None),
+                "gpu_id": nullable("integer", Some(u32::MAX as u64)),
+                "cores": nullable("integer", Some(u8::MAX as u64)),
+                "features": { "type": "array", "items": { "type": "string" } },
+                "detected_ioctls": { "type": "array", "items": { "$ref": "#/definitions/DetectedIoctl" } },
+                "metadata": { "$ref": "#/definitions/Metadata" },
+                "arch_version": nullable("string", None),
+                "core_mask": nullable("integer", Some(u32::MAX as u64)),
+                "l2_cache_count": nullable("integer", Some(u8::MAX as u64)),
+                "l2_cache_size": nullable("integer", Some(u32::MAX as u64)),
+                "bus_width": nullable("integer", Some(u16::MAX as u64)),
+                "engines_per_core": nullable("integer", Some(u8::MAX as u64)),
+                "fp32_fmas_per_core": nullable("integer", Some(u16::MAX as u64)),
+                "fp16_fmas_per_core": nullable("integer", Some(u16::MAX as u64)),
+                "texels_per_core": nullable("integer", Some(u16::MAX as u64)),
+                "pixels_per_core": nullable("integer", Some(u16::MAX as u64)),
+                "raw_ioctl_data": {
+                    "description": "Responses of IOCTLs without a known parser, keyed by command",
+                    "type": "object",
+                    "additionalProperties": { "type": "string", "contentEncoding": "base64" },
+                },
+            },
+            "definitions": {
+                "DetectedIoctl": {
+                    "type": "object",
+                    "required": ["name", "cmd", "works", "returns_data"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "cmd": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                        "works": { "type": "boolean" },
+                        "returns_data": { "type": "boolean" },
+                        "errno": { "type": ["integer", "null"] },
+                        "return_value": { "type": ["integer", "null"] },
+                    },
+                },
+                "Metadata": {
+                    "description": "Free-form profile metadata; well-known keys are typed",
+                    "type": "object",
+                    "additionalProperties": true,
+                    "properties": {
+                        "architecture": { "type": "string" },
+                        "model_number": hex_or_integer,
+                        "core_count": { "type": "integer", "minimum": 0, "maximum": u8::MAX },
+                        "core_mask": hex_or_integer,
+                        "arch_version": { "type": "string" },
+                        "tier": { "enum": ["HighPerformance", "Mainstream", "LowPower", "UltraLowPower"] },
+                        "kernel_version": { "type": "string" },
+                        "confirmed_kernel_version": { "type": "string" },
+                        "match_confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                        "total_fp32_fmas": { "type": "integer", "minimum": 0 },
+                        "total_fp16_fmas": { "type": "integer", "minimum": 0 },
+                        "total_texels_per_cycle": { "type": "integer", "minimum": 0 },
+                        "total_pixels_per_cycle": { "type": "integer", "minimum": 0 },
+                    },
+                },
+            },
+        })
+    }
+
+    /// Convert to Android system property pairs (`ro.hardware.gpu.*`)
+    ///
+    /// Only fields that are set are included. Numeric IDs and masks are
+    /// written as `0x`-prefixed hex.
+    pub fn to_android_prop_format(&self) -> HashMap<String, String> {
+        let mut props = HashMap::new();
+        let mut insert = |key: &str, value: String| {
+            props.insert(format!("{}{}", ANDROID_PROP_PREFIX, key), value);
+        };
+
+        insert("vendor", self.vendor.clone());
+        insert("model", self.model.clone());
+        if let Some(arch) = &self.architecture {
+            insert("arch", arch.clone());
+        }
+        if let Some(arch_version) = &self.arch_version {
+            insert("arch_version", arch_version.clone());
+        }
+        if let Some(driver_version) = &self.driver_version {
+            insert("driver_version", driver_version.clone());
+        }
+        if let Some(gpu_id) = self.gpu_id {
+            insert("id", format!("0x{:08x}", gpu_id));
+        }
+        if let Some(cores) = self.cores {
+            insert("cores", cores.to_string());
+        }
+        if let Some(core_mask) = self.core_mask {
+            insert("core_mask", format!("0x{:x}", core_mask));
+        }
+        if let Some(l2_cache_count) = self.l2_cache_count {
+            insert("l2_cache_count", l2_cache_count.to_string());
+        }
+        if let Some(l2_cache_size) = self.l2_cache_size {
+            insert("l2_cache_size", l2_cache_size.to_string());
+        }
+        if let Some(bus_width) = self.bus_width {
+            insert("bus_width", bus_width.to_string());
+        }
+        if !self.features.is_empty() {
+            insert("features", self.features.join(","));
+        }
+
+        props
+    }
+
+    /// Format as `IODISCO_*` shell variable assignments, one per line
+    ///
+    /// Meant for `eval $(iodisco info --format=sysfs)`. Strings are double
+    /// quoted with `"`, `\`, `$` and `` ` `` escaped; unset fields are omitted.
+    pub fn to_sysfs_format(&self) -> String {
+        let mut out = String::new();
+        let mut quoted = |key: &str, value: &str| {
+            out.push_str(&format!("IODISCO_{}={}\n", key, shell_quote(value)));
+        };
+
+        quoted("VENDOR", &self.vendor);
+        quoted("MODEL", &self.model);
+        if let Some(arch) = &self.architecture {
+            quoted("ARCH", arch);
+        }
+        if let Some(arch_version) = &self.arch_version {
+            quoted("ARCH_VERSION", arch_version);
+        }
+        if let Some(driver_version) = &self.driver_version {
+            quoted("DRIVER_VERSION", driver_version);
+        }
+        if !self.features.is_empty() {
+            quoted("FEATURES", &self.features.join(","));
+        }
+
+        let numbers = [
+            ("GPU_ID", self.gpu_id.map(|v| format!("0x{:x}", v))),
+            ("CORES", self.cores.map(|v| v.to_string())),
+            ("CORE_MASK", self.core_mask.map(|v| format!("0x{:x}", v))),
+            ("L2_CACHE_COUNT", self.l2_cache_count.map(|v| v.to_string())),
+            ("L2_CACHE_SIZE", self.l2_cache_size.map(|v| v.to_string())),
+            ("BUS_WIDTH", self.bus_width.map(|v| v.to_string())),
+        ];
+        for (key, value) in numbers {
+            if let Some(value) = value {
+                out.push_str(&format!("IODISCO_{}={}\n", key, value));
+            }
+        }
+        out
+    }
+
+    /// Build GPU information from Android system property pairs
+    ///
+    /// `ro.hardware.gpu.vendor` and `ro.hardware.gpu.model` are required.
+    pub fn from_android_props(props: &HashMap<String, String>) -> Result<GpuInfo, DiscoveryError> {
+        let get = |key: &str| props.get(&format!("{}{}", ANDROID_PROP_PREFIX, key));
+        let required = |key: &str| {
+            get(key).cloned().ok_or_else(|| DiscoveryError::InvalidParameter(
+                format!("missing property {}{}", ANDROID_PROP_PREFIX, key)
+            ))
+        };
+        // Decimal or 0x-prefixed hex, and it has to fit the field
+        fn number<T: TryFrom<u64>>(props: &HashMap<String, String>, key: &str) -> Result<Option<T>, DiscoveryError> {
+            let Some(value) = props.get(&format!("{}{}", ANDROID_PROP_PREFIX, key)) else {
+                return Ok(None);
+            };
+            let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => value.parse(),
+            };
+            let parsed = parsed.map_err(|_| DiscoveryError::InvalidParameter(
+                format!("invalid number for {}{}: {}", ANDROID_PROP_PREFIX, key, value)
+            ))?;
+            T::try_from(parsed).map(Some).map_err(|_| DiscoveryError::InvalidParameter(
+                format!("out of range value for {}{}: {}", ANDROID_PROP_PREFIX, key, value)
+            ))
+        }
+
+        Ok(GpuInfo {
+            vendor: required("vendor")?,
+            model: required("model")?,
+            architecture: get("arch").cloned(),
+            driver_version: get("driver_version").cloned(),
+            gpu_id: number(props, "id")?,
+            cores: number(props, "cores")?,
+            features: get("features")
+                .map(|f| f.split(',').filter(|s| !s.is_empty()).map(|s| s.to_string()).collect())
+                .unwrap_or_default(),
+            detected_ioctls: Vec::new(),
+            metadata: serde_json::json!({}),
+            arch_version: get("arch_version").cloned(),
+            core_mask: number(props, "core_mask")?,
+            l2_cache_count: number(props, "l2_cache_count")?,
+            l2_cache_size: number(props, "l2_cache_size")?,
+            bus_width: number(props, "bus_width")?,
+            engines_per_core: None,
+            fp32_fmas_per_core: None,
+            fp16_fmas_per_core: None,
+            texels_per_core: None,
+            pixels_per_core: None,
+            raw_ioctl_data: HashMap::new(),
+        })
+    }
+
+    /// Theoretical throughput estimate
+    ///
+    /// Uses the `total_*` values in `metadata` when present, otherwise
+    /// multiplies the per-core figures by the core count. Returns `None`
+    /// when no FP32 throughput is known.
+    pub fn performance_spec(&self) -> Option<GpuPerformanceSpec> {
+        let metadata_total = |key: &str| {
+            self.metadata.get(key).and_then(|v| v.as_u64()).map(|v| v as u32)
+        };
+        let per_core_total = |per_core: Option<u16>| {
+            Some(per_core? as u32 * self.cores? as u32)
+        };
+
+        let fp32_fmas = metadata_total("total_fp32_fmas")
+            .or_else(|| per_core_total(self.fp32_fmas_per_core))?;
+        let fp16_fmas = metadata_total("total_fp16_fmas")
+            .or_else(|| per_core_total(self.fp16_fmas_per_core))
+            .unwrap_or(0);
+
+        Some(GpuPerformanceSpec {
+            fp32_gflops_per_ghz: fp32_fmas as f64 * 2.0,
+            fp16_gflops_per_ghz: fp16_fmas as f64 * 2.0,
+            texels_per_cycle: metadata_total("total_texels_per_cycle")
+                .or_else(|| per_core_total(self.texels_per_core))
+                .unwrap_or(0),
+            pixels_per_cycle: metadata_total("total_pixels_per_cycle")
+                .or_else(|| per_core_total(self.pixels_per_core))
+                .unwrap_or(0),
+        })
+    }
+
+    /// Numeric architecture generation
+    ///
+    /// Mali: Midgard = 1, Bifrost = 2, Valhall = 3, Arm 5th Gen = 4.
+    /// Adreno: the series times ten, parsed from the architecture or model
+    /// name (A6xx / Adreno 640 = 60, A7xx = 70). Returns `None` when the
+    /// generation cannot be determined.
+    pub fn architecture_generation(&self) -> Option<u8> {
+        #[allow(unused_mut)]
+        let mut architecture = self.architecture.clone();
+        #[cfg(any(feature = "mali", feature = "adreno"))]
+        if architecture.is_none() {
+            use crate::mappings::{find_mali_model_by_name, identify_mali_gpu};
+            architecture = self.gpu_id
+                .and_then(identify_mali_gpu)
+                .or_else(|| find_mali_model_by_name(&self.model))
+                .map(|m| m.architecture.to_string());
+        }
+
+        match architecture.as_deref() {
+            Some("Midgard") => return Some(1),
+            Some("Bifrost") => return Some(2),
+            Some("Valhall") => return Some(3),
+            Some("Arm 5th Gen") => return Some(4),
+            _ => {}
+        }
+
+        let adreno_series = |name: &str| {
+            let name = name.trim();
+            let digits = name.strip_prefix("Adreno")
+                .map(str::trim_start)
+                .or_else(|| name.strip_prefix('A').or_else(|| name.strip_prefix('a')))?;
+            let series = digits.chars().next()?.to_digit(10)?;
+            (series > 0).then_some(series as u8 * 10)
+        };
+        architecture.as_deref().and_then(adreno_series)
+            .or_else(|| adreno_series(&self.model))
+    }
+
+    /// Compare architecture generations
+    ///
+    /// Returns `None` when either generation is unknown or the vendors differ,
+    /// since Mali and Adreno generation numbers are not comparable.
+    pub fn is_newer_than(&self, other: &GpuInfo) -> Option<bool> {
+        if !self.vendor.eq_ignore_ascii_case(&other.vendor) {
+            return None;
+        }
+        Some(self.architecture_generation()? > other.architecture_generation()?)
+    }
+
+    /// What Vulkan should report for this GPU in `VkPhysicalDeviceProperties`
+    ///
+    /// `device_id` is the raw `gpu_id`. Mali and Adreno GPUs share system
+    /// memory at every performance tier, so they always map to
+    /// `IntegratedGpu`. Returns `None` for other vendors or without a `gpu_id`.
+    pub fn to_vulkan_hint(&self) -> Option<VulkanDeviceHint> {
+        let vendor = self.vendor.to_ascii_lowercase();
+        let vendor_id = if vendor.contains("mali") || vendor.contains("arm") {
+            VULKAN_VENDOR_ID_ARM
+        } else if vendor.contains("qualcomm") || vendor.contains("adreno") {
+            VULKAN_VENDOR_ID_QUALCOMM
+        } else {
+            return None;
+        };
+
+        Some(VulkanDeviceHint {
+            device_name: self.model.clone(),
+            vendor_id,
+            device_id: self.gpu_id?,
+            device_type: VulkanDeviceType::IntegratedGpu,
+        })
+    }
+
+    /// Field-by-field differences from `self` (before) to `other` (after)
+    ///
+    /// Core counts, caches, bus width and per-core throughput count as
+    /// improvements when they grow; identity fields are neutral.
+    pub fn compare_to(&self, other: &GpuInfo) -> GpuInfoDiff {
+        fn opt<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map_or_else(|| "-".to_string(), T::to_string)
+        }
+        fn hex(value: Option<u32>) -> String {
+            value.map_or_else(|| "-".to_string(), |v| format!("0x{:x}", v))
+        }
+
+        // (field, old, new, higher is better)
+        let fields = [
+            ("vendor", self.vendor.clone(), other.vendor.clone(), false),
+            ("model", self.model.clone(), other.model.clone(), false),
+            ("architecture", opt(&self.architecture), opt(&other.architecture), false),
+            ("driver_version", opt(&self.driver_version), opt(&other.driver_version), false),
+            ("gpu_id", hex(self.gpu_id), hex(other.gpu_id), false),
+            ("arch_version", opt(&self.arch_version), opt(&other.arch_version), false),
+            ("core_mask", hex(self.core_mask), hex(other.core_mask), false),
+            ("cores", opt(&self.cores), opt(&other.cores), true),
+            ("l2_cache_count", opt(&self.l2_cache_count), opt(&other.l2_cache_count), true),
+            ("l2_cache_size", opt(&self.l2_cache_size), opt(&other.l2_cache_size), true),
+            ("bus_width", opt(&self.bus_width), opt(&other.bus_width), true),
+            ("engines_per_core", opt(&self.engines_per_core), opt(&other.engines_per_core), true),
+            ("fp32_fmas_per_core", opt(&self.fp32_fmas_per_core), opt(&other.fp32_fmas_per_core), true),
+            ("fp16_fmas_per_core", opt(&self.fp16_fmas_per_core), opt(&other.fp16_fmas_per_core), true),
+            ("texels_per_core", opt(&self.texels_per_core), opt(&other.texels_per_core), true),
+            ("pixels_per_core", opt(&self.pixels_per_core), opt(&other.pixels_per_core), true),
+        ];
+
+        let mut diff = GpuInfoDiff::default();
+        for (field, old, new, higher_is_better) in fields {
+            if old == new {
+                continue;
+            }
+            let change = match (old.parse::<u64>(), new.parse::<u64>()) {
+                (Ok(o), Ok(n)) if higher_is_better && n > o => FieldChange::Improvement,
+                (Ok(_), Ok(_)) if higher_is_better => FieldChange::Regression,
+                _ => FieldChange::Neutral,
+            };
+            diff.assessments.insert(field.to_string(), change);
+            diff.changed_fields.insert(field.to_string(), (old, new));
+        }
+
+        diff.added_features = other.features.iter()
+            .filter(|f| !self.features.contains(f))
+            .cloned()
+            .collect();
+        diff.removed_features = self.features.iter()
+            .filter(|f| !other.features.contains(f))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl GpuInfo {
+    /// Rough power class estimate from architecture, tier and core count
+    ///
+    /// Useful for thermal management when no clock frequency data is
+    /// available. The tier comes from the Mali hardware database, looked up by
+    /// GPU ID or model name. Unknown models fall back to the typical tier of
+    /// their architecture, and to `Balanced` without one.
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    pub fn estimated_power_class(&self) -> PowerClass {
+        use crate::mappings::{find_mali_model_by_name, identify_mali_gpu, GpuTier};
+
+        let model = self.gpu_id
+            .and_then(identify_mali_gpu)
+            .or_else(|| find_mali_model_by_name(&self.model));
+        let architecture = self.architecture.as_deref().or(model.map(|m| m.architecture));
+        let cores = self.cores.or(model.map(|m| m.min_cores)).unwrap_or(0);
+
+        let tier = match (model, architecture) {
+            (Some(model), _) => model.tier.clone(),
+            (None, Some("Midgard")) => GpuTier::LowPower,
+            (None, Some("Bifrost")) => GpuTier::Mainstream,
+            (None, Some("Valhall" | "Arm 5th Gen")) => GpuTier::HighPerformance,
+            (None, _) => return PowerClass::Balanced,
+        };
+
+        match (tier, architecture.unwrap_or_default()) {
+            (GpuTier::UltraLowPower, _) => PowerClass::Ultra,
+            (GpuTier::HighPerformance, "Arm 5th Gen") if cores >= 9 => PowerClass::Flagship,
+            (GpuTier::HighPerformance, _) => PowerClass::Performance,
+            (GpuTier::Mainstream, _) => PowerClass::Balanced,
+            (GpuTier::LowPower, _) => PowerClass::Efficient,
+        }
+    }
+
+    /// `core_mask` as a [`MaliCoreMask`]
+    pub fn core_mask_parsed(&self) -> Option<MaliCoreMask> {
+        self.core_mask.map(MaliCoreMask)
+    }
+
+    /// Active and maximum shader core count as `(active, max_possible)`
+    ///
+    /// `active` is the popcount of `core_mask`. `max_possible` is the
+    /// `max_cores` of the `MALI_GPU_MODELS` entry `gpu_id` identifies; binned
+    /// dies report fewer active cores than that. Returns `None` when either
+    /// value is unavailable, or when more cores are active than the model
+    /// can have, which points at a misidentified GPU.
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    pub fn shader_core_count_range(&self) -> Option<(u8, u8)> {
+        let active = self.core_mask?.count_ones() as u8;
+        let model = crate::mappings::identify_mali_gpu(self.gpu_id?)?;
+        (active <= model.max_cores).then_some((active, model.max_cores))
+    }
+
+    /// Check whether this info describes the GPU of `profile`, without any IOCTL calls
+    ///
+    /// Vendor (case-insensitive) and model prefix must match. The profile's
+    /// `model_number` and `architecture` metadata are only compared when both
+    /// sides have a value; `model_number` may match the full GPU ID or its
+    /// product ID bits.
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    pub fn matches_profile(&self, profile: &crate::profiles::IoctlProfile) -> bool {
+        if !self.vendor.eq_ignore_ascii_case(&profile.vendor) || !self.model.starts_with(&profile.model) {
+            return false;
+        }
+
+        let model_number = profile.metadata.get("model_number").and_then(crate::profiles::parse_json_u32);
+        if let (Some(gpu_id), Some(expected)) = (self.gpu_id, model_number) {
+            if gpu_id != expected && gpu_id & 0xFFFF != expected {
+                return false;
+            }
+        }
+
+        let architecture = profile.metadata.get("architecture").and_then(|v| v.as_str());
+        match (self.architecture.as_deref(), architecture) {
+            (Some(actual), Some(expected)) => actual.eq_ignore_ascii_case(expected),
+            _ => true,
+        }
+    }
+}
+
+/// Estimated GPU power class, from lowest to highest power draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PowerClass {
+    /// Ultra low power parts
+    Ultra = 0,
+    /// Power efficient low/mid-range parts
+    Efficient = 1,
+    /// Mainstream parts
+    Balanced = 2,
+    /// High performance parts
+    Performance = 3,
+    /// Flagship parts with large core counts
+    Flagship = 4,
+}
+
+impl PowerClass {
+    /// Recommended maximum GPU temperature for this class
+    pub fn max_recommended_temperature_celsius(&self) -> u8 {
+        match self {
+            PowerClass::Ultra => 65,
+            PowerClass::Efficient => 70,
+            PowerClass::Balanced => 75,
+            PowerClass::Performance => 80,
+            PowerClass::Flagship => 85,
+        }
+    }
+}
+
+impl std::fmt::Display for PowerClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PowerClass::Ultra => "Ultra",
+            PowerClass::Efficient => "Efficient",
+            PowerClass::Balanced => "Balanced",
+            PowerClass::Performance => "Performance",
+            PowerClass::Flagship => "Flagship",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for PowerClass {
+    type Err = DiscoveryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ultra" => Ok(PowerClass::Ultra),
+            "efficient" => Ok(PowerClass::Efficient),
+            "balanced" => Ok(PowerClass::Balanced),
+            "performance" => Ok(PowerClass::Performance),
+            "flagship" => Ok(PowerClass::Flagship),
+            _ => Err(DiscoveryError::InvalidParameter(format!("unknown power class: {}", s))),
+        }
+    }
+}
+
+impl From<PowerClass> for u8 {
+    fn from(class: PowerClass) -> u8 {
+        class as u8
+    }
+}
+
 /// Get GPU information with automatic device detection
 ///
+/// This function automatically detects the GPU device and returns
+/// structured information about it using pre-defined profiles.
+///
 /// This function requires the `discovery` feature to be enabled.
 /// For API-only usage without IOCTL discovery, see `get_gpu_info_static()`.
+///
+/// # Example
+///
+/// ```no_run
+/// match iodisco::get_gpu_info() {
+///     Ok(info) => {
+///         println!("Found GPU: {} {}", info.vendor, info.model);
+///     }
+///     Err(iodisco::GpuInfoError::NoProfile) => {
+///         eprintln!("GPU not recognized. Consider running discovery mode.");
+///     }
+///     Err(e) => {
+///         eprintln!("Error: {}", e);
+///     }
+/// }
+/// ```
 pub fn get_gpu_info() -> Result<GpuInfo, GpuInfoError> {
     get_gpu_info_with_device(None)
 }
 
+/// Get GPU information, reusing a JSON cache file when it is fresh
+///
+/// If `cache_path` points to a file younger than 24 hours it is loaded
+/// instead of issuing any IOCTLs. Otherwise a normal discovery is performed
+/// and the result is written to the cache (write failures are ignored).
+pub fn get_gpu_info_cached(cache_path: Option<&str>) -> Result<GpuInfo, GpuInfoError> {
+    let Some(path) = cache_path else {
+        return get_gpu_info();
+    };
+
+    let is_fresh = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < GPU_INFO_CACHE_MAX_AGE);
+
+    if is_fresh {
+        if let Ok(info) = GpuInfo::from_json_file(path) {
+            return Ok(info);
+        }
+    }
+
+    let info = get_gpu_info()?;
+    let _ = info.save_to_json_file(path);
+    Ok(info)
+}
+
 /// Get GPU information with a specific device path
 ///
 /// This function requires the `discovery` feature to be enabled.
@@ -89,16 +856,16 @@ pub fn get_gpu_info_with_device(device_path: Option<&str>) -> Result<GpuInfo, Gp
         // Check if discovery feature is enabled
         #[cfg(not(feature = "discovery"))]
         {
-            return Err(GpuInfoError::Discovery(
+            let _ = device_path;
+            Err(GpuInfoError::Discovery(
                 "IOCTL discovery requires the 'discovery' feature to be enabled".to_string()
-            ));
+            ))
         }
         
         #[cfg(feature = "discovery")]
         {
             use crate::profiles::{load_mali_profiles, load_adreno_profiles};
-            use crate::discovery::{IoctlDiscovery, DiscoveryConfig};
-            
+
             // 1. Find or use specified device
             let device = if let Some(path) = device_path {
                 path.to_string()
@@ -111,7 +878,17 @@ pub fn get_gpu_info_with_device(device_path: Option<&str>) -> Result<GpuInfo, Gp
             let mut all_profiles = load_mali_profiles();
             all_profiles.extend(load_adreno_profiles());
 
-            // 3. Try each profile until one matches
+            // Skip profiles that require a newer kernel than the running one
+            if let Some(kernel) = crate::profiles::running_kernel_version() {
+                all_profiles.retain(|p| p.is_applicable_for_kernel(&kernel));
+            }
+
+            // 3. Registered vendor extensions claiming this device go first
+            if let Some(gpu_info) = crate::vendor::VendorRegistry::for_device(&device, |ext| try_vendor_extension(&device, ext)) {
+                return Ok(gpu_info);
+            }
+
+            // 4. Try each profile until one matches
             for profile in all_profiles {
                 if let Some(gpu_info) = try_profile(&device, &profile) {
                     return Ok(gpu_info);
@@ -152,12 +929,107 @@ pub fn get_gpu_info_static() -> Result<GpuInfo, GpuInfoError> {
     Err(GpuInfoError::NoProfile)
 }
 
+/// sysfs files exposing the Mali GPU ID, in lookup order
+#[cfg(feature = "mali")]
+const MALI_SYSFS_GPU_ID_PATHS: &[&str] = &[
+    "/sys/class/misc/mali0/device/gpu_id",
+    "/sys/class/misc/mali0/device/product_id",
+];
+
+/// Platform driver directory whose device subdirectories may contain `gpu_id`
+#[cfg(feature = "mali")]
+const MALI_PLATFORM_DRIVER_DIR: &str = "/sys/bus/platform/drivers/mali";
+
+/// Identify a Mali GPU from sysfs without opening the device node
+///
+/// Reads `sysfs_path` if given, otherwise the known kbase sysfs entries,
+/// `/sys/bus/platform/drivers/mali/*/gpu_id` and finally the debugfs
+/// `gpu_id`. Values are parsed as hex: either in the layout `identify_mali_gpu`
+/// expects, a bare 16-bit product ID, or a raw `GPU_ID` register value with
+/// the product ID in the upper half.
+#[cfg(feature = "mali")]
+pub fn identify_mali_gpu_from_sysfs(sysfs_path: Option<&str>) -> Option<GpuInfo> {
+    let candidates: Vec<std::path::PathBuf> = match sysfs_path {
+        Some(path) => vec![path.into()],
+        None => {
+            let mut paths: Vec<std::path::PathBuf> = MALI_SYSFS_GPU_ID_PATHS.iter().map(Into::into).collect();
+            if let Ok(entries) = std::fs::read_dir(MALI_PLATFORM_DRIVER_DIR) {
+                let mut devices: Vec<_> = entries.flatten().map(|e| e.path().join("gpu_id")).collect();
+                devices.sort();
+                paths.extend(devices);
+            }
+            paths.push("/sys/kernel/debug/mali0/gpu_id".into());
+            paths
+        }
+    };
+
+    candidates.iter().find_map(|path| {
+        let raw = std::fs::read_to_string(path).ok()?;
+        let raw = raw.trim();
+        let digits = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")).unwrap_or(raw);
+        let value = u32::from_str_radix(digits, 16).ok()?;
+
+        // sysfs usually has no core count, so fall back to the smallest variant
+        let product = if value > 0xFFFF { value >> 16 } else { value };
+        let (gpu_id, model) = [value, (1 << 16) | product].into_iter()
+            .find_map(|id| crate::mappings::identify_mali_gpu(id).map(|model| (id, model)))?;
+
+        Some(GpuInfo {
+            vendor: "Mali".to_string(),
+            model: model.name.to_string(),
+            architecture: Some(model.architecture.to_string()),
+            driver_version: None,
+            gpu_id: Some(gpu_id),
+            cores: Some(model.min_cores),
+            features: Vec::new(),
+            detected_ioctls: Vec::new(),
+            metadata: serde_json::json!({ "source": path.to_string_lossy() }),
+            arch_version: None,
+            core_mask: None,
+            l2_cache_count: None,
+            l2_cache_size: None,
+            bus_width: model.bus_width_bits,
+            engines_per_core: Some(model.execution_engines),
+            fp32_fmas_per_core: Some(model.fma_per_engine),
+            fp16_fmas_per_core: Some(model.fma_per_engine * 2),
+            texels_per_core: Some(model.texels_per_cycle as u16),
+            pixels_per_core: Some(model.pixels_per_cycle as u16),
+            raw_ioctl_data: HashMap::new(),
+        })
+    })
+}
+
+/// Try the profiles of a registered vendor extension against a device
+#[cfg(all(any(feature = "mali", feature = "adreno"), feature = "discovery"))]
+fn try_vendor_extension(device_path: &str, ext: &dyn crate::vendor::VendorExtensions) -> Option<GpuInfo> {
+    use crate::discovery::{IoctlDiscovery, DiscoveryConfig};
+
+    ext.profiles().iter().find_map(|profile| {
+        let matched = try_profile(device_path, profile)?;
+        // Prefer the extension's own decoding of the GPU info block
+        let identified = profile.gpu_info_ioctl.as_ref().and_then(|def| {
+            let mut discovery = IoctlDiscovery::open(device_path, DiscoveryConfig::quick().into()).ok()?;
+            let raw = discovery.execute_ioctl(def.cmd, def.buffer_size as usize).ok()?;
+            ext.identify(&raw)
+        });
+        Some(match identified {
+            Some(mut info) => {
+                info.detected_ioctls = matched.detected_ioctls;
+                info
+            }
+            None => matched,
+        })
+    })
+}
+
 /// Try to match a device against a profile (requires discovery feature)
 #[cfg(all(any(feature = "mali", feature = "adreno"), feature = "discovery"))]
 fn try_profile(device_path: &str, profile: &crate::profiles::IoctlProfile) -> Option<GpuInfo> {
-    use crate::discovery::{IoctlDiscovery, DiscoveryConfig};
+    use crate::discovery::{IoctlDiscovery, DiscoveryConfig, DiscoveryOptions, Verbosity};
+    use crate::profiles::ValidationResult;
     
-    let mut discovery = IoctlDiscovery::open(device_path, DiscoveryConfig::quick().into())
+    let options: DiscoveryOptions = DiscoveryConfig::quick().into();
+    let mut discovery = IoctlDiscovery::open(device_path, options.clone())
         .ok()?;
 
     // Test signature IOCTLs from profile
@@ -199,9 +1071,56 @@ fn try_profile(device_path: &str, profile: &crate::profiles::IoctlProfile) -> Op
     }
 
     // Profile matches! Collect additional information
+    if profile.deprecated && options.verbosity.is_at_least(Verbosity::Normal) {
+        options.write_progress(true, format_args!(
+            "⚠️  Matched deprecated profile '{}'{}",
+            profile.model,
+            profile.superseded_by.as_ref()
+                .map(|s| format!(" (superseded by '{}')", s))
+                .unwrap_or_default()
+        ));
+    }
     let mut gpu_info = create_gpu_info_from_profile(profile);
     gpu_info.detected_ioctls = working_ioctls;
 
+    // Signature score is 1.0 once all detection IOCTLs exist, halved when a
+    // live response contradicts the profile; scale it by how close the running
+    // kernel is to the one the profile was confirmed on
+    let mut signature_score = 1.0;
+    for (ioctl_def, detected) in profile.detection_ioctls.iter().zip(&gpu_info.detected_ioctls) {
+        let has_expectations = ioctl_def.params.as_object().is_some_and(|p| !p.is_empty());
+        if !has_expectations {
+            continue;
+        }
+        let response = discovery
+            .execute_ioctl(ioctl_def.cmd, ioctl_def.buffer_size as usize)
+            .unwrap_or_default();
+        let ret_val = detected.return_value.unwrap_or(0);
+        if let ValidationResult::Mismatch { .. } = ioctl_def.validate_response(&response, ret_val) {
+            signature_score = 0.5;
+        }
+    }
+    let mut kernel_confidence = crate::profiles::KernelVersion::running()
+        .map(|kernel| profile.confidence(&kernel))
+        .unwrap_or(1.0);
+    // Stale profile: the running kernel was never tested with it
+    if crate::profiles::check_kernel_compatibility(profile) == crate::profiles::CompatibilityStatus::Incompatible {
+        if options.verbosity.is_at_least(Verbosity::Normal) {
+            options.write_progress(true, format_args!(
+                "⚠️  Profile '{}' is incompatible with kernel {}",
+                profile.model,
+                crate::profiles::running_kernel_version().unwrap_or_default()
+            ));
+        }
+        kernel_confidence *= 0.5;
+    }
+    if !gpu_info.metadata.is_object() {
+        gpu_info.metadata = serde_json::json!({});
+    }
+    if let serde_json::Value::Object(ref mut map) = gpu_info.metadata {
+        map.insert("match_confidence".to_string(), serde_json::json!(signature_score * kernel_confidence));
+    }
+
     // Try to get version information if defined in profile
     if let Some(version_ioctl) = &profile.version_ioctl {
         match discovery.test_single_ioctl(version_ioctl.cmd) {
@@ -255,7 +1174,7 @@ fn try_profile(device_path: &str, profile: &crate::profiles::IoctlProfile) -> Op
                                     gpu_info.engines_per_core = Some(model_info.execution_engines);
                                 }
                                 if gpu_info.fp32_fmas_per_core.is_none() {
-                                    gpu_info.fp32_fmas_per_core = Some(model_info.fma_per_engine as u16);
+                                    gpu_info.fp32_fmas_per_core = Some(model_info.fma_per_engine);
                                 }
                                 if gpu_info.texels_per_core.is_none() {
                                     gpu_info.texels_per_core = Some(model_info.texels_per_cycle as u16);
@@ -266,7 +1185,11 @@ fn try_profile(device_path: &str, profile: &crate::profiles::IoctlProfile) -> Op
 
                                 // Estimate FP16 (usually 2x FP32 for Mali)
                                 if gpu_info.fp16_fmas_per_core.is_none() {
-                                    gpu_info.fp16_fmas_per_core = Some((model_info.fma_per_engine * 2) as u16);
+                                    gpu_info.fp16_fmas_per_core = Some(model_info.fma_per_engine * 2);
+                                }
+
+                                if gpu_info.bus_width.is_none() {
+                                    gpu_info.bus_width = model_info.bus_width_bits;
                                 }
                             }
                         }
@@ -326,9 +1249,39 @@ fn try_profile(device_path: &str, profile: &crate::profiles::IoctlProfile) -> Op
         }
     }
 
+    // Keep responses no parser understands for offline analysis
+    let optional = [&profile.version_ioctl, &profile.gpu_info_ioctl, &profile.features_ioctl];
+    for ioctl_def in profile.detection_ioctls.iter().chain(optional.into_iter().flatten()) {
+        if KNOWN_PARSERS.contains(&ioctl_def.parser.as_str()) || gpu_info.raw_ioctl_data.contains_key(&ioctl_def.cmd) {
+            continue;
+        }
+        if !discovery.test_single_ioctl(ioctl_def.cmd).is_ok_and(|r| r.is_success()) {
+            continue;
+        }
+        if let Ok(data) = discovery.execute_ioctl(ioctl_def.cmd, ioctl_def.buffer_size as usize) {
+            gpu_info.raw_ioctl_data.insert(ioctl_def.cmd, data);
+        }
+    }
+
     Some(gpu_info)
 }
 
+/// Parser names understood by `parse_version`, `extract_gpu_id` and `parse_features`
+#[cfg(all(any(feature = "mali", feature = "adreno"), feature = "discovery"))]
+const KNOWN_PARSERS: &[&str] = &[
+    "parse_version_return_value",
+    "parse_version_two_u32",
+    "parse_version_u32",
+    "parse_gpu_id_u32",
+    "parse_gpu_id_le",
+    "parse_gpu_id_be",
+    "parse_gpu_id_auto",
+    "parse_features_bitmask",
+    "parse_features_hex",
+    "parse_mali_bifrost_features",
+    "parse_mali_valhall_features",
+];
+
 /// Create GPU info from a profile (without IOCTL testing)
 #[cfg(any(feature = "mali", feature = "adreno"))]
 fn create_gpu_info_from_profile(profile: &crate::profiles::IoctlProfile) -> GpuInfo {
@@ -347,9 +1300,7 @@ fn create_gpu_info_from_profile(profile: &crate::profiles::IoctlProfile) -> GpuI
         detected_ioctls: Vec::new(),
         metadata: profile.metadata.clone(),
         arch_version: None,
-        core_mask: profile.metadata.get("core_mask")
-            .and_then(|v| v.as_u64())
-            .map(|c| c as u32),
+        core_mask: profile.metadata.get("core_mask").and_then(crate::profiles::parse_json_u32),
         l2_cache_count: profile.metadata.get("l2_cache_count")
             .and_then(|v| v.as_u64())
             .map(|c| c as u8),
@@ -374,15 +1325,17 @@ fn create_gpu_info_from_profile(profile: &crate::profiles::IoctlProfile) -> GpuI
         pixels_per_core: profile.metadata.get("pixels_per_core")
             .and_then(|v| v.as_u64())
             .map(|p| p as u16),
+        raw_ioctl_data: HashMap::new(),
     }
 }
 
 /// Parse version from raw data using generic parser
+#[cfg_attr(not(feature = "discovery"), allow(dead_code))]
 fn parse_version(data: &[u8], parser: &str, ret_val: i32) -> Option<String> {
     match parser {
         // Generic parser: Extract version from return value (major in high byte, minor in low byte)
         "parse_version_return_value" => {
-            Some(format!("{}.{}", (ret_val >> 8) as u8, ret_val as u8 & 0xFF))
+            Some(format!("{}.{}", (ret_val >> 8) as u8, ret_val as u8))
         }
         // Generic parser: First 8 bytes as two u32 (major, minor)
         "parse_version_two_u32" => {
@@ -409,6 +1362,7 @@ fn parse_version(data: &[u8], parser: &str, ret_val: i32) -> Option<String> {
 }
 
 /// Extract GPU ID from raw data using generic parser
+#[cfg_attr(not(feature = "discovery"), allow(dead_code))]
 fn extract_gpu_id(data: &[u8], parser: &str) -> Option<u32> {
     match parser {
         // Generic parser: First 4 bytes as GPU ID
@@ -435,12 +1389,49 @@ fn extract_gpu_id(data: &[u8], parser: &str) -> Option<u32> {
                 None
             }
         }
+        // Generic parser: First 4 bytes with byte order auto-detection
+        "parse_gpu_id_auto" => {
+            if data.len() >= 4 {
+                Some(detect_gpu_id_byte_order(data[0..4].try_into().ok()?))
+            } else {
+                None
+            }
+        }
         // Unknown parser - return None
         _ => None,
     }
 }
 
+/// Interpret 4 raw bytes as a GPU ID, guessing the byte order
+///
+/// Prefers the interpretation whose hardware ID lies in the modern Mali
+/// range (0x6000-0xFFFF), little-endian first. Otherwise the one matching a
+/// known model wins, falling back to little-endian.
+#[cfg_attr(not(feature = "discovery"), allow(dead_code))]
+fn detect_gpu_id_byte_order(bytes: [u8; 4]) -> u32 {
+    let le = u32::from_le_bytes(bytes);
+    let be = u32::from_be_bytes(bytes);
+    let plausible = |id: u32| (0x6000..=0xFFFF).contains(&(id & 0xFFFF));
+
+    if plausible(le) {
+        return le;
+    }
+    if plausible(be) {
+        return be;
+    }
+
+    #[cfg(feature = "mali")]
+    {
+        if crate::mappings::identify_mali_gpu(le).is_none() && crate::mappings::identify_mali_gpu(be).is_some() {
+            return be;
+        }
+    }
+
+    le
+}
+
 /// Parse feature flags from raw data using generic parser
+#[cfg_attr(not(feature = "discovery"), allow(dead_code))]
 fn parse_features(data: &[u8], parser: &str) -> Vec<String> {
     match parser {
         // Generic parser: 4-byte bitmask with named bits
@@ -452,6 +1443,18 @@ fn parse_features(data: &[u8], parser: &str) -> Vec<String> {
                 Vec::new()
             }
         }
+        "parse_mali_bifrost_features" | "parse_mali_valhall_features" => {
+            let table = if parser == "parse_mali_bifrost_features" {
+                &MALI_BIFROST_FEATURE_BITS
+            } else {
+                &MALI_VALHALL_FEATURE_BITS
+            };
+            if data.len() >= 4 {
+                parse_features_with_table(u32::from_le_bytes(data[0..4].try_into().unwrap()), table)
+            } else {
+                Vec::new()
+            }
+        }
         // Generic parser: Raw hex value
         "parse_features_hex" => {
             if data.len() >= 4 {
@@ -466,37 +1469,71 @@ fn parse_features(data: &[u8], parser: &str) -> Vec<String> {
     }
 }
 
-/// Helper: Convert bitmask to feature names (generic for all GPUs)
-fn parse_bitmask_to_features(bitmask: u32) -> Vec<String> {
-    let mut features = Vec::new();
+/// Names of the 32 bits of a feature bitmask, as `(bit, name)` pairs
+///
+/// Bits with an empty name are reported as `BIT_<n>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureBitTable(pub [(u8, &'static str); 32]);
 
-    // Generic bit names - profiles can override or extend
-    for i in 0..32 {
-        if bitmask & (1 << i) != 0 {
-            features.push(format!("BIT_{}", i));
+impl FeatureBitTable {
+    /// Table naming only the given bits
+    pub const fn new(named: &[(u8, &'static str)]) -> Self {
+        let mut table = [(0u8, ""); 32];
+        let mut bit = 0;
+        while bit < 32 {
+            table[bit].0 = bit as u8;
+            bit += 1;
         }
+        Self(table).with(named)
     }
 
-    // If we have known special bits, name them
-    if bitmask & (1 << 0) != 0 {
-        if let Some(pos) = features.iter().position(|f| f == "BIT_0") {
-            features[pos] = "JOB_CHAINING".to_string();
+    /// This table with the given bits named, e.g. a newer family's additions
+    pub const fn with(mut self, named: &[(u8, &'static str)]) -> Self {
+        let mut i = 0;
+        while i < named.len() {
+            self.0[named[i].0 as usize].1 = named[i].1;
+            i += 1;
         }
+        self
     }
 
-    if bitmask & (1 << 1) != 0 {
-        if let Some(pos) = features.iter().position(|f| f == "BIT_1") {
-            features[pos] = "TILER".to_string();
-        }
+    /// Name of `bit`, if the table has one
+    pub fn name(&self, bit: u8) -> Option<&'static str> {
+        let (_, name) = self.0.get(bit as usize)?;
+        Some(*name).filter(|name| !name.is_empty())
     }
+}
 
-    if bitmask & (1 << 2) != 0 {
-        if let Some(pos) = features.iter().position(|f| f == "BIT_2") {
-            features[pos] = "COHERENCY".to_string();
-        }
-    }
+/// Bits named by the generic `parse_features_bitmask` parser
+const GENERIC_FEATURE_BITS: FeatureBitTable = FeatureBitTable::new(&[
+    (0, "JOB_CHAINING"),
+    (1, "TILER"),
+    (2, "COHERENCY"),
+]);
 
-    features
+/// `GPU_FEATURES` register bits of Bifrost, see `mali_kbase_gpu_regmap.h`
+///
+/// Bifrost GPUs predate the register and read it as zero, so the table keeps
+/// the generic names. Later families extend it.
+pub const MALI_BIFROST_FEATURE_BITS: FeatureBitTable = GENERIC_FEATURE_BITS;
+
+/// `GPU_FEATURES` register bits of Valhall: Bifrost plus the ray intersection unit
+pub const MALI_VALHALL_FEATURE_BITS: FeatureBitTable = MALI_BIFROST_FEATURE_BITS.with(&[
+    (2, "GPU_FEATURES_RAY_INTERSECTION"),
+]);
+
+/// Names of the set bits of `bitmask`, lowest bit first
+pub fn parse_features_with_table(bitmask: u32, table: &FeatureBitTable) -> Vec<String> {
+    (0..32u8)
+        .filter(|bit| bitmask & (1 << bit) != 0)
+        .map(|bit| table.name(bit).map_or_else(|| format!("BIT_{}", bit), str::to_string))
+        .collect()
+}
+
+/// Helper: Convert bitmask to feature names (generic for all GPUs)
+#[cfg_attr(not(feature = "discovery"), allow(dead_code))]
+fn parse_bitmask_to_features(bitmask: u32) -> Vec<String> {
+    parse_features_with_table(bitmask, &GENERIC_FEATURE_BITS)
 }
 
 #[cfg(test)]
@@ -535,4 +1572,386 @@ mod tests {
         let result = extract_gpu_id(&data, "parse_gpu_id_u32");
         assert_eq!(result, Some(0x21));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_android_props_roundtrip() {
+        let mut props = HashMap::new();
+        props.insert("ro.hardware.gpu.vendor".to_string(), "ARM Mali".to_string());
+        props.insert("ro.hardware.gpu.model".to_string(), "Mali-G720".to_string());
+        props.insert("ro.hardware.gpu.arch".to_string(), "Arm 5th Gen".to_string());
+        props.insert("ro.hardware.gpu.cores".to_string(), "7".to_string());
+        props.insert("ro.hardware.gpu.id".to_string(), "0x0007c000".to_string());
+
+        let info = GpuInfo::from_android_props(&props).unwrap();
+        assert_eq!(info.model, "Mali-G720");
+        assert_eq!(info.cores, Some(7));
+        assert_eq!(info.gpu_id, Some(0x0007c000));
+
+        assert_eq!(info.to_android_prop_format(), props);
+    }
+
+    #[test]
+    fn test_android_props_missing_model() {
+        let mut props = HashMap::new();
+        props.insert("ro.hardware.gpu.vendor".to_string(), "ARM Mali".to_string());
+        assert!(GpuInfo::from_android_props(&props).is_err());
+    }
+
+    #[test]
+    fn test_android_props_out_of_range() {
+        let mut props = HashMap::new();
+        props.insert("ro.hardware.gpu.vendor".to_string(), "ARM Mali".to_string());
+        props.insert("ro.hardware.gpu.model".to_string(), "Mali-G720".to_string());
+        props.insert("ro.hardware.gpu.core_mask".to_string(), "0XFF".to_string());
+        assert_eq!(GpuInfo::from_android_props(&props).unwrap().core_mask, Some(0xFF));
+
+        props.insert("ro.hardware.gpu.cores".to_string(), "300".to_string());
+        let err = GpuInfo::from_android_props(&props).unwrap_err();
+        assert!(matches!(&err, DiscoveryError::InvalidParameter(msg) if msg.contains("ro.hardware.gpu.cores")));
+
+        props.insert("ro.hardware.gpu.cores".to_string(), "7".to_string());
+        props.insert("ro.hardware.gpu.bus_width".to_string(), "70000".to_string());
+        let err = GpuInfo::from_android_props(&props).unwrap_err();
+        assert!(matches!(&err, DiscoveryError::InvalidParameter(msg) if msg.contains("ro.hardware.gpu.bus_width")));
+    }
+
+    fn sample_gpu_info() -> GpuInfo {
+        let mut props = HashMap::new();
+        props.insert("ro.hardware.gpu.vendor".to_string(), "Mali".to_string());
+        props.insert("ro.hardware.gpu.model".to_string(), "Mali-G71".to_string());
+        props.insert("ro.hardware.gpu.cores".to_string(), "2".to_string());
+        GpuInfo::from_android_props(&props).unwrap()
+    }
+
+    #[test]
+    fn test_estimated_power_class() {
+        let mut info = sample_gpu_info();
+        assert_eq!(info.estimated_power_class(), PowerClass::Balanced); // Bifrost Mainstream
+
+        info.model = "Mali-T760".to_string();
+        assert_eq!(info.estimated_power_class(), PowerClass::Efficient);
+
+        info.model = "Immortalis-G720".to_string();
+        info.cores = Some(12);
+        assert_eq!(info.estimated_power_class(), PowerClass::Flagship);
+        assert_eq!(info.estimated_power_class().max_recommended_temperature_celsius(), 85);
+
+        info.gpu_id = Some(0x0002a002); // Mali-G710
+        assert_eq!(info.estimated_power_class(), PowerClass::Performance);
+
+        // Models missing from the database go by architecture
+        info.gpu_id = None;
+        info.model = "Mali-G999".to_string();
+        info.architecture = Some("Midgard".to_string());
+        assert_eq!(info.estimated_power_class(), PowerClass::Efficient);
+        info.architecture = Some("Valhall".to_string());
+        assert_eq!(info.estimated_power_class(), PowerClass::Performance);
+        info.architecture = None;
+        assert_eq!(info.estimated_power_class(), PowerClass::Balanced);
+    }
+
+    #[test]
+    fn test_architecture_generation() {
+        let mut bifrost = sample_gpu_info();
+        bifrost.architecture = Some("Bifrost".to_string());
+        assert_eq!(bifrost.architecture_generation(), Some(2));
+
+        let mut valhall = bifrost.clone();
+        valhall.architecture = Some("Valhall".to_string());
+        assert_eq!(valhall.is_newer_than(&bifrost), Some(true));
+        assert_eq!(bifrost.is_newer_than(&valhall), Some(false));
+
+        let mut adreno = bifrost.clone();
+        adreno.vendor = "Qualcomm".to_string();
+        adreno.model = "Adreno 640".to_string();
+        adreno.architecture = None;
+        assert_eq!(adreno.architecture_generation(), Some(60));
+        adreno.architecture = Some("A7xx".to_string());
+        assert_eq!(adreno.architecture_generation(), Some(70));
+        assert_eq!(adreno.is_newer_than(&valhall), None);
+
+        let mut unknown = bifrost.clone();
+        unknown.model = "Unknown".to_string();
+        unknown.architecture = None;
+        assert_eq!(unknown.architecture_generation(), None);
+        assert_eq!(unknown.is_newer_than(&bifrost), None);
+    }
+
+    #[test]
+    fn test_to_vulkan_hint() {
+        let mut info = sample_gpu_info();
+        assert_eq!(info.to_vulkan_hint(), None);
+
+        info.gpu_id = Some(0x6000_0011);
+        let hint = info.to_vulkan_hint().unwrap();
+        assert_eq!(hint.device_name, "Mali-G71");
+        assert_eq!(hint.vendor_id, VULKAN_VENDOR_ID_ARM);
+        assert_eq!(hint.device_id, 0x6000_0011);
+        assert_eq!(hint.device_type, VulkanDeviceType::IntegratedGpu);
+
+        info.vendor = "Qualcomm".to_string();
+        assert_eq!(info.to_vulkan_hint().unwrap().vendor_id, VULKAN_VENDOR_ID_QUALCOMM);
+        info.vendor = "Vivante".to_string();
+        assert_eq!(info.to_vulkan_hint(), None);
+    }
+
+    #[test]
+    fn test_raw_ioctl_data_base64() {
+        let mut info = sample_gpu_info();
+        info.raw_ioctl_data.insert(0xC0048034, vec![1, 2, 3]);
+        info.raw_ioctl_data.insert(0xC0048000, vec![0xC0, 0x04, 0x80, 0x00]);
+        let mut value = serde_json::to_value(&info).unwrap();
+        assert_eq!(value["raw_ioctl_data"]["3221520436"], "AQID");
+        assert_eq!(value["raw_ioctl_data"]["3221520384"], "wASAAA==");
+        let parsed: GpuInfo = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(parsed.raw_ioctl_data, info.raw_ioctl_data);
+
+        value["raw_ioctl_data"]["3221520436"] = "w*==".into();
+        let err = serde_json::from_value::<GpuInfo>(value).unwrap_err();
+        assert!(err.to_string().contains("invalid base64 data for IOCTL 0xc0048034"));
+
+        let without: GpuInfo = serde_json::from_value(serde_json::to_value(sample_gpu_info()).unwrap()).unwrap();
+        assert!(without.raw_ioctl_data.is_empty());
+    }
+
+    #[test]
+    fn test_parse_features_with_table() {
+        let bits = (1u32 << 0 | 1 << 2 | 1 << 31).to_le_bytes();
+        assert_eq!(parse_features(&bits, "parse_mali_bifrost_features"), parse_features(&bits, "parse_features_bitmask"));
+        assert_eq!(parse_features(&bits, "parse_mali_bifrost_features"), vec!["JOB_CHAINING", "COHERENCY", "BIT_31"]);
+        assert_eq!(
+            parse_features(&bits, "parse_mali_valhall_features"),
+            vec!["JOB_CHAINING", "GPU_FEATURES_RAY_INTERSECTION", "BIT_31"]
+        );
+        assert_eq!(MALI_BIFROST_FEATURE_BITS.0[31], (31, ""));
+        assert_eq!(MALI_VALHALL_FEATURE_BITS.with(&[(31, "X")]).name(31), Some("X"));
+        assert!(parse_features(&bits[..2], "parse_mali_bifrost_features").is_empty());
+    }
+
+    #[test]
+    fn test_compare_to() {
+        let mut before = sample_gpu_info();
+        before.l2_cache_size = Some(512 * 1024);
+        assert!(before.compare_to(&before).is_empty());
+
+        let mut after = before.clone();
+        after.driver_version = Some("r99p0".to_string());
+        after.cores = Some(1);
+        after.l2_cache_size = Some(1024 * 1024);
+        after.features.push("new_feature".to_string());
+
+        let diff = before.compare_to(&after);
+        assert_eq!(diff.changed_fields["driver_version"].1, "r99p0");
+        assert_eq!(diff.assessments["driver_version"], FieldChange::Neutral);
+        assert_eq!(diff.regressions(), vec!["cores"]);
+        assert_eq!(diff.improvements(), vec!["l2_cache_size"]);
+        assert_eq!(diff.added_features, vec!["new_feature".to_string()]);
+        assert!(diff.removed_features.is_empty());
+        assert_eq!(after.compare_to(&before).removed_features, vec!["new_feature".to_string()]);
+    }
+
+    #[test]
+    fn test_core_mask_parsed() {
+        let g720 = crate::profiles::load_mali_profiles().into_iter().find(|p| p.model == "Mali-G720").unwrap();
+        let mask = create_gpu_info_from_profile(&g720).core_mask_parsed().unwrap();
+        assert_eq!(mask, MaliCoreMask(0x150055));
+        assert_eq!(mask.active_cores(), 7);
+        assert_eq!(mask.max_core_index(), 20);
+        assert_eq!(mask.core_indices(), vec![0, 2, 4, 6, 16, 18, 20]);
+        assert!(!mask.is_contiguous());
+
+        assert!(MaliCoreMask(0x3).is_contiguous());
+        assert!(MaliCoreMask(0xF0).is_contiguous());
+        assert!(MaliCoreMask(u32::MAX).is_contiguous());
+        assert!(MaliCoreMask(0).is_contiguous());
+        assert_eq!(MaliCoreMask(0).max_core_index(), 0);
+        assert!(sample_gpu_info().core_mask_parsed().is_none());
+    }
+
+    #[test]
+    fn test_shader_core_count_range() {
+        let mut info = sample_gpu_info();
+        info.gpu_id = Some(0x000c_c000); // Immortalis-G720 with 12 cores
+        assert_eq!(info.shader_core_count_range(), None);
+
+        info.core_mask = Some(0x150055);
+        assert_eq!(info.shader_core_count_range(), Some((7, 16)));
+
+        // 17 active cores can't be an Immortalis-G720
+        info.core_mask = Some(0x1FFFF);
+        assert_eq!(info.shader_core_count_range(), None);
+
+        info.gpu_id = Some(0x000c_ffff);
+        assert_eq!(info.shader_core_count_range(), None);
+        info.gpu_id = None;
+        assert_eq!(info.shader_core_count_range(), None);
+    }
+
+    #[test]
+    fn test_to_sysfs_format() {
+        let mut info = sample_gpu_info();
+        info.vendor = "ARM Mali".to_string();
+        info.architecture = Some("Bifrost".to_string());
+        info.gpu_id = Some(0x6000);
+        info.driver_version = Some(r#"r1p0 "$(rm)""#.to_string());
+
+        let output = info.to_sysfs_format();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines, vec![
+            r#"IODISCO_VENDOR="ARM Mali""#,
+            r#"IODISCO_MODEL="Mali-G71""#,
+            r#"IODISCO_ARCH="Bifrost""#,
+            r#"IODISCO_DRIVER_VERSION="r1p0 \"\$(rm)\"""#,
+            "IODISCO_GPU_ID=0x6000",
+            "IODISCO_CORES=2",
+        ]);
+    }
+
+    #[test]
+    fn test_power_class_conversions() {
+        assert_eq!("flagship".parse::<PowerClass>().unwrap(), PowerClass::Flagship);
+        assert!("turbo".parse::<PowerClass>().is_err());
+        assert_eq!(PowerClass::Balanced.to_string(), "Balanced");
+        assert_eq!(u8::from(PowerClass::Efficient), 1);
+    }
+
+    #[test]
+    fn test_performance_spec() {
+        let mut info = sample_gpu_info();
+        assert!(info.performance_spec().is_none());
+
+        // Per-core fallback: 2 cores * 12 FMAs
+        info.fp32_fmas_per_core = Some(12);
+        info.texels_per_core = Some(1);
+        let spec = info.performance_spec().unwrap();
+        assert_eq!(spec.fp32_gflops_per_ghz, 48.0);
+        assert_eq!(spec.fp16_gflops_per_ghz, 0.0);
+        assert_eq!(spec.texels_per_cycle, 2);
+
+        // Pre-computed metadata wins
+        info.metadata = serde_json::json!({ "total_fp32_fmas": 30, "total_fp16_fmas": 60 });
+        let spec = info.performance_spec().unwrap();
+        assert_eq!(spec.fp32_gflops_per_ghz, 60.0);
+        assert_eq!(spec.fp16_gflops_per_ghz, 120.0);
+    }
+
+    #[test]
+    fn test_json_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpu.json");
+        let path = path.to_str().unwrap();
+
+        let info = sample_gpu_info();
+        info.save_to_json_file(path).unwrap();
+
+        let loaded = GpuInfo::from_json_file(path).unwrap();
+        assert_eq!(loaded.model, info.model);
+        assert_eq!(loaded.cores, info.cores);
+        assert_eq!(loaded.to_android_prop_format(), info.to_android_prop_format());
+    }
+
+    #[test]
+    fn test_json_schema_validates_exports() {
+        let schema = GpuInfo::json_schema();
+        let validator = jsonschema::draft7::new(&schema).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpu.json");
+        let path = path.to_str().unwrap();
+        sample_gpu_info().save_to_json_file(path).unwrap();
+
+        let exported: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(exported["$schema"], GPU_INFO_SCHEMA_URL);
+        assert!(validator.is_valid(&exported));
+
+        let mut invalid = exported.clone();
+        invalid["cores"] = 300.into();
+        assert!(!validator.is_valid(&invalid));
+        invalid = exported;
+        invalid.as_object_mut().unwrap().remove("vendor");
+        assert!(!validator.is_valid(&invalid));
+
+        // The published schema file is kept in sync with the code
+        let published: serde_json::Value =
+            serde_json::from_str(include_str!("../gpu_info.schema.json")).unwrap();
+        assert_eq!(published, schema);
+    }
+
+    #[test]
+    fn test_get_gpu_info_cached_uses_fresh_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpu.json");
+        let path = path.to_str().unwrap();
+
+        sample_gpu_info().save_to_json_file(path).unwrap();
+
+        let info = get_gpu_info_cached(Some(path)).unwrap();
+        assert_eq!(info.model, "Mali-G71");
+    }
+
+    #[test]
+    fn test_extract_gpu_id_byte_orders() {
+        // Mali-G720 with 7 cores: 0x0007c000
+        let le = [0x00, 0xc0, 0x07, 0x00];
+        let be = [0x00, 0x07, 0xc0, 0x00];
+        assert_eq!(extract_gpu_id(&le, "parse_gpu_id_le"), Some(0x0007c000));
+        assert_eq!(extract_gpu_id(&be, "parse_gpu_id_be"), Some(0x0007c000));
+        assert_eq!(extract_gpu_id(&le, "parse_gpu_id_auto"), Some(0x0007c000));
+        assert_eq!(extract_gpu_id(&be, "parse_gpu_id_auto"), Some(0x0007c000));
+
+        // Mali-G71 with 2 cores: 0x00026000
+        assert_eq!(extract_gpu_id(&[0x00, 0x60, 0x02, 0x00], "parse_gpu_id_auto"), Some(0x00026000));
+        assert_eq!(extract_gpu_id(&[0x00, 0x02, 0x60, 0x00], "parse_gpu_id_auto"), Some(0x00026000));
+    }
+
+    #[cfg(feature = "mali")]
+    #[test]
+    fn test_extract_gpu_id_auto_known_midgard_model() {
+        // Mali-T760 (0x0750, 4 cores) is outside the modern range: resolved via model table
+        assert_eq!(extract_gpu_id(&[0x00, 0x04, 0x07, 0x50], "parse_gpu_id_auto"), Some(0x00040750));
+        assert_eq!(extract_gpu_id(&[0x50, 0x07, 0x04, 0x00], "parse_gpu_id_auto"), Some(0x00040750));
+    }
+
+    #[cfg(feature = "mali")]
+    #[test]
+    fn test_matches_profile() {
+        let profiles = crate::profiles::load_mali_profiles();
+        let g71 = profiles.iter().find(|p| p.model == "Mali-G71").unwrap();
+        let g720 = profiles.iter().find(|p| p.model == "Mali-G720").unwrap();
+
+        let mut info = create_gpu_info_from_profile(g71);
+        info.vendor = info.vendor.to_uppercase();
+        info.gpu_id = Some(0x0002_6000);
+        assert!(info.matches_profile(g71));
+        assert!(!info.matches_profile(g720));
+
+        info.gpu_id = Some(0x7000);
+        assert!(!info.matches_profile(g71));
+
+        info.gpu_id = None;
+        info.architecture = Some("Valhall".to_string());
+        assert!(!info.matches_profile(g71));
+    }
+
+    #[cfg(feature = "mali")]
+    #[test]
+    fn test_identify_mali_gpu_from_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpu_id");
+        let path_str = path.to_str().unwrap();
+
+        std::fs::write(&path, "0x7001\n").unwrap();
+        let info = identify_mali_gpu_from_sysfs(Some(path_str)).unwrap();
+        assert_eq!(info.model, "Mali-G76");
+        assert_eq!(info.gpu_id, Some(0x0001_7001));
+        assert_eq!(info.architecture.as_deref(), Some("Bifrost"));
+
+        // Raw GPU_ID register with the product ID in the upper half
+        std::fs::write(&path, "60000011").unwrap();
+        assert_eq!(identify_mali_gpu_from_sysfs(Some(path_str)).unwrap().model, "Mali-G71");
+
+        std::fs::write(&path, "not a number").unwrap();
+        assert!(identify_mali_gpu_from_sysfs(Some(path_str)).is_none());
+        assert!(identify_mali_gpu_from_sysfs(Some("/nonexistent/gpu_id")).is_none());
+    }
+}
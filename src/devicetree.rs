@@ -0,0 +1,264 @@
+//! GPU information from the device tree
+//!
+//! Works on a flattened device tree blob as well as on the unpacked tree the
+//! running kernel exposes under `/sys/firmware/devicetree/base`. The result
+//! only has what the GPU node describes: vendor, model or architecture from
+//! `compatible`, plus the register base and clock frequencies in `metadata`.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use fdt::node::FdtNode;
+use fdt::Fdt;
+
+use crate::api::GpuInfo;
+
+/// Unpacked device tree of the running kernel
+pub const DEVICETREE_BASE: &str = "/sys/firmware/devicetree/base";
+
+const FDT_MAGIC: u32 = 0xD00D_FEED;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_END: u32 = 9;
+
+/// Read the GPU node of a device tree into a partial `GpuInfo`
+///
+/// `dtb_path` may name a DTB file or an unpacked tree directory; `None` reads
+/// [`DEVICETREE_BASE`]. Returns `None` when the tree can't be read or has no
+/// Mali or Adreno GPU node.
+pub fn read_gpu_from_devicetree(dtb_path: Option<&str>) -> Option<GpuInfo> {
+    let path = Path::new(dtb_path.unwrap_or(DEVICETREE_BASE));
+    let blob = if path.is_dir() { flatten_dir(path).ok()? } else { std::fs::read(path).ok()? };
+    let fdt = Fdt::new(&blob).ok()?;
+
+    let node = fdt.all_nodes().find(|node| is_gpu_node(*node))?;
+    let compatible: Vec<&str> = node.compatible()?.all().collect();
+    let mut info = gpu_info_from_compatible(&compatible)?;
+
+    let reg_base = node.reg().and_then(|mut reg| reg.next()).map(|region| region.starting_address as usize);
+    let mut frequencies = Vec::new();
+    if let Some(hz) = node.property("clock-frequency").and_then(|p| p.as_usize()) {
+        frequencies.push(hz);
+    }
+    // Mali: operating-points-v2 table, referenced by phandle
+    if let Some(table) = node.property("operating-points-v2")
+        .and_then(|p| p.as_usize())
+        .and_then(|phandle| fdt.find_phandle(phandle as u32))
+    {
+        frequencies.extend(table.children().filter_map(|opp| opp.property("opp-hz")?.as_usize()));
+    }
+    // Adreno: qcom,gpu-pwrlevels, possibly nested in qcom,gpu-pwrlevel-bins
+    collect_pwrlevels(node, &mut frequencies);
+    frequencies.sort_unstable_by(|a, b| b.cmp(a));
+    frequencies.dedup();
+
+    info.metadata = serde_json::json!({
+        "source": "devicetree",
+        "node": node.name,
+        "compatible": compatible,
+        "reg_base": reg_base.map(|base| format!("0x{:x}", base)),
+        "clock_frequencies_hz": frequencies,
+        "max_clock_hz": frequencies.first(),
+    });
+    Some(info)
+}
+
+fn is_gpu_node(node: FdtNode) -> bool {
+    let named_gpu = node.name.starts_with("gpu") || node.name.starts_with("qcom,kgsl-3d");
+    named_gpu && node.compatible().is_some_and(|c| c.all().any(|s| gpu_vendor(s).is_some()))
+}
+
+fn gpu_vendor(compatible: &str) -> Option<&'static str> {
+    if compatible.starts_with("arm,mali") {
+        Some("Mali")
+    } else if compatible.starts_with("qcom,adreno") || compatible == "qcom,kgsl-3d0" {
+        Some("Adreno")
+    } else {
+        None
+    }
+}
+
+/// Vendor and model from the most specific recognized `compatible` entry
+fn gpu_info_from_compatible(compatible: &[&str]) -> Option<GpuInfo> {
+    let vendor = compatible.iter().find_map(|c| gpu_vendor(c))?;
+
+    let (model, architecture) = if vendor == "Mali" {
+        let variants: Vec<&str> = compatible.iter().filter_map(|c| c.strip_prefix("arm,mali-")).collect();
+        let architecture = variants.iter()
+            .find(|v| matches!(**v, "utgard" | "midgard" | "bifrost" | "valhall"))
+            .map(|v| v[..1].to_uppercase() + &v[1..]);
+        let model = variants.iter()
+            .find(|v| !matches!(**v, "utgard" | "midgard" | "bifrost" | "valhall"))
+            .map_or_else(|| "Mali".to_string(), |v| format!("Mali-{}", v.to_uppercase()));
+        (model, architecture)
+    } else {
+        // e.g. qcom,adreno-640.1 where 640 is the chip and .1 the patch level
+        let model = compatible.iter()
+            .filter_map(|c| c.strip_prefix("qcom,adreno-"))
+            .find_map(|v| v.split('.').next().filter(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())))
+            .map_or_else(|| "Adreno".to_string(), |n| format!("Adreno {}", n));
+        (model, None)
+    };
+
+    Some(GpuInfo {
+        vendor: vendor.to_string(),
+        model,
+        architecture,
+        driver_version: None,
+        gpu_id: None,
+        cores: None,
+        features: Vec::new(),
+        detected_ioctls: Vec::new(),
+        metadata: serde_json::Value::Null,
+        arch_version: None,
+        core_mask: None,
+        l2_cache_count: None,
+        l2_cache_size: None,
+        bus_width: None,
+        engines_per_core: None,
+        fp32_fmas_per_core: None,
+        fp16_fmas_per_core: None,
+        texels_per_core: None,
+        pixels_per_core: None,
+        raw_ioctl_data: HashMap::new(),
+    })
+}
+
+fn collect_pwrlevels(node: FdtNode, frequencies: &mut Vec<usize>) {
+    for child in node.children() {
+        if let Some(hz) = child.property("qcom,gpu-freq").and_then(|p| p.as_usize()) {
+            frequencies.push(hz);
+        } else if child.name.starts_with("qcom,gpu-pwrlevel") {
+            collect_pwrlevels(child, frequencies);
+        }
+    }
+}
+
+/// Pack an unpacked device tree directory into a DTB
+///
+/// Directories become nodes and files become properties, the layout used by
+/// `/sys/firmware/devicetree/base`.
+fn flatten_dir(root: &Path) -> io::Result<Vec<u8>> {
+    fn push_u32(out: &mut Vec<u8>, value: u32) {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    fn push_padded(out: &mut Vec<u8>, data: &[u8]) {
+        out.extend_from_slice(data);
+        out.resize((out.len() + 3) & !3, 0);
+    }
+    fn walk(dir: &Path, name: &str, structure: &mut Vec<u8>, strings: &mut Vec<u8>) -> io::Result<()> {
+        push_u32(structure, FDT_BEGIN_NODE);
+        push_padded(structure, format!("{}\0", name).as_bytes());
+
+        let mut entries: Vec<_> = std::fs::read_dir(dir)?.flatten().collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        let (dirs, files): (Vec<_>, Vec<_>) = entries.into_iter().partition(|entry| entry.path().is_dir());
+
+        // Properties have to come before child nodes
+        for entry in files {
+            let Ok(value) = std::fs::read(entry.path()) else { continue };
+            let prop_name = entry.file_name().to_string_lossy().into_owned();
+            let offset = strings.len() as u32;
+            strings.extend_from_slice(prop_name.as_bytes());
+            strings.push(0);
+
+            push_u32(structure, FDT_PROP);
+            push_u32(structure, value.len() as u32);
+            push_u32(structure, offset);
+            push_padded(structure, &value);
+        }
+        for entry in dirs {
+            walk(&entry.path(), &entry.file_name().to_string_lossy(), structure, strings)?;
+        }
+
+        push_u32(structure, FDT_END_NODE);
+        Ok(())
+    }
+
+    let (mut structure, mut strings) = (Vec::new(), Vec::new());
+    walk(root, "", &mut structure, &mut strings)?;
+    push_u32(&mut structure, FDT_END);
+
+    // Header, an empty memory reservation map, then the two blocks
+    let struct_offset = 40 + 16;
+    let strings_offset = struct_offset + structure.len();
+    let total = strings_offset + strings.len();
+    let mut blob = Vec::with_capacity(total);
+    for value in [
+        FDT_MAGIC,
+        total as u32,
+        struct_offset as u32,
+        strings_offset as u32,
+        40,
+        17,
+        16,
+        0,
+        strings.len() as u32,
+        structure.len() as u32,
+    ] {
+        push_u32(&mut blob, value);
+    }
+    blob.resize(struct_offset, 0);
+    blob.extend_from_slice(&structure);
+    blob.extend_from_slice(&strings);
+    Ok(blob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tree(root: &Path, files: &[(&str, &[u8])]) {
+        for (path, value) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, value).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_gpu_from_devicetree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("base");
+        write_tree(&dir, &[
+            ("compatible", b"google,oriole\0"),
+            ("soc/#address-cells", &1u32.to_be_bytes()),
+            ("soc/#size-cells", &1u32.to_be_bytes()),
+            ("soc/gpu@1c500000/compatible", b"arm,mali-g78\0arm,mali-valhall\0"),
+            ("soc/gpu@1c500000/reg", &[0x1C, 0x50, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00]),
+            ("soc/gpu@1c500000/operating-points-v2", &7u32.to_be_bytes()),
+            ("soc/opp-table/phandle", &7u32.to_be_bytes()),
+            ("soc/opp-table/opp-151000000/opp-hz", &151_000_000u64.to_be_bytes()),
+            ("soc/opp-table/opp-848000000/opp-hz", &848_000_000u64.to_be_bytes()),
+        ]);
+
+        let info = read_gpu_from_devicetree(dir.to_str()).unwrap();
+        assert_eq!(info.vendor, "Mali");
+        assert_eq!(info.model, "Mali-G78");
+        assert_eq!(info.architecture.as_deref(), Some("Valhall"));
+        assert_eq!(info.metadata["reg_base"], "0x1c500000");
+        assert_eq!(info.metadata["max_clock_hz"], 848_000_000);
+        assert_eq!(info.metadata["clock_frequencies_hz"], serde_json::json!([848_000_000, 151_000_000]));
+
+        // The same tree as a blob
+        let dtb = dir.with_extension("dtb");
+        std::fs::write(&dtb, flatten_dir(&dir).unwrap()).unwrap();
+        assert_eq!(read_gpu_from_devicetree(dtb.to_str()).unwrap().metadata, info.metadata);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        write_tree(&dir, &[
+            ("soc/qcom,kgsl-3d0@3d00000/compatible", b"qcom,adreno-640.1\0qcom,adreno\0"),
+            ("soc/qcom,kgsl-3d0@3d00000/qcom,gpu-pwrlevels/qcom,gpu-pwrlevel@0/qcom,gpu-freq", &585_000_000u32.to_be_bytes()),
+            ("soc/qcom,kgsl-3d0@3d00000/qcom,gpu-pwrlevels/qcom,gpu-pwrlevel@1/qcom,gpu-freq", &257_000_000u32.to_be_bytes()),
+            ("soc/qcom,adreno-smmu@3da0000/compatible", b"qcom,adreno-smmu\0"),
+        ]);
+        let info = read_gpu_from_devicetree(dir.to_str()).unwrap();
+        assert_eq!((info.vendor.as_str(), info.model.as_str()), ("Adreno", "Adreno 640"));
+        assert_eq!(info.metadata["max_clock_hz"], 585_000_000);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(read_gpu_from_devicetree(dir.to_str()).is_none());
+    }
+}
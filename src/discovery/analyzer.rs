@@ -1,11 +1,17 @@
 //! Advanced analysis tools for IOCTL discovery
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io;
 use libc;
 use std::os::unix::io::RawFd;
 use crate::discovery::{Verbosity, IoctlResult};
 
+/// Byte offset -> whether setting that byte changed the IOCTL outcome
+pub type DataDependencyMap = BTreeMap<usize, bool>;
+
+/// Number of leading bytes probed by `DetailedAnalyzer::analyze_data_dependency`
+const DATA_DEPENDENCY_BYTES: usize = 8;
+
 /// Detailed analyzer for specific IOCTL types
 pub struct DetailedAnalyzer {
     fd: RawFd,
@@ -49,10 +55,10 @@ impl DetailedAnalyzer {
                     buffer[0..4].copy_from_slice(&magic);
                 }
 
-                let cmd = ((3u32 << 30) | ((size as u32) << 16) |
-                          ((self.type_to_test as u32) << 8) | (nr as u32)) as i32;
+                let cmd = (3u32 << 30) | ((size as u32) << 16) |
+                          ((self.type_to_test as u32) << 8) | (nr as u32);
 
-                let result = unsafe { libc::ioctl(self.fd, cmd, buffer.as_mut_ptr()) };
+                let result = unsafe { libc::ioctl(self.fd, cmd as _, buffer.as_mut_ptr()) };
                 let errno = if result < 0 {
                     io::Error::last_os_error().raw_os_error().unwrap_or(-1)
                 } else {
@@ -81,6 +87,53 @@ impl DetailedAnalyzer {
 
         size_map
     }
+
+    /// Find which of the first 8 buffer bytes the kernel reads
+    ///
+    /// Calls the read/write IOCTL once with an all-zero buffer and once per
+    /// offset with only that byte set to `0xFF`. An offset affects the result
+    /// when errno or the return value differs from the zero baseline, which
+    /// hints at a field the kernel validates.
+    pub fn analyze_data_dependency(&self, nr: u8, size: u16) -> DataDependencyMap {
+        let cmd = (3u32 << 30) | ((size as u32) << 16) |
+                  ((self.type_to_test as u32) << 8) | (nr as u32);
+        let call = |buffer: &mut [u8]| {
+            let result = unsafe { libc::ioctl(self.fd, cmd as _, buffer.as_mut_ptr()) };
+            let errno = if result < 0 {
+                io::Error::last_os_error().raw_os_error().unwrap_or(-1)
+            } else {
+                0
+            };
+            (result, errno)
+        };
+        self.data_dependency_with(nr, size, call)
+    }
+
+    /// `analyze_data_dependency` with the IOCTL call supplied by the caller
+    ///
+    /// `call` gets the argument buffer and must return `(return value, errno)`.
+    pub(crate) fn data_dependency_with<F>(&self, nr: u8, size: u16, mut call: F) -> DataDependencyMap
+    where
+        F: FnMut(&mut [u8]) -> (i32, i32),
+    {
+        let baseline = call(&mut vec![0u8; size as usize]);
+        let mut map = DataDependencyMap::new();
+        for offset in 0..DATA_DEPENDENCY_BYTES.min(size as usize) {
+            let mut buffer = vec![0u8; size as usize];
+            buffer[offset] = 0xFF;
+            let outcome = call(&mut buffer);
+            let affects_result = outcome != baseline;
+
+            if self.verbosity.is_at_least(Verbosity::Detailed) {
+                println!("  nr=0x{:02x} offset {}: result={} errno={}{}",
+                        nr, offset, outcome.0, outcome.1,
+                        if affects_result { " ← affects result" } else { "" });
+            }
+            map.insert(offset, affects_result);
+        }
+
+        map
+    }
 }
 
 /// Pattern analyzer for IOCTL results
@@ -88,6 +141,12 @@ pub struct PatternAnalyzer {
     patterns: HashMap<String, Vec<u8>>,
 }
 
+impl Default for PatternAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PatternAnalyzer {
     /// Create new pattern analyzer
     pub fn new() -> Self {
@@ -178,4 +237,27 @@ impl PatternAnalyzer {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::io::AsRawFd;
+
+    #[test]
+    fn test_analyze_data_dependency() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let analyzer = DetailedAnalyzer::new(file.as_raw_fd(), b'T', Vec::new(), Verbosity::Minimal);
+
+        // /dev/null fails every IOCTL with ENOTTY whatever the buffer holds
+        let map = analyzer.analyze_data_dependency(0x01, 16);
+        assert_eq!(map.len(), DATA_DEPENDENCY_BYTES);
+        assert!(map.values().all(|affects| !affects));
+
+        // A driver that rejects a non-zero flags byte at offset 2
+        let map = analyzer.data_dependency_with(0x01, 4, |buffer| {
+            if buffer[2] != 0 { (-1, libc::EINVAL) } else { (0, 0) }
+        });
+        assert_eq!(map, DataDependencyMap::from([(0, false), (1, false), (2, true), (3, false)]));
+    }
+}
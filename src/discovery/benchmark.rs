@@ -0,0 +1,105 @@
+//! IOCTL throughput measurement
+//!
+//! Helps pick a realistic `max_calls_per_second` for a device by timing raw
+//! IOCTL calls without any rate limiting.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::discovery::{DiscoveryOptions, IoctlDiscovery, Verbosity};
+
+/// Latency and throughput statistics of a benchmark run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    /// Calls completed per second over the whole run
+    pub calls_per_second: f64,
+    /// Fastest call in microseconds
+    pub min_latency_us: u64,
+    /// Slowest call in microseconds
+    pub max_latency_us: u64,
+    /// Median call latency in microseconds
+    pub p50_latency_us: u64,
+    /// 99th percentile call latency in microseconds
+    pub p99_latency_us: u64,
+}
+
+/// Runs repeated IOCTL calls against a device and measures their latency
+pub struct BenchmarkRunner;
+
+impl BenchmarkRunner {
+    /// Call `cmd` with a null argument `iterations` times on `device`
+    ///
+    /// Rate limiting is disabled for the run; errors returned by the IOCTL
+    /// itself (e.g. EFAULT) are part of the measurement.
+    pub fn run(device: &str, cmd: u32, iterations: u32) -> io::Result<BenchmarkResult> {
+        if iterations == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "iterations must be at least 1"));
+        }
+
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            max_total_calls: None,
+            ..Default::default()
+        };
+        let discovery = IoctlDiscovery::open(device, options)?;
+
+        let mut latencies = Vec::with_capacity(iterations as usize);
+        let started = Instant::now();
+        for _ in 0..iterations {
+            let call_start = Instant::now();
+            discovery.test_ioctl(cmd, 0)?;
+            latencies.push(call_start.elapsed());
+        }
+        let total = started.elapsed();
+
+        Ok(Self::summarize(latencies, total))
+    }
+
+    /// Compute statistics from individual call latencies
+    fn summarize(mut latencies: Vec<Duration>, total: Duration) -> BenchmarkResult {
+        latencies.sort_unstable();
+        let micros = |d: Duration| d.as_micros() as u64;
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p * latencies.len() as f64).ceil() as usize;
+            micros(latencies[rank.clamp(1, latencies.len()) - 1])
+        };
+
+        BenchmarkResult {
+            calls_per_second: latencies.len() as f64 / total.as_secs_f64().max(f64::EPSILON),
+            min_latency_us: micros(latencies[0]),
+            max_latency_us: micros(latencies[latencies.len() - 1]),
+            p50_latency_us: percentile(0.50),
+            p99_latency_us: percentile(0.99),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_percentiles() {
+        let latencies: Vec<Duration> = (1..=100).rev().map(Duration::from_micros).collect();
+        let result = BenchmarkRunner::summarize(latencies, Duration::from_millis(50));
+
+        assert_eq!(result.min_latency_us, 1);
+        assert_eq!(result.max_latency_us, 100);
+        assert_eq!(result.p50_latency_us, 50);
+        assert_eq!(result.p99_latency_us, 99);
+        assert_eq!(result.calls_per_second, 2000.0);
+    }
+
+    #[test]
+    fn test_run_on_null_device() {
+        let result = BenchmarkRunner::run("/dev/null", 0xC0048000, 50).unwrap();
+        assert!(result.calls_per_second > 0.0);
+        assert!(result.min_latency_us <= result.p50_latency_us);
+        assert!(result.p99_latency_us <= result.max_latency_us);
+
+        assert!(BenchmarkRunner::run("/dev/null", 0xC0048000, 0).is_err());
+    }
+}
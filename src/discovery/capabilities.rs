@@ -0,0 +1,85 @@
+//! Capability summary derived from discovered IOCTLs
+//!
+//! Maps well-known (type, nr) pairs of the Mali kbase, Qualcomm KGSL and DRM
+//! UAPIs to a compact bitmask.
+
+use crate::discovery::IoctlResult;
+
+bitflags::bitflags! {
+    /// Device capabilities inferred from a discovery scan
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct DeviceCapabilityFlags: u32 {
+        /// Driver version can be queried
+        const VERSION_QUERY = 0x01;
+        /// GPU ID / chip ID can be queried
+        const GPU_ID_QUERY = 0x02;
+        /// GPU feature / property blocks can be queried
+        const FEATURE_QUERY = 0x04;
+        /// Jobs or command buffers can be submitted
+        const JOB_SUBMIT = 0x08;
+        /// GPU memory can be allocated
+        const MEMORY_ALLOC = 0x10;
+        /// GPU contexts can be created
+        const CONTEXT_CREATE = 0x20;
+        /// Hardware performance counters are available
+        const HW_COUNTERS = 0x40;
+    }
+}
+
+/// Known IOCTL signatures as (type, nr, capabilities)
+const KNOWN_SIGNATURES: &[(u8, u8, DeviceCapabilityFlags)] = &[
+    // Mali kbase (mali_kbase_ioctl.h, type 0x80)
+    (0x80, 0x00, DeviceCapabilityFlags::VERSION_QUERY), // VERSION_CHECK
+    (0x80, 0x02, DeviceCapabilityFlags::JOB_SUBMIT),    // JOB_SUBMIT
+    (0x80, 0x03, DeviceCapabilityFlags::GPU_ID_QUERY.union(DeviceCapabilityFlags::FEATURE_QUERY)), // GET_GPUPROPS
+    (0x80, 0x05, DeviceCapabilityFlags::MEMORY_ALLOC),  // MEM_ALLOC
+    (0x80, 0x08, DeviceCapabilityFlags::HW_COUNTERS),   // HWCNT_READER_SETUP
+    // Qualcomm KGSL (msm_kgsl.h, type 0x09)
+    (0x09, 0x02, DeviceCapabilityFlags::VERSION_QUERY
+        .union(DeviceCapabilityFlags::GPU_ID_QUERY)
+        .union(DeviceCapabilityFlags::FEATURE_QUERY)), // DEVICE_GETPROPERTY
+    (0x09, 0x10, DeviceCapabilityFlags::JOB_SUBMIT),     // RINGBUFFER_ISSUEIBCMDS
+    (0x09, 0x13, DeviceCapabilityFlags::CONTEXT_CREATE), // DRAWCTXT_CREATE
+    (0x09, 0x34, DeviceCapabilityFlags::MEMORY_ALLOC),   // GPUMEM_ALLOC_ID
+    (0x09, 0x38, DeviceCapabilityFlags::HW_COUNTERS),    // PERFCOUNTER_GET
+    (0x09, 0x45, DeviceCapabilityFlags::MEMORY_ALLOC),   // GPUOBJ_ALLOC
+    (0x09, 0x4A, DeviceCapabilityFlags::JOB_SUBMIT),     // GPU_COMMAND
+    // DRM core (drm.h, type 'd')
+    (0x64, 0x00, DeviceCapabilityFlags::VERSION_QUERY), // DRM_IOCTL_VERSION
+];
+
+impl DeviceCapabilityFlags {
+    /// Derive capabilities from the IOCTLs that exist on the device
+    pub fn from_results(results: &[IoctlResult]) -> Self {
+        results.iter()
+            .filter(|r| r.is_valid())
+            .flat_map(|r| {
+                KNOWN_SIGNATURES.iter()
+                    .filter(move |(ty, nr, _)| *ty == r.ty && *nr == r.nr)
+                    .map(|(_, _, flags)| *flags)
+            })
+            .fold(Self::empty(), |acc, flags| acc | flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::Interpretation;
+
+    #[test]
+    fn test_flags_from_mali_results() {
+        let result = IoctlResult::from_cmd_for_test;
+        let results = vec![
+            result(0xC0048000, Interpretation::Exists),
+            result(0x40108003, Interpretation::Success),
+            result(0xC0208005, Interpretation::NotExist),
+        ];
+
+        let caps = DeviceCapabilityFlags::from_results(&results);
+        assert!(caps.contains(DeviceCapabilityFlags::VERSION_QUERY));
+        assert!(caps.contains(DeviceCapabilityFlags::FEATURE_QUERY | DeviceCapabilityFlags::GPU_ID_QUERY));
+        assert!(!caps.contains(DeviceCapabilityFlags::MEMORY_ALLOC));
+        assert!(DeviceCapabilityFlags::from_results(&[]).is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! Byte-level comparison of IOCTL buffers before and after a call
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Bytes shown per row by [`format_diff`]
+const BYTES_PER_ROW: usize = 16;
+
+/// A single byte the kernel changed in an IOCTL buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferChange {
+    /// Offset of the byte within the buffer
+    pub offset: usize,
+    /// Value before the call
+    pub before_byte: u8,
+    /// Value after the call
+    pub after_byte: u8,
+}
+
+/// Compare two buffers and list every byte that differs
+///
+/// Bytes past the end of the shorter buffer are compared against zero.
+pub fn diff_buffers(before: &[u8], after: &[u8]) -> Vec<BufferChange> {
+    (0..before.len().max(after.len()))
+        .filter_map(|offset| {
+            let before_byte = before.get(offset).copied().unwrap_or(0);
+            let after_byte = after.get(offset).copied().unwrap_or(0);
+            (before_byte != after_byte).then_some(BufferChange { offset, before_byte, after_byte })
+        })
+        .collect()
+}
+
+/// Format changes as a hex diff in the style of `xxd -d`
+///
+/// Every row that contains a change is printed twice: `-` with the old
+/// bytes and `+` with the new ones. Unchanged bytes are shown as `..`.
+pub fn format_diff(changes: &[BufferChange]) -> String {
+    let mut rows: Vec<(usize, Vec<&BufferChange>)> = Vec::new();
+    for change in changes {
+        let row = change.offset / BYTES_PER_ROW * BYTES_PER_ROW;
+        match rows.last_mut() {
+            Some((start, row_changes)) if *start == row => row_changes.push(change),
+            _ => rows.push((row, vec![change])),
+        }
+    }
+
+    let mut out = String::new();
+    for (start, row_changes) in rows {
+        for sign in ['-', '+'] {
+            let _ = write!(out, "{:08}: {}", start, sign);
+            for col in 0..BYTES_PER_ROW {
+                match row_changes.iter().find(|c| c.offset == start + col) {
+                    Some(c) => {
+                        let byte = if sign == '-' { c.before_byte } else { c.after_byte };
+                        let _ = write!(out, " {:02x}", byte);
+                    }
+                    None => out.push_str(" .."),
+                }
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_buffers() {
+        let changes = diff_buffers(&[0, 0, 0, 0], &[0, 7, 0, 0, 1]);
+        assert_eq!(changes, vec![
+            BufferChange { offset: 1, before_byte: 0, after_byte: 7 },
+            BufferChange { offset: 4, before_byte: 0, after_byte: 1 },
+        ]);
+        assert!(diff_buffers(&[1, 2], &[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn test_format_diff() {
+        let mut after = vec![0u8; 20];
+        after[1] = 0xab;
+        after[17] = 0x01;
+        let text = format_diff(&diff_buffers(&[0; 20], &after));
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "00000000: - .. 00 .. .. .. .. .. .. .. .. .. .. .. .. .. ..");
+        assert_eq!(lines[1], "00000000: + .. ab .. .. .. .. .. .. .. .. .. .. .. .. .. ..");
+        assert!(lines[3].starts_with("00000016: + .. 01 .."));
+        assert!(format_diff(&[]).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! DRM driver identification via `DRM_IOCTL_VERSION`
+//!
+//! DRI render nodes are shared by many vendors. Asking the DRM core for the
+//! driver name first avoids flooding e.g. Intel/AMD drivers with Mali IOCTLs.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// `DRM_IOCTL_VERSION` on 64-bit targets: `_IOWR('d', 0x00, struct drm_version)`
+pub const DRM_IOCTL_VERSION: u32 = 0xC0406400;
+
+/// Upper bound for driver strings returned by the kernel
+const MAX_DRM_STRING_LEN: usize = 4096;
+
+/// Kernel `struct drm_version` layout
+#[repr(C)]
+struct DrmVersionRaw {
+    version_major: i32,
+    version_minor: i32,
+    version_patchlevel: i32,
+    name_len: usize,
+    name: *mut u8,
+    date_len: usize,
+    date: *mut u8,
+    desc_len: usize,
+    desc: *mut u8,
+}
+
+/// DRM driver information reported by `DRM_IOCTL_VERSION`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrmDriverInfo {
+    /// Driver name (e.g. "i915", "amdgpu", "msm", "panfrost")
+    pub name: String,
+    /// Driver description
+    pub desc: String,
+    /// Driver date string
+    pub date: String,
+    /// Driver major version
+    pub version_major: i32,
+    /// Driver minor version
+    pub version_minor: i32,
+    /// Driver patch level
+    pub version_patchlevel: i32,
+}
+
+/// IOCTL types to scan for a given DRM driver name
+///
+/// Returns an empty slice for desktop drivers (Intel/AMD) that should not be
+/// probed with embedded GPU IOCTLs.
+pub fn scan_types_for_drm_driver(name: &str) -> &'static [u8] {
+    match name {
+        "mali" | "panfrost" | "panthor" => super::MALI_SCAN_TYPES,
+        "msm" | "kgsl" => super::ADRENO_SCAN_TYPES,
+        "i915" | "xe" | "amdgpu" | "radeon" => &[],
+        _ => super::MALI_SCAN_TYPES,
+    }
+}
+
+/// Command number of `DRM_IOCTL_VERSION` for the current target's struct layout
+fn drm_ioctl_version_cmd() -> u32 {
+    let size = std::mem::size_of::<DrmVersionRaw>() as u32;
+    (3u32 << 30) | (size << 16) | (0x64 << 8)
+}
+
+/// Query the DRM driver version using the given ioctl function
+///
+/// `ioctl` is called with the command number and argument pointer and must
+/// return `(return value, errno)`.
+pub(crate) fn query_version<F>(mut ioctl: F) -> io::Result<DrmDriverInfo>
+where
+    F: FnMut(u32, usize) -> io::Result<(i32, i32)>,
+{
+    let cmd = drm_ioctl_version_cmd();
+    let mut raw = DrmVersionRaw {
+        version_major: 0,
+        version_minor: 0,
+        version_patchlevel: 0,
+        name_len: 0,
+        name: std::ptr::null_mut(),
+        date_len: 0,
+        date: std::ptr::null_mut(),
+        desc_len: 0,
+        desc: std::ptr::null_mut(),
+    };
+
+    // First call: kernel fills in the string lengths
+    let (result, errno) = ioctl(cmd, &mut raw as *mut DrmVersionRaw as usize)?;
+    if result < 0 {
+        return Err(io::Error::from_raw_os_error(errno));
+    }
+
+    let mut name = vec![0u8; raw.name_len.min(MAX_DRM_STRING_LEN)];
+    let mut date = vec![0u8; raw.date_len.min(MAX_DRM_STRING_LEN)];
+    let mut desc = vec![0u8; raw.desc_len.min(MAX_DRM_STRING_LEN)];
+    raw.name_len = name.len();
+    raw.name = name.as_mut_ptr();
+    raw.date_len = date.len();
+    raw.date = date.as_mut_ptr();
+    raw.desc_len = desc.len();
+    raw.desc = desc.as_mut_ptr();
+
+    // Second call: kernel copies the strings into our buffers
+    let (result, errno) = ioctl(cmd, &mut raw as *mut DrmVersionRaw as usize)?;
+    if result < 0 {
+        return Err(io::Error::from_raw_os_error(errno));
+    }
+
+    let to_string = |buf: &[u8], len: usize| {
+        String::from_utf8_lossy(&buf[..len.min(buf.len())])
+            .trim_end_matches('\0')
+            .to_string()
+    };
+
+    Ok(DrmDriverInfo {
+        name: to_string(&name, raw.name_len),
+        desc: to_string(&desc, raw.desc_len),
+        date: to_string(&date, raw.date_len),
+        version_major: raw.version_major,
+        version_minor: raw.version_minor,
+        version_patchlevel: raw.version_patchlevel,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_drm_ioctl_version_cmd() {
+        assert_eq!(drm_ioctl_version_cmd(), DRM_IOCTL_VERSION);
+    }
+
+    #[test]
+    fn test_scan_types_for_drm_driver() {
+        assert!(scan_types_for_drm_driver("i915").is_empty());
+        assert!(scan_types_for_drm_driver("amdgpu").is_empty());
+        assert_eq!(scan_types_for_drm_driver("msm"), crate::discovery::ADRENO_SCAN_TYPES);
+        assert_eq!(scan_types_for_drm_driver("panfrost"), crate::discovery::MALI_SCAN_TYPES);
+    }
+}
@@ -0,0 +1,164 @@
+//! Profile generation from repeated discovery runs
+//!
+//! Flaky IOCTLs that only sometimes answer `Exists` are filtered out by
+//! requiring them to show up in most samples.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::discovery::{DiscoveryResult, Interpretation};
+use crate::profiles::{IoctlDefinition, IoctlProfile};
+
+/// Share of samples an IOCTL has to work in to become a detection IOCTL
+const MIN_SAMPLE_RATIO: f32 = 0.8;
+
+/// Builds an `IoctlProfile` from several discovery runs on the same device
+#[derive(Debug, Default)]
+pub struct ProfileGenerator {
+    samples: usize,
+    // cmd -> (definition from the first sample it worked in, samples it worked in)
+    working: BTreeMap<u32, (IoctlDefinition, usize)>,
+}
+
+impl ProfileGenerator {
+    /// Create an empty generator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record which IOCTLs returned `Success` or `Exists` in `result`
+    ///
+    /// Potentially dangerous IOCTLs are ignored.
+    pub fn add_sample(&mut self, result: DiscoveryResult) {
+        self.samples += 1;
+
+        let mut seen = HashSet::new();
+        for r in result.results() {
+            if r.is_potentially_dangerous
+                || !matches!(r.interpretation, Interpretation::Success | Interpretation::Exists)
+                || !seen.insert(r.cmd)
+            {
+                continue;
+            }
+
+            self.working.entry(r.cmd)
+                .or_insert_with(|| {
+                    let definition = IoctlDefinition {
+                        name: format!("unknown_{:02x}_{:02x}", r.ty, r.nr),
+                        cmd: r.cmd,
+                        buffer_size: r.discovered_size.unwrap_or(r.size),
+                        parser: "parse_generic".to_string(),
+                        params: serde_json::json!({}),
+                    };
+                    (definition, 0)
+                })
+                .1 += 1;
+        }
+    }
+
+    /// Number of samples added so far
+    pub fn sample_count(&self) -> usize {
+        self.samples
+    }
+
+    /// Build a profile from the IOCTLs that worked in at least 80% of samples
+    ///
+    /// `sample_confidence` is the mean share of samples the chosen detection
+    /// IOCTLs worked in, or 0.0 when none qualified.
+    pub fn generate_profile(&self, vendor: &str, model: &str) -> IoctlProfile {
+        let mut ratio_sum = 0.0;
+        let detection_ioctls: Vec<IoctlDefinition> = self.working.values()
+            .filter_map(|(definition, count)| {
+                let ratio = *count as f32 / self.samples as f32;
+                (ratio >= MIN_SAMPLE_RATIO).then(|| {
+                    ratio_sum += ratio;
+                    definition.clone()
+                })
+            })
+            .collect();
+
+        let confidence = if detection_ioctls.is_empty() {
+            0.0
+        } else {
+            ratio_sum / detection_ioctls.len() as f32
+        };
+
+        IoctlProfile {
+            vendor: vendor.to_string(),
+            model: model.to_string(),
+            version: "1.0.0".to_string(),
+            min_kernel_version: None,
+            compatible_kernels: Vec::new(),
+            deprecated: false,
+            superseded_by: None,
+            parent_profile: None,
+            sample_confidence: Some(confidence),
+            sha256sum: None,
+            detection_ioctls,
+            version_ioctl: None,
+            gpu_info_ioctl: None,
+            features_ioctl: None,
+            metadata: serde_json::json!({
+                "confirmed_by": "iodisco_profile_generator",
+                "samples": self.samples,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{DiscoveryOptions, DiscoverySnapshot, IoctlResult};
+    use std::time::Duration;
+
+    fn sample(working: &[u32]) -> DiscoveryResult {
+        let results = [0xC0048000, 0xC0108001, 0xC0208002]
+            .iter()
+            .map(|&cmd| {
+                let interpretation = if working.contains(&cmd) {
+                    Interpretation::Exists
+                } else {
+                    Interpretation::NotExist
+                };
+                IoctlResult::from_cmd_for_test(cmd, interpretation)
+            })
+            .collect();
+
+        DiscoverySnapshot {
+            device: "/dev/mali0".to_string(),
+            options: DiscoveryOptions::default(),
+            results,
+            call_count: 3,
+            elapsed: Duration::ZERO,
+        }
+        .to_discovery_result()
+    }
+
+    #[test]
+    fn test_generate_profile_drops_flaky_ioctls() {
+        let mut generator = ProfileGenerator::new();
+        for i in 0..5 {
+            // 0xC0108001 only works in 3 of 5 runs, 0xC0208002 in 4 of 5
+            let mut working = vec![0xC0048000];
+            if i < 3 {
+                working.push(0xC0108001);
+            }
+            if i < 4 {
+                working.push(0xC0208002);
+            }
+            generator.add_sample(sample(&working));
+        }
+        assert_eq!(generator.sample_count(), 5);
+
+        let profile = generator.generate_profile("ARM", "Mali-G71");
+        let cmds: Vec<u32> = profile.detection_ioctls.iter().map(|d| d.cmd).collect();
+        assert_eq!(cmds, vec![0xC0048000, 0xC0208002]);
+        assert_eq!(profile.detection_ioctls[1].buffer_size, 32);
+        assert!((profile.sample_confidence.unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(profile.metadata["samples"], 5);
+
+        let empty = ProfileGenerator::new().generate_profile("ARM", "Mali-G71");
+        assert!(empty.detection_ioctls.is_empty());
+        assert_eq!(empty.sample_confidence, Some(0.0));
+    }
+}
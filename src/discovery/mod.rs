@@ -1,27 +1,147 @@
 //! IOCTL discovery engine for GPU devices
 
 mod analyzer;
+mod benchmark;
+mod capabilities;
+mod diff;
+mod drm;
+#[cfg(any(feature = "mali", feature = "adreno"))]
+mod generator;
+#[cfg(feature = "profiler")]
+mod profiler;
+mod progress;
 mod scanner;
+#[cfg(all(feature = "seccomp", any(target_os = "linux", target_os = "android")))]
+mod seccomp;
 
 use serde::{Deserialize, Serialize};
 
-pub use analyzer::{DetailedAnalyzer, PatternAnalyzer};
-pub use scanner::{DiscoveryOptions, Interpretation, IoctlDiscovery, IoctlResult, IoctlTestResult};
+use crate::error::DiscoveryError;
 
+pub use analyzer::{DataDependencyMap, DetailedAnalyzer, PatternAnalyzer};
+pub use benchmark::{BenchmarkResult, BenchmarkRunner};
+pub use capabilities::DeviceCapabilityFlags;
+pub use diff::{diff_buffers, format_diff, BufferChange};
+pub use drm::{scan_types_for_drm_driver, DrmDriverInfo, DRM_IOCTL_VERSION};
+#[cfg(any(feature = "mali", feature = "adreno"))]
+pub use generator::ProfileGenerator;
+#[cfg(feature = "profiler")]
+pub use profiler::{FieldGuess, FieldType, StructLayout, StructProfiler};
+#[cfg(all(feature = "seccomp", any(target_os = "linux", target_os = "android")))]
+pub use seccomp::SeccompFilter;
+pub use progress::{NullProgressReporter, ProgressOutput, ProgressReporter, StdoutProgressReporter, StringWriter, WriterProgressReporter};
+pub use scanner::{Anomaly, CancelHandle, CancelToken, DiscoveryOptions, DiscoverySnapshot, DiscoverySummary, DriverInfo, Interpretation, IoctlDiscovery, IoctlResult, IoctlTestResult, JsonMetadata, JsonOutput, JsonStatistics, ScanCoverage, ScanStopReason, TimeoutBehavior};
+
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::io;
+use std::time::{Duration, Instant};
+
+/// IOCTL types scanned on Mali (and unknown) devices
+pub const MALI_SCAN_TYPES: &[u8] = &[0x80, 0x64, 0x46, 0x4B, 0x54, 0x6D];
+
+/// IOCTL types scanned on Adreno devices (KGSL and DRM)
+pub const ADRENO_SCAN_TYPES: &[u8] = &[0x09, 0x64];
+
+/// IOCTL types to scan on `device`, judged by its node name alone
+///
+/// KGSL nodes get `ADRENO_SCAN_TYPES`, everything else `MALI_SCAN_TYPES`.
+/// DRI render nodes need [`scan_types_for_drm_driver`] on top.
+pub fn scan_types_for_device_path(device: &str) -> &'static [u8] {
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    {
+        use crate::mappings::GpuVendor;
+        match GpuVendor::from_device_path(device) {
+            GpuVendor::Adreno => ADRENO_SCAN_TYPES,
+            _ => MALI_SCAN_TYPES,
+        }
+    }
+    #[cfg(not(any(feature = "mali", feature = "adreno")))]
+    {
+        let _ = device;
+        MALI_SCAN_TYPES
+    }
+}
+
+/// IOCTL types to scan on the opened `device`
+///
+/// Like [`scan_types_for_device_path`], but DRI render nodes are shared by
+/// many vendors and ask the DRM core first; its answer is returned as well.
+pub fn scan_types_for_device(discovery: &IoctlDiscovery, device: &str) -> (&'static [u8], Option<DrmDriverInfo>) {
+    if device.starts_with("/dev/dri/") {
+        if let Ok(info) = discovery.query_drm_version() {
+            return (scan_types_for_drm_driver(&info.name), Some(info));
+        }
+    }
+    (scan_types_for_device_path(device), None)
+}
 
 /// Scan a GPU device for available IOCTLs
 ///
 /// # Arguments
 /// * `device_path` - Optional device path. If None, auto-detects.
-/// * `config` - Optional discovery configuration
+/// * `config` - Discovery configuration: a `DiscoveryConfig`, `()` for the
+///   defaults, or a preset name such as `"quick"`
+/// * `cancel` - Optional token from `CancelToken::new()` to stop the scan early;
+///   results collected so far are still returned
 ///
 /// # Returns
 /// Discovery result containing all found IOCTLs
 pub fn scan_device(
+    device_path: Option<&str>,
+    config: impl Into<DiscoveryConfig>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> io::Result<DiscoveryResult> {
+    let config = config.into();
+    // Debug verbosity keeps the classic dots on stdout
+    if config.verbosity.is_at_least(Verbosity::Debug) {
+        scan_device_reporting(device_path, config, cancel, &StdoutProgressReporter)
+    } else {
+        scan_device_reporting(device_path, config, cancel, &NullProgressReporter)
+    }
+}
+
+/// Scan every device from [`find_gpu_devices`] concurrently
+///
+/// Each device gets its own `IoctlDiscovery`. Results are returned as
+/// `(device_path, result)` pairs in the order the devices were found;
+/// `config` defaults to `DiscoveryConfig::default()` when `None`.
+#[cfg(feature = "parallel")]
+pub fn scan_devices_parallel(config: Option<DiscoveryConfig>) -> Vec<(String, io::Result<DiscoveryResult>)> {
+    scan_paths_parallel(find_gpu_devices(), config.unwrap_or_default())
+}
+
+#[cfg(feature = "parallel")]
+fn scan_paths_parallel(devices: Vec<String>, config: DiscoveryConfig) -> Vec<(String, io::Result<DiscoveryResult>)> {
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    devices
+        .into_par_iter()
+        .map(|device| {
+            let result = scan_device(Some(&device), config.clone(), None);
+            (device, result)
+        })
+        .collect()
+}
+
+/// Scan a GPU device like [`scan_device`], sending structured progress events to `reporter`
+///
+/// `config` defaults to `DiscoveryConfig::default()` when `None`.
+pub fn scan_device_with_progress(
     device_path: Option<&str>,
     config: Option<DiscoveryConfig>,
+    reporter: &dyn ProgressReporter,
+) -> io::Result<DiscoveryResult> {
+    scan_device_reporting(device_path, config.unwrap_or_default(), None, reporter)
+}
+
+fn scan_device_reporting(
+    device_path: Option<&str>,
+    options: DiscoveryConfig,
+    cancel: Option<Arc<AtomicBool>>,
+    reporter: &dyn ProgressReporter,
 ) -> io::Result<DiscoveryResult> {
     let device = if let Some(path) = device_path {
         path.to_string()
@@ -30,26 +150,43 @@ pub fn scan_device(
             .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No GPU device found"))?
     };
 
-    let options = config.unwrap_or_default();
+    // Keep a copy for our own messages; it shares `progress_output` with the scanner
+    let options: DiscoveryOptions = options.into();
+    let mut discovery = IoctlDiscovery::open(&device, options.clone())?;
+    discovery.warm_up()?;
 
-    // Clone verbosity before moving options
-    let verbosity = options.verbosity;
+    let (types_to_scan, drm_driver) = scan_types_for_device(&discovery, &device);
+    if let Some(info) = drm_driver.as_ref().filter(|_| types_to_scan.is_empty()) {
+        if options.verbosity.is_at_least(Verbosity::Normal) {
+            options.write_progress(true, format_args!("ℹ️  DRM driver '{}' detected - skipping IOCTL scan", info.name));
+        }
+    }
 
-    let mut discovery = IoctlDiscovery::open(&device, options.into())?;
+    let cancel = cancel.unwrap_or_default();
+    let mut type_scan_timings = HashMap::new();
+    for &ty in types_to_scan {
+        let started = Instant::now();
+        let outcome = discovery.scan_type_with_progress(ty, Some(&cancel), reporter);
+        type_scan_timings.insert(ty, started.elapsed());
 
-    // Scan common Mali types
-    let types_to_scan = vec![0x80u8, 0x64, 0x46, 0x4B, 0x54, 0x6D];
-    for ty in types_to_scan {
-        if let Err(e) = discovery.scan_type(ty) {
-            // Use the cloned verbosity
-            if verbosity.is_at_least(Verbosity::Normal) {
-                eprintln!("Warning: Failed to scan type 0x{:02x}: {}", ty, e);
+        match outcome {
+            Ok(ScanStopReason::Completed) => {}
+            Ok(ScanStopReason::Cancelled)
+            | Ok(ScanStopReason::RateLimitExceeded)
+            | Ok(ScanStopReason::TimedOut) => break,
+            // A lost device will not come back for the next type
+            Ok(ScanStopReason::Error(e)) => return Err(e),
+            Err(e) => {
+                if options.verbosity.is_at_least(Verbosity::Normal) {
+                    let e = DiscoveryError::from(e).context(format!("scanning type 0x{:02x} on {}", ty, device));
+                    options.write_progress(true, format_args!("Warning: {}", e));
+                }
+                // Continue with next type
             }
-            // Continue with next type
         }
     }
 
-    Ok(DiscoveryResult { discovery })
+    Ok(DiscoveryResult { discovery, drm_driver, type_scan_timings })
 }
 
 /// Find all GPU devices on the system
@@ -72,6 +209,11 @@ pub fn find_gpu_devices() -> Vec<String> {
         }
     }
 
+    // Check Vivante (NXP i.MX) devices
+    if fs::metadata(VIVANTE_DEVICE).is_ok() {
+        devices.push(VIVANTE_DEVICE.to_string());
+    }
+
     // Check DRI render nodes
     for i in 128..138 {
         let path = format!("/dev/dri/renderD{}", i);
@@ -80,18 +222,167 @@ pub fn find_gpu_devices() -> Vec<String> {
         }
     }
 
+    // Nodes of vendors registered by other crates
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    for path in crate::vendor::VendorRegistry::device_paths() {
+        if !devices.contains(&path) {
+            devices.push(path);
+        }
+    }
+
     devices
 }
 
 /// Find the most likely GPU device
 pub fn find_gpu_device() -> Option<String> {
     let devices = find_gpu_devices();
-    devices.into_iter().next()
+
+    // Prefer Mali over Adreno nodes, then other vendor nodes, then DRI nodes
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    {
+        use crate::mappings::GpuVendor;
+        devices.into_iter().min_by_key(|path| match GpuVendor::from_device_path(path) {
+            GpuVendor::Mali => 0,
+            GpuVendor::Adreno => 1,
+            GpuVendor::Unknown => 3,
+            _ => 2,
+        })
+    }
+    #[cfg(not(any(feature = "mali", feature = "adreno")))]
+    {
+        devices.into_iter().next()
+    }
+}
+
+/// Well-known DMA-BUF heaps used by Android GPU stacks
+const KNOWN_DMA_HEAPS: &[&str] = &[
+    "/dev/dma_heap/system",
+    "/dev/dma_heap/system-uncached",
+    "/dev/dma_heap/qcom,system",
+];
+
+/// Vivante galcore device node
+const VIVANTE_DEVICE: &str = "/dev/galcore";
+
+/// i.MX display/video devices that usually accompany a Vivante GPU
+const VIVANTE_RELATED_DEVICES: &[&str] = &["/dev/mxc_vout", "/dev/mxc_ipu"];
+
+/// GPU device node together with its associated allocator devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuDeviceInfo {
+    /// GPU device node path (e.g. "/dev/mali0")
+    pub path: String,
+    /// Related devices such as DMA-BUF heaps or i.MX display/video nodes
+    pub related_devices: Vec<String>,
+}
+
+/// Find all GPU devices including their related allocator devices
+pub fn find_gpu_device_info() -> Vec<GpuDeviceInfo> {
+    let heaps = find_dma_heaps();
+
+    find_gpu_devices()
+        .into_iter()
+        .map(|path| {
+            let mut related_devices = heaps.clone();
+            if path == VIVANTE_DEVICE {
+                related_devices.extend(
+                    VIVANTE_RELATED_DEVICES
+                        .iter()
+                        .filter(|dev| fs::metadata(dev).is_ok())
+                        .map(|dev| dev.to_string()),
+                );
+            }
+            GpuDeviceInfo { path, related_devices }
+        })
+        .collect()
+}
+
+/// Find DMA-BUF heap devices under `/dev/dma_heap/`
+///
+/// Well-known heaps come first, followed by any other (vendor-specific) heaps.
+pub fn find_dma_heaps() -> Vec<String> {
+    let mut heaps: Vec<String> = KNOWN_DMA_HEAPS
+        .iter()
+        .filter(|path| fs::metadata(path).is_ok())
+        .map(|path| path.to_string())
+        .collect();
+
+    if let Ok(entries) = fs::read_dir("/dev/dma_heap") {
+        let mut others: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().to_string_lossy().into_owned())
+            .filter(|path| !heaps.contains(path))
+            .collect();
+        others.sort();
+        heaps.extend(others);
+    }
+
+    heaps
+}
+
+/// Socket path keywords that hint at a GPU or graphics HAL
+const GPU_SOCKET_KEYWORDS: &[&str] = &["mali", "gpu", "graphics", "pvr"];
+
+/// Find Unix sockets of the GPU stack, e.g. `@vendor.google.hardware.graphics.mapper`
+///
+/// Reads `/proc/net/unix`; abstract sockets keep their leading `@`. This is
+/// informational only, nothing is ever sent over these sockets.
+pub fn find_gpu_ipc_sockets() -> Vec<String> {
+    fs::read_to_string("/proc/net/unix")
+        .map(|content| parse_gpu_ipc_sockets(&content))
+        .unwrap_or_default()
+}
+
+fn parse_gpu_ipc_sockets(proc_net_unix: &str) -> Vec<String> {
+    let mut sockets: Vec<String> = proc_net_unix
+        .lines()
+        .skip(1)
+        // Num RefCount Protocol Flags Type St Inode Path
+        .filter_map(|line| line.split_whitespace().nth(7))
+        .filter(|path| {
+            let path = path.to_ascii_lowercase();
+            GPU_SOCKET_KEYWORDS.iter().any(|keyword| path.contains(keyword))
+        })
+        .map(str::to_string)
+        .collect();
+    sockets.sort();
+    sockets.dedup();
+    sockets
+}
+
+impl DiscoverySnapshot {
+    /// Rebuild a result for exports and analysis; the device is not reopened
+    pub fn to_discovery_result(&self) -> DiscoveryResult {
+        DiscoveryResult {
+            discovery: IoctlDiscovery::from_snapshot(self),
+            drm_driver: None,
+            type_scan_timings: HashMap::new(),
+        }
+    }
+}
+
+/// Per-type result counts, see [`DiscoveryResult::type_summary`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TypeSummary {
+    /// IOCTL type
+    pub ty: u8,
+    /// Results of this type
+    pub total: usize,
+    /// IOCTLs that succeeded
+    pub successful: usize,
+    /// IOCTLs that exist but rejected the argument (EFAULT/EINVAL)
+    pub exists: usize,
+    /// IOCTLs gated by permissions (EPERM/EACCES)
+    pub permission: usize,
+    /// IOCTLs that do not exist
+    pub not_existent: usize,
 }
 
 /// Discovery result wrapper
 pub struct DiscoveryResult {
     discovery: IoctlDiscovery,
+    drm_driver: Option<DrmDriverInfo>,
+    type_scan_timings: HashMap<u8, Duration>,
 }
 
 impl DiscoveryResult {
@@ -105,11 +396,182 @@ impl DiscoveryResult {
         self.discovery.export_json(path)
     }
 
+    /// Export results as MessagePack, with the same layout as `export_json`
+    #[cfg(feature = "msgpack")]
+    pub fn export_msgpack(&self, path: &str) -> io::Result<()> {
+        self.discovery.export_msgpack(path)
+    }
+
+    /// Load a result written by `export_msgpack`
+    ///
+    /// The export carries no device path or call count, so both are empty.
+    #[cfg(feature = "msgpack")]
+    pub fn import_msgpack(path: &str) -> io::Result<DiscoveryResult> {
+        let output: JsonOutput = rmp_serde::from_slice(&fs::read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let snapshot = DiscoverySnapshot {
+            device: String::new(),
+            options: serde_json::from_value(output.metadata.options).unwrap_or_default(),
+            results: output.results,
+            call_count: 0,
+            elapsed: Duration::ZERO,
+        };
+        Ok(snapshot.to_discovery_result())
+    }
+
+    /// Number of calls recorded in an `audit_log_path` file
+    pub fn audit_entry_count(path: &str) -> io::Result<u64> {
+        let content = fs::read_to_string(path)?;
+        Ok(content.lines().filter(|line| !line.trim().is_empty()).count() as u64)
+    }
+
+    /// Export results as an IDA Pro Python script
+    pub fn export_ida_script(&self, path: &str, module_name: &str) -> io::Result<()> {
+        self.discovery.export_ida_script(path, module_name)
+    }
+
+    /// Export a Markdown report of the scan
+    pub fn export_markdown(&self, path: &str, title: &str) -> io::Result<()> {
+        self.discovery.export_markdown(path, title)
+    }
+
+    /// Export a Wireshark Lua dissector for the discovered IOCTLs
+    pub fn export_wireshark_lua(&self, path: &str, driver_name: &str) -> io::Result<()> {
+        self.discovery.export_wireshark_lua(path, driver_name)
+    }
+
+    /// SQL `INSERT` statements for all results, one line per result
+    ///
+    /// Matches the table from `create_table_sql` and works with PostgreSQL
+    /// as well as SQLite. `scan_ts` is the RFC 3339 time of each test.
+    pub fn export_sql_insert(&self, table_name: &str, device_id: &str) -> String {
+        let table = sql_identifier(table_name);
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "NULL".to_string());
+
+        self.discovery.results.iter()
+            .map(|r| {
+                format!(
+                    "INSERT INTO {} (device_id, scan_ts, cmd, ty, nr, dir, size, interpretation, discovered_size, is_dangerous) \
+                     VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}, {});\n",
+                    table,
+                    sql_string(device_id),
+                    optional(r.timestamp.as_deref().map(sql_string)),
+                    r.cmd,
+                    r.ty,
+                    r.nr,
+                    r.dir,
+                    r.size,
+                    sql_string(&format!("{:?}", r.interpretation)),
+                    optional(r.discovered_size.map(|s| s.to_string())),
+                    if r.is_potentially_dangerous { "TRUE" } else { "FALSE" },
+                )
+            })
+            .collect()
+    }
+
+    /// `CREATE TABLE IF NOT EXISTS` statement for `export_sql_insert`
+    pub fn create_table_sql(table_name: &str) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n    \
+             device_id TEXT NOT NULL,\n    \
+             scan_ts TIMESTAMP WITH TIME ZONE,\n    \
+             cmd BIGINT NOT NULL,\n    \
+             ty SMALLINT NOT NULL,\n    \
+             nr SMALLINT NOT NULL,\n    \
+             dir SMALLINT NOT NULL,\n    \
+             size INTEGER NOT NULL,\n    \
+             interpretation TEXT NOT NULL,\n    \
+             discovered_size INTEGER,\n    \
+             is_dangerous BOOLEAN NOT NULL\n\
+             );\n",
+            sql_identifier(table_name)
+        )
+    }
+
+    /// Export a bpftrace program tracing calls of the recognized IOCTLs
+    pub fn export_bpftrace_script(&self, path: &str, driver_name: &str) -> io::Result<()> {
+        self.discovery.export_bpftrace_script(path, driver_name)
+    }
+
     /// Get all IOCTL results
     pub fn results(&self) -> &[IoctlResult] {
         &self.discovery.results
     }
 
+    /// Results grouped by IOCTL type
+    pub fn group_by_type(&self) -> HashMap<u8, Vec<&IoctlResult>> {
+        let mut groups: HashMap<u8, Vec<&IoctlResult>> = HashMap::new();
+        for result in &self.discovery.results {
+            groups.entry(result.ty).or_default().push(result);
+        }
+        groups
+    }
+
+    /// Results grouped by IOCTL type, in ascending type order
+    pub fn group_by_type_sorted(&self) -> BTreeMap<u8, Vec<&IoctlResult>> {
+        self.group_by_type().into_iter().collect()
+    }
+
+    /// Count the results of one type by interpretation
+    ///
+    /// Results with an unknown errno only count towards `total`.
+    pub fn type_summary(&self, ty: u8) -> TypeSummary {
+        let mut summary = TypeSummary { ty, ..Default::default() };
+        for result in self.discovery.results.iter().filter(|r| r.ty == ty) {
+            summary.total += 1;
+            match result.interpretation {
+                Interpretation::Success => summary.successful += 1,
+                Interpretation::Exists => summary.exists += 1,
+                Interpretation::Permission => summary.permission += 1,
+                Interpretation::NotExist => summary.not_existent += 1,
+                Interpretation::Unknown(_) => {}
+            }
+        }
+        summary
+    }
+
+    /// Collapse results that differ only in the tested size
+    pub fn deduplicate_results(&mut self) {
+        self.discovery.deduplicate_results();
+    }
+
+    /// Render per-IOCTL scan times as folded stacks for `flamegraph.pl`
+    pub fn to_flamegraph_data(&self) -> String {
+        self.discovery.to_flamegraph_data()
+    }
+
+    /// Summarize which known capabilities the device exposes
+    pub fn capability_flags(&self) -> DeviceCapabilityFlags {
+        DeviceCapabilityFlags::from_results(&self.discovery.results)
+    }
+
+    /// Wall-clock time spent scanning each IOCTL type
+    pub fn timings_by_type(&self) -> &HashMap<u8, Duration> {
+        &self.type_scan_timings
+    }
+
+    /// The IOCTL type that took longest to scan
+    pub fn slowest_type(&self) -> Option<(u8, Duration)> {
+        self.type_scan_timings.iter()
+            .max_by_key(|(ty, duration)| (**duration, std::cmp::Reverse(**ty)))
+            .map(|(ty, duration)| (*ty, *duration))
+    }
+
+    /// DRM driver information (only for DRI render nodes)
+    pub fn drm_driver(&self) -> Option<&DrmDriverInfo> {
+        self.drm_driver.as_ref()
+    }
+
+    /// The `k` most promising detection IOCTLs, see `IoctlResult::candidate_score`
+    pub fn top_k_candidates(&self, k: usize) -> Vec<&IoctlResult> {
+        self.discovery.top_k_candidates(k)
+    }
+
+    /// Results behaving unexpectedly, see `IoctlDiscovery::anomalies`
+    pub fn anomalies(&self) -> Vec<Anomaly<'_>> {
+        self.discovery.anomalies()
+    }
+
     /// Generate a profile template from discovery results
     pub fn generate_profile_template(&self, output_path: &str) -> io::Result<()> {
         self.discovery.generate_profile_template(output_path)
@@ -119,10 +581,102 @@ impl DiscoveryResult {
     pub fn get_call_count(&self) -> u32 {
         self.discovery.get_call_count()
     }
+
+    /// Estimated total heap memory used by the scanner
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.discovery.memory_usage_bytes()
+    }
+
+    /// Get machine-readable statistics for this discovery run
+    pub fn summarize(&self) -> DiscoverySummary {
+        self.discovery.summarize()
+    }
+
+    /// Share of the `(ty, nr)` space of `planned_types` that was tested
+    pub fn scan_coverage(&self, planned_types: &[u8]) -> ScanCoverage {
+        self.discovery.scan_coverage(planned_types)
+    }
+
+    /// Format the scan statistics as `IODISCO_*` shell variable assignments
+    ///
+    /// Companion of [`crate::GpuInfo::to_sysfs_format`]; strings are shell-quoted.
+    pub fn to_sysfs_format(&self) -> String {
+        let summary = self.summarize();
+        let types: Vec<String> = summary.unique_types_found.iter()
+            .map(|ty| format!("0x{:02x}", ty))
+            .collect();
+
+        let mut out = format!("IODISCO_DEVICE={}\n", crate::api::shell_quote(self.discovery.device_path()));
+        if let Some(driver) = &self.drm_driver {
+            out.push_str(&format!("IODISCO_DRM_DRIVER={}\n", crate::api::shell_quote(&driver.name)));
+        }
+        out.push_str(&format!("IODISCO_TYPES={}\n", crate::api::shell_quote(&types.join(","))));
+        for (key, value) in [
+            ("TOTAL_TESTED", summary.total_tested),
+            ("SUCCESSFUL", summary.successful),
+            ("EXISTS", summary.exists_efault),
+            ("PERMISSION_GATED", summary.permission_gated),
+            ("NOT_EXISTENT", summary.not_existent),
+            ("CALL_COUNT", summary.call_count as usize),
+        ] {
+            out.push_str(&format!("IODISCO_{}={}\n", key, value));
+        }
+        out
+    }
+
+    /// Combine the results of several devices into one report
+    ///
+    /// Every `IoctlResult` gets its `source_device` set, so `summarize()` and
+    /// `export_json()` also report statistics per device. The call count is
+    /// the sum and the elapsed time the longest of all inputs. The merged
+    /// result has no open device.
+    pub fn merge(results: Vec<DiscoveryResult>) -> DiscoveryResult {
+        let mut devices = Vec::new();
+        let mut options = None;
+        let mut merged_results = Vec::new();
+        let mut call_count = 0u32;
+        let mut elapsed = Duration::ZERO;
+        let mut drm_driver = None;
+        let mut type_scan_timings: HashMap<u8, Duration> = HashMap::new();
+
+        for result in results {
+            drm_driver = drm_driver.or(result.drm_driver);
+            for (ty, duration) in result.type_scan_timings {
+                *type_scan_timings.entry(ty).or_default() += duration;
+            }
+
+            let snapshot = result.discovery.into_snapshot();
+            call_count = call_count.saturating_add(snapshot.call_count);
+            elapsed = elapsed.max(snapshot.elapsed);
+            options.get_or_insert(snapshot.options);
+            merged_results.extend(snapshot.results.into_iter().map(|mut r| {
+                r.source_device.get_or_insert_with(|| snapshot.device.clone());
+                r
+            }));
+            devices.push(snapshot.device);
+        }
+
+        let snapshot = DiscoverySnapshot {
+            device: devices.join(", "),
+            options: options.unwrap_or_default(),
+            results: merged_results,
+            call_count,
+            elapsed,
+        };
+        DiscoveryResult {
+            discovery: IoctlDiscovery::from_snapshot(&snapshot),
+            drm_driver,
+            type_scan_timings,
+        }
+    }
 }
 
 /// Discovery configuration (public API)
-#[derive(Debug, Clone)]
+///
+/// Deserializes from JSON; missing fields take their default values
+/// (see `iodisco_config.schema.json`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct DiscoveryConfig {
     /// Verbosity level
     pub verbosity: Verbosity,
@@ -139,6 +693,8 @@ pub struct DiscoveryConfig {
     pub allow_types: Option<Vec<u8>>,
     /// Safety: denied ioctl types
     pub deny_types: Vec<u8>,
+    /// Safety: NR values never tested in any type
+    pub deny_nrs: Vec<u8>,
     /// Safety: warn only about dangerous types
     pub warn_only_on_dangerous: bool,
     /// Safety: attempt to find exact argument size
@@ -155,6 +711,31 @@ pub struct DiscoveryConfig {
     pub max_size_discovery_attempts: u8,
     /// Size discovery: candidate sizes
     pub size_discovery_candidates: Vec<u16>,
+
+    /// Memory: max bytes of IOCTL buffers kept alive
+    pub max_buffer_memory_bytes: usize,
+
+    /// Plan the scan without issuing any IOCTL calls
+    pub dry_run: bool,
+    /// Keep raw argument buffers of existing IOCTLs in the JSON export
+    pub forensics_mode: bool,
+
+    /// Wall-clock limit for the whole scan
+    pub scan_timeout: Option<Duration>,
+    /// What to do once `scan_timeout` has elapsed
+    pub timeout_behavior: TimeoutBehavior,
+    /// JSONL file every IOCTL call is appended to
+    pub audit_log_path: Option<String>,
+    /// Refuse to open the device when its kernel module is not loaded
+    pub verify_driver: bool,
+    /// IOCTLs issued before the scan, e.g. Mali's `VERSION_CHECK` (`0x40108003`)
+    pub warm_up_ioctls: Vec<u32>,
+    /// How often the first warm-up IOCTL is issued
+    pub warm_up_count: u32,
+    /// Retry scan calls failing with EAGAIN or EBUSY
+    pub retry_on_eagain: bool,
+    /// Retries per call when `retry_on_eagain` is set
+    pub max_retries: u8,
 }
 
 impl Default for DiscoveryConfig {
@@ -168,6 +749,7 @@ impl Default for DiscoveryConfig {
 
             allow_types: None,
             deny_types: vec![0x12, 0x88, 0x8B, 0xFD, 0xFE, 0xFF],
+            deny_nrs: Vec::new(),
             warn_only_on_dangerous: false,
             try_find_size: false,
 
@@ -177,6 +759,20 @@ impl Default for DiscoveryConfig {
 
             max_size_discovery_attempts: 5,
             size_discovery_candidates: vec![4, 8, 16, 24, 32, 40, 48, 64, 80, 96, 128, 256],
+
+            max_buffer_memory_bytes: 16 * 1024 * 1024,
+
+            dry_run: false,
+            forensics_mode: false,
+
+            scan_timeout: None,
+            timeout_behavior: TimeoutBehavior::ReturnPartial,
+            audit_log_path: None,
+            verify_driver: false,
+            warm_up_ioctls: Vec::new(),
+            warm_up_count: 1,
+            retry_on_eagain: false,
+            max_retries: 3,
         }
     }
 }
@@ -218,34 +814,286 @@ impl DiscoveryConfig {
             skip_details: true,
             parallel: false,
             warn_only_on_dangerous: false,
+            delay_between_calls_ms: 10,
             max_calls_per_second: Some(50),
             max_total_calls: Some(500),
             deny_types: vec![0x12, 0x88, 0x8B, 0xFD, 0xFE, 0xFF, 0x00, 0x01],
             ..Default::default()
         }
     }
+
+    /// Aggressive options for exhaustive discovery on test devices
+    pub fn aggressive() -> Self {
+        Self {
+            verbosity: Verbosity::Normal,
+            max_results: usize::MAX,
+            skip_details: false,
+            parallel: false,
+            warn_only_on_dangerous: true,
+            try_find_size: true,
+            max_calls_per_second: Some(5000),
+            max_total_calls: None,
+            ..Default::default()
+        }
+    }
+
+    /// Forensics options for incident investigation, see `DiscoveryOptions::forensics`
+    pub fn forensics() -> Self {
+        Self {
+            verbosity: Verbosity::Debug,
+            max_results: usize::MAX,
+            skip_details: false,
+            parallel: false,
+            warn_only_on_dangerous: true,
+            try_find_size: true,
+            max_calls_per_second: Some(200),
+            forensics_mode: true,
+            ..Default::default()
+        }
+    }
+
+    /// Abort the scan once it has run for `timeout`
+    ///
+    /// Checked every 16 NR values; see `timeout_behavior` for what happens then.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.scan_timeout = Some(timeout);
+        self
+    }
+
+    /// Load a config from a JSON file
+    pub fn from_json_file(path: &str) -> Result<Self, DiscoveryError> {
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            DiscoveryError::InvalidParameter(format!("cannot read config file {}: {}", path, e))
+        })?;
+        Self::from_json_str(&data)
+    }
+
+    /// Parse a config from a JSON string and validate it
+    pub fn from_json_str(s: &str) -> Result<Self, DiscoveryError> {
+        let config: Self = serde_json::from_str(s)?;
+        DiscoveryOptions::from(config.clone())
+            .validate()
+            .map_err(|e| DiscoveryError::InvalidParameter(e.to_string()))?;
+        Ok(config)
+    }
+
+    /// Merge an override config on top of this one
+    ///
+    /// Every field of `override_config` that differs from
+    /// `DiscoveryConfig::default()` wins; all other fields keep the value of `self`.
+    pub fn merge(self, override_config: DiscoveryConfig) -> DiscoveryConfig {
+        self.override_with(&PartialDiscoveryConfig::from_non_default(override_config))
+    }
+
+    /// Apply all fields that are set in `other` on top of this config
+    pub fn override_with(mut self, other: &PartialDiscoveryConfig) -> Self {
+        if let Some(v) = other.verbosity { self.verbosity = v; }
+        if let Some(v) = other.max_results { self.max_results = v; }
+        if let Some(v) = other.skip_details { self.skip_details = v; }
+        if let Some(v) = &other.focus_nr { self.focus_nr = v.clone(); }
+        if let Some(v) = other.parallel { self.parallel = v; }
+
+        if let Some(v) = &other.allow_types { self.allow_types = v.clone(); }
+        if let Some(v) = &other.deny_types { self.deny_types = v.clone(); }
+        if let Some(v) = &other.deny_nrs { self.deny_nrs = v.clone(); }
+        if let Some(v) = other.warn_only_on_dangerous { self.warn_only_on_dangerous = v; }
+        if let Some(v) = other.try_find_size { self.try_find_size = v; }
+
+        if let Some(v) = other.delay_between_calls_ms { self.delay_between_calls_ms = v; }
+        if let Some(v) = other.max_calls_per_second { self.max_calls_per_second = v; }
+        if let Some(v) = other.max_total_calls { self.max_total_calls = v; }
+
+        if let Some(v) = other.max_size_discovery_attempts { self.max_size_discovery_attempts = v; }
+        if let Some(v) = &other.size_discovery_candidates { self.size_discovery_candidates = v.clone(); }
+
+        if let Some(v) = other.max_buffer_memory_bytes { self.max_buffer_memory_bytes = v; }
+        if let Some(v) = other.dry_run { self.dry_run = v; }
+        if let Some(v) = other.forensics_mode { self.forensics_mode = v; }
+
+        if let Some(v) = other.scan_timeout { self.scan_timeout = v; }
+        if let Some(v) = other.timeout_behavior { self.timeout_behavior = v; }
+        if let Some(v) = &other.audit_log_path { self.audit_log_path = v.clone(); }
+        if let Some(v) = other.verify_driver { self.verify_driver = v; }
+        if let Some(v) = &other.warm_up_ioctls { self.warm_up_ioctls = v.clone(); }
+        if let Some(v) = other.warm_up_count { self.warm_up_count = v; }
+        if let Some(v) = other.retry_on_eagain { self.retry_on_eagain = v; }
+        if let Some(v) = other.max_retries { self.max_retries = v; }
+        self
+    }
 }
 
-impl Into<DiscoveryOptions> for DiscoveryConfig {
-    fn into(self) -> DiscoveryOptions {
+/// Partial discovery configuration used to override a base [`DiscoveryConfig`]
+///
+/// Fields set to `None` leave the base value untouched.
+#[derive(Debug, Clone, Default)]
+pub struct PartialDiscoveryConfig {
+    /// Verbosity level
+    pub verbosity: Option<Verbosity>,
+    /// Maximum results per category
+    pub max_results: Option<usize>,
+    /// Skip detailed analysis
+    pub skip_details: Option<bool>,
+    /// Focus on specific NR values
+    pub focus_nr: Option<Option<Vec<u8>>>,
+    /// Use parallel scanning
+    pub parallel: Option<bool>,
+
+    /// Safety: explicitly allowed ioctl types
+    pub allow_types: Option<Option<Vec<u8>>>,
+    /// Safety: denied ioctl types
+    pub deny_types: Option<Vec<u8>>,
+    /// Safety: NR values never tested in any type
+    pub deny_nrs: Option<Vec<u8>>,
+    /// Safety: warn only about dangerous types
+    pub warn_only_on_dangerous: Option<bool>,
+    /// Safety: attempt to find exact argument size
+    pub try_find_size: Option<bool>,
+
+    /// Rate limiting: delay between calls in ms
+    pub delay_between_calls_ms: Option<u64>,
+    /// Rate limiting: max calls per second
+    pub max_calls_per_second: Option<Option<u32>>,
+    /// Rate limiting: max total calls
+    pub max_total_calls: Option<Option<u32>>,
+
+    /// Size discovery: max attempts
+    pub max_size_discovery_attempts: Option<u8>,
+    /// Size discovery: candidate sizes
+    pub size_discovery_candidates: Option<Vec<u16>>,
+
+    /// Memory: max bytes of IOCTL buffers kept alive
+    pub max_buffer_memory_bytes: Option<usize>,
+
+    /// Plan the scan without issuing any IOCTL calls
+    pub dry_run: Option<bool>,
+    /// Keep raw argument buffers of existing IOCTLs in the JSON export
+    pub forensics_mode: Option<bool>,
+
+    /// Wall-clock limit for the whole scan
+    pub scan_timeout: Option<Option<Duration>>,
+    /// What to do once `scan_timeout` has elapsed
+    pub timeout_behavior: Option<TimeoutBehavior>,
+    /// JSONL file every IOCTL call is appended to
+    pub audit_log_path: Option<Option<String>>,
+    /// Refuse to open the device when its kernel module is not loaded
+    pub verify_driver: Option<bool>,
+    /// IOCTLs issued before the scan
+    pub warm_up_ioctls: Option<Vec<u32>>,
+    /// How often the first warm-up IOCTL is issued
+    pub warm_up_count: Option<u32>,
+    /// Retry scan calls failing with EAGAIN or EBUSY
+    pub retry_on_eagain: Option<bool>,
+    /// Retries per call when `retry_on_eagain` is set
+    pub max_retries: Option<u8>,
+}
+
+impl PartialDiscoveryConfig {
+    /// Build a partial config containing only the fields that differ from the default
+    pub fn from_non_default(config: DiscoveryConfig) -> Self {
+        let default = DiscoveryConfig::default();
+        fn changed<T: PartialEq>(value: T, default: T) -> Option<T> {
+            if value != default { Some(value) } else { None }
+        }
+
+        Self {
+            verbosity: changed(config.verbosity, default.verbosity),
+            max_results: changed(config.max_results, default.max_results),
+            skip_details: changed(config.skip_details, default.skip_details),
+            focus_nr: changed(config.focus_nr, default.focus_nr),
+            parallel: changed(config.parallel, default.parallel),
+
+            allow_types: changed(config.allow_types, default.allow_types),
+            deny_types: changed(config.deny_types, default.deny_types),
+            deny_nrs: changed(config.deny_nrs, default.deny_nrs),
+            warn_only_on_dangerous: changed(config.warn_only_on_dangerous, default.warn_only_on_dangerous),
+            try_find_size: changed(config.try_find_size, default.try_find_size),
+
+            delay_between_calls_ms: changed(config.delay_between_calls_ms, default.delay_between_calls_ms),
+            max_calls_per_second: changed(config.max_calls_per_second, default.max_calls_per_second),
+            max_total_calls: changed(config.max_total_calls, default.max_total_calls),
+
+            max_size_discovery_attempts: changed(config.max_size_discovery_attempts, default.max_size_discovery_attempts),
+            size_discovery_candidates: changed(config.size_discovery_candidates, default.size_discovery_candidates),
+
+            max_buffer_memory_bytes: changed(config.max_buffer_memory_bytes, default.max_buffer_memory_bytes),
+            dry_run: changed(config.dry_run, default.dry_run),
+            forensics_mode: changed(config.forensics_mode, default.forensics_mode),
+
+            scan_timeout: changed(config.scan_timeout, default.scan_timeout),
+            timeout_behavior: changed(config.timeout_behavior, default.timeout_behavior),
+            audit_log_path: changed(config.audit_log_path, default.audit_log_path),
+            verify_driver: changed(config.verify_driver, default.verify_driver),
+            warm_up_ioctls: changed(config.warm_up_ioctls, default.warm_up_ioctls),
+            warm_up_count: changed(config.warm_up_count, default.warm_up_count),
+            retry_on_eagain: changed(config.retry_on_eagain, default.retry_on_eagain),
+            max_retries: changed(config.max_retries, default.max_retries),
+        }
+    }
+}
+
+impl From<()> for DiscoveryConfig {
+    fn from(_: ()) -> Self {
+        DiscoveryConfig::default()
+    }
+}
+
+impl From<Option<DiscoveryConfig>> for DiscoveryConfig {
+    fn from(config: Option<DiscoveryConfig>) -> Self {
+        config.unwrap_or_default()
+    }
+}
+
+/// Preset by name: "quick", "debug", "embedded", "aggressive" or "forensics"
+///
+/// Unknown names fall back to `DiscoveryConfig::default()`.
+impl From<&str> for DiscoveryConfig {
+    fn from(preset: &str) -> Self {
+        match preset.to_ascii_lowercase().as_str() {
+            "quick" => DiscoveryConfig::quick(),
+            "debug" => DiscoveryConfig::debug(),
+            "embedded" => DiscoveryConfig::embedded(),
+            "aggressive" => DiscoveryConfig::aggressive(),
+            "forensics" => DiscoveryConfig::forensics(),
+            _ => DiscoveryConfig::default(),
+        }
+    }
+}
+
+impl From<DiscoveryConfig> for DiscoveryOptions {
+    fn from(config: DiscoveryConfig) -> Self {
         DiscoveryOptions {
-            verbosity: self.verbosity,
-            max_results: self.max_results,
-            skip_details: self.skip_details,
-            focus_nr: self.focus_nr,
-            parallel: self.parallel,
-
-            allow_types: self.allow_types,
-            deny_types: self.deny_types,
-            warn_only_on_dangerous: self.warn_only_on_dangerous,
-            try_find_size: self.try_find_size,
-
-            delay_between_calls_ms: self.delay_between_calls_ms,
-            max_calls_per_second: self.max_calls_per_second,
-            max_total_calls: self.max_total_calls,
-
-            max_size_discovery_attempts: self.max_size_discovery_attempts,
-            size_discovery_candidates: self.size_discovery_candidates,
+            verbosity: config.verbosity,
+            max_results: config.max_results,
+            skip_details: config.skip_details,
+            focus_nr: config.focus_nr,
+            parallel: config.parallel,
+
+            allow_types: config.allow_types,
+            deny_types: config.deny_types,
+            deny_nrs: config.deny_nrs,
+            warn_only_on_dangerous: config.warn_only_on_dangerous,
+            try_find_size: config.try_find_size,
+
+            delay_between_calls_ms: config.delay_between_calls_ms,
+            max_calls_per_second: config.max_calls_per_second,
+            max_total_calls: config.max_total_calls,
+
+            max_size_discovery_attempts: config.max_size_discovery_attempts,
+            size_discovery_candidates: config.size_discovery_candidates,
+
+            max_buffer_memory_bytes: config.max_buffer_memory_bytes,
+            dry_run: config.dry_run,
+            forensics_mode: config.forensics_mode,
+
+            scan_timeout: config.scan_timeout,
+            timeout_behavior: config.timeout_behavior,
+            audit_log_path: config.audit_log_path,
+            verify_driver: config.verify_driver,
+            progress_output: None,
+            warm_up_ioctls: config.warm_up_ioctls,
+            warm_up_count: config.warm_up_count,
+            retry_on_eagain: config.retry_on_eagain,
+            max_retries: config.max_retries,
         }
     }
 }
@@ -279,3 +1127,385 @@ impl Verbosity {
         self.value() >= level.value()
     }
 }
+
+/// Single-quoted SQL string literal
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Double-quoted SQL identifier, keeping `schema.table` qualification
+fn sql_identifier(name: &str) -> String {
+    name.split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_device_records_type_timings() {
+        let config = DiscoveryConfig {
+            dry_run: true,
+            focus_nr: Some(vec![0x00, 0x03]),
+            ..Default::default()
+        };
+        let result = scan_device(Some("/dev/null"), config, None).unwrap();
+
+        let timings = result.timings_by_type();
+        assert_eq!(timings.len(), MALI_SCAN_TYPES.len());
+        assert!(MALI_SCAN_TYPES.iter().all(|ty| timings.contains_key(ty)));
+
+        let (slowest, duration) = result.slowest_type().unwrap();
+        assert!(timings.values().all(|d| *d <= duration));
+        assert_eq!(timings[&slowest], duration);
+
+        // KGSL nodes are scanned with the Adreno types
+        let dir = tempfile::tempdir().unwrap();
+        let kgsl = dir.path().join("kgsl-3d0");
+        std::os::unix::fs::symlink("/dev/null", &kgsl).unwrap();
+        let config = DiscoveryConfig {
+            dry_run: true,
+            focus_nr: Some(vec![0x00]),
+            ..Default::default()
+        };
+        let result = scan_device(kgsl.to_str(), config, None).unwrap();
+        let mut types: Vec<u8> = result.timings_by_type().keys().copied().collect();
+        types.sort_unstable();
+        assert_eq!(types, ADRENO_SCAN_TYPES);
+    }
+
+    #[test]
+    fn test_scan_device_with_progress_reports_events() {
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl ProgressReporter for Recorder {
+            fn on_type_start(&self, ty: u8) {
+                self.0.lock().unwrap().push(format!("start {:02x}", ty));
+            }
+            fn on_nr_complete(&self, ty: u8, nr: u8, results: usize) {
+                self.0.lock().unwrap().push(format!("nr {:02x}.{:02x} {}", ty, nr, results));
+            }
+            fn on_type_complete(&self, ty: u8, valid_count: usize) {
+                self.0.lock().unwrap().push(format!("done {:02x} {}", ty, valid_count));
+            }
+        }
+
+        let config = DiscoveryConfig {
+            verbosity: Verbosity::Minimal,
+            focus_nr: Some(vec![0x00, 0x01]),
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let recorder = Recorder::default();
+        let result = scan_device_with_progress(Some("/dev/null"), Some(config), &recorder).unwrap();
+
+        let events = recorder.0.into_inner().unwrap();
+        let per_nr = result.results().len() / (MALI_SCAN_TYPES.len() * 2);
+        assert_eq!(events.len(), MALI_SCAN_TYPES.len() * 4);
+        assert_eq!(events[..4], [
+            "start 80".to_string(),
+            format!("nr 80.00 {}", per_nr),
+            format!("nr 80.01 {}", per_nr),
+            "done 80 0".to_string(),
+        ]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_scan_paths_parallel_keeps_order() {
+        let config = DiscoveryConfig {
+            verbosity: Verbosity::Minimal,
+            focus_nr: Some(vec![0x00]),
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let devices = vec!["/dev/null".to_string(), "/nonexistent/mali0".to_string(), "/dev/zero".to_string()];
+        let results = scan_paths_parallel(devices.clone(), config);
+
+        let paths: Vec<&str> = results.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, devices);
+        assert!(results[0].1.as_ref().is_ok_and(|r| r.get_call_count() > 0));
+        assert!(results[1].1.is_err());
+        assert!(results[2].1.is_ok());
+    }
+
+    #[test]
+    fn test_parse_gpu_ipc_sockets() {
+        let proc_net_unix = "\
+Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 23456 @vendor.google.hardware.graphics.mapper
+0000000000000000: 00000002 00000000 00010000 0001 01 23457 /dev/socket/logd
+0000000000000000: 00000003 00000000 00000000 0001 03 23458
+0000000000000000: 00000002 00000000 00010000 0001 01 23459 /dev/socket/mali_hal
+0000000000000000: 00000002 00000000 00010000 0001 01 23460 @vendor.google.hardware.graphics.mapper
+";
+        assert_eq!(parse_gpu_ipc_sockets(proc_net_unix), vec![
+            "/dev/socket/mali_hal".to_string(),
+            "@vendor.google.hardware.graphics.mapper".to_string(),
+        ]);
+        let _ = find_gpu_ipc_sockets();
+    }
+
+    #[test]
+    fn test_discovery_result_to_sysfs_format() {
+        let snapshot = DiscoverySnapshot {
+            device: "/dev/mali0".to_string(),
+            options: DiscoveryOptions::default(),
+            results: vec![IoctlResult::new(
+                0xC0048000, 3, 0x80, 0x00, 4, (0, 0), None, Interpretation::Success, None, false,
+            )],
+            call_count: 2,
+            elapsed: Duration::ZERO,
+        };
+        let output = snapshot.to_discovery_result().to_sysfs_format();
+        assert!(output.starts_with("IODISCO_DEVICE=\"/dev/mali0\"\nIODISCO_TYPES=\"0x80\"\n"));
+        assert!(output.contains("IODISCO_SUCCESSFUL=1\n"));
+        assert!(output.ends_with("IODISCO_CALL_COUNT=2\n"));
+    }
+
+    #[test]
+    fn test_merge_results_per_device() {
+        let sample = |device: &str, interpretations: Vec<Interpretation>, call_count: u32| {
+            let results = interpretations.into_iter()
+                .map(|i| IoctlResult::new(0xC0048000, 3, 0x80, 0x00, 4, (0, 0), None, i, None, false))
+                .collect();
+            DiscoverySnapshot {
+                device: device.to_string(),
+                options: DiscoveryOptions::default(),
+                results,
+                call_count,
+                elapsed: Duration::from_millis(call_count as u64),
+            }
+            .to_discovery_result()
+        };
+
+        let merged = DiscoveryResult::merge(vec![
+            sample("/dev/mali0", vec![Interpretation::Success, Interpretation::NotExist], 3),
+            sample("/dev/mali1", vec![Interpretation::Exists], 2),
+        ]);
+        assert_eq!(merged.results().len(), 3);
+        assert_eq!(merged.results()[2].source_device.as_deref(), Some("/dev/mali1"));
+
+        let summary = merged.summarize();
+        assert_eq!(summary.call_count, 5);
+        assert_eq!(summary.per_device.len(), 2);
+        assert_eq!(summary.per_device["/dev/mali0"].successful, 1);
+        assert_eq!(summary.per_device["/dev/mali0"].not_existent, 1);
+        assert_eq!(summary.per_device["/dev/mali1"].potentially_existent, 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("merged.json");
+        merged.export_json(path.to_str().unwrap()).unwrap();
+        let output: JsonOutput = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(output.statistics.total, 3);
+        assert_eq!(output.statistics.per_device, summary.per_device);
+    }
+
+    #[test]
+    fn test_export_sql_insert() {
+        let mut results = vec![
+            IoctlResult::new(0xC0048000, 3, 0x80, 0x00, 4, (0, 0), Some((0, 0)), Interpretation::Success, None, false),
+            IoctlResult::new(0xC010FF03, 3, 0xFF, 0x03, 16, (-1, 22), None, Interpretation::Unknown(22), Some(8), true),
+        ];
+        results[0].timestamp = Some("2024-05-01T12:00:00+02:00".to_string());
+        results[1].timestamp = None;
+        let result = DiscoverySnapshot {
+            device: "/dev/mali0".to_string(),
+            options: DiscoveryOptions::default(),
+            results,
+            call_count: 2,
+            elapsed: Duration::ZERO,
+        }
+        .to_discovery_result();
+
+        let sql = result.export_sql_insert("public.gpu_scans", "pixel'6");
+        let lines: Vec<&str> = sql.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "INSERT INTO \"public\".\"gpu_scans\" (device_id, scan_ts, cmd, ty, nr, dir, size, interpretation, \
+             discovered_size, is_dangerous) VALUES ('pixel''6', '2024-05-01T12:00:00+02:00', 3221520384, 128, 0, 3, 4, \
+             'Success', NULL, FALSE);"
+        );
+        assert!(lines[1].ends_with("VALUES ('pixel''6', NULL, 3222339331, 255, 3, 3, 16, 'Unknown(22)', 8, TRUE);"));
+
+        let create = DiscoveryResult::create_table_sql("gpu\"scans");
+        assert!(create.starts_with("CREATE TABLE IF NOT EXISTS \"gpu\"\"scans\" (\n    device_id TEXT NOT NULL,\n"));
+        assert!(create.ends_with("    is_dangerous BOOLEAN NOT NULL\n);\n"));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_roundtrip_is_smaller() {
+        let results = (0..1000u32)
+            .map(|i| {
+                let cmd = 0xC0108000 | (i & 0xFF);
+                IoctlResult::new(cmd, 3, 0x80, cmd as u8, 16, (-1, 22), Some((0, 0)), Interpretation::Exists, Some(16), false)
+            })
+            .collect();
+        let result = DiscoverySnapshot {
+            device: "/dev/mali0".to_string(),
+            options: DiscoveryOptions::default(),
+            results,
+            call_count: 1000,
+            elapsed: Duration::ZERO,
+        }
+        .to_discovery_result();
+
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("scan.json");
+        let msgpack = dir.path().join("scan.msgpack");
+        result.export_json(json.to_str().unwrap()).unwrap();
+        result.export_msgpack(msgpack.to_str().unwrap()).unwrap();
+
+        let json_size = fs::metadata(&json).unwrap().len();
+        let msgpack_size = fs::metadata(&msgpack).unwrap().len();
+        assert!(msgpack_size * 10 <= json_size * 7, "{} vs {} bytes", msgpack_size, json_size);
+
+        let imported = DiscoveryResult::import_msgpack(msgpack.to_str().unwrap()).unwrap();
+        assert_eq!(imported.results().len(), 1000);
+        assert_eq!(imported.results()[5].cmd, 0xC0108005);
+        assert_eq!(imported.results()[5].discovered_size, Some(16));
+    }
+
+    #[test]
+    fn test_group_by_type_and_summary() {
+        let result = IoctlResult::from_cmd_for_test;
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        discovery.results = vec![
+            result(0xC0048000, Interpretation::Success),
+            result(0xC0040901, Interpretation::Exists),
+            result(0xC0048001, Interpretation::Permission),
+            result(0xC0048002, Interpretation::NotExist),
+            result(0xC0048003, Interpretation::Unknown(5)),
+        ];
+        let result = DiscoveryResult { discovery, drm_driver: None, type_scan_timings: HashMap::new() };
+
+        let groups = result.group_by_type();
+        assert_eq!(groups[&0x80].len(), 4);
+        assert_eq!(groups[&0x09].len(), 1);
+        let types: Vec<u8> = result.group_by_type_sorted().into_keys().collect();
+        assert_eq!(types, vec![0x09, 0x80]);
+
+        assert_eq!(result.type_summary(0x80), TypeSummary {
+            ty: 0x80, total: 4, successful: 1, exists: 0, permission: 1, not_existent: 1,
+        });
+        assert_eq!(result.type_summary(0x42), TypeSummary { ty: 0x42, ..Default::default() });
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let options = DiscoveryOptions {
+            focus_nr: Some(vec![0x00]),
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_type(0x80).unwrap();
+        let calls = discovery.get_call_count();
+
+        let json = serde_json::to_string(&discovery.into_snapshot()).unwrap();
+        let snapshot: DiscoverySnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot.device, "/dev/null");
+        assert_eq!(snapshot.call_count, calls);
+
+        let result = snapshot.to_discovery_result();
+        assert_eq!(result.results().len(), snapshot.results.len());
+        assert_eq!(result.type_summary(0x80).total, snapshot.results.len());
+        assert_eq!(result.summarize().call_count, calls);
+    }
+
+    #[test]
+    fn test_config_from_presets() {
+        let config: DiscoveryConfig = ().into();
+        assert_eq!(config.max_results, DiscoveryConfig::default().max_results);
+
+        let config: DiscoveryConfig = "embedded".into();
+        assert_eq!(config.delay_between_calls_ms, 10);
+
+        let config: DiscoveryConfig = "Aggressive".into();
+        assert!(config.try_find_size);
+        assert!(config.max_total_calls.is_none());
+        assert!(DiscoveryOptions::from(config).validate().is_ok());
+
+        let options = DiscoveryOptions::from(DiscoveryConfig::from("forensics"));
+        let preset = DiscoveryOptions::forensics();
+        assert!(options.forensics_mode && preset.forensics_mode);
+        assert_eq!(options.max_calls_per_second, preset.max_calls_per_second);
+        assert_eq!(options.max_results, usize::MAX);
+        assert!(options.validate().is_ok());
+
+        let config: DiscoveryConfig = "no-such-preset".into();
+        assert_eq!(config.verbosity, Verbosity::Normal);
+    }
+
+    #[test]
+    fn test_merge_embedded_with_parallel_override() {
+        let override_config = DiscoveryConfig {
+            parallel: true,
+            ..Default::default()
+        };
+
+        let merged = DiscoveryConfig::embedded().merge(override_config);
+        assert!(merged.parallel);
+        assert_eq!(merged.delay_between_calls_ms, 10);
+        assert_eq!(merged.max_total_calls, Some(500));
+        assert_eq!(merged.verbosity, Verbosity::Minimal);
+    }
+
+    #[test]
+    fn test_override_with_partial() {
+        let partial = PartialDiscoveryConfig {
+            max_total_calls: Some(None),
+            verbosity: Some(Verbosity::Debug),
+            ..Default::default()
+        };
+
+        let config = DiscoveryConfig::quick().override_with(&partial);
+        assert_eq!(config.max_total_calls, None);
+        assert_eq!(config.verbosity, Verbosity::Debug);
+        assert_eq!(config.max_calls_per_second, Some(100));
+    }
+
+    #[test]
+    fn test_config_from_json() {
+        let config = DiscoveryConfig::from_json_str(
+            r#"{"verbosity": "Debug", "max_total_calls": null, "deny_types": [18]}"#
+        ).unwrap();
+        assert_eq!(config.verbosity, Verbosity::Debug);
+        assert_eq!(config.max_total_calls, None);
+        assert_eq!(config.deny_types, vec![0x12]);
+        assert_eq!(config.max_results, DiscoveryConfig::default().max_results);
+
+        let err = DiscoveryConfig::from_json_str(r#"{"max_calls_per_second": 0}"#).unwrap_err();
+        assert!(matches!(err, DiscoveryError::InvalidParameter(_)));
+        assert!(matches!(DiscoveryConfig::from_json_str("{"), Err(DiscoveryError::Json(_))));
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), r#"{"dry_run": true}"#).unwrap();
+        let config = DiscoveryConfig::from_json_file(file.path().to_str().unwrap()).unwrap();
+        assert!(config.dry_run);
+        assert!(DiscoveryConfig::from_json_file("/nonexistent/iodisco.json").is_err());
+    }
+
+    #[test]
+    fn test_config_timeout() {
+        let options = DiscoveryOptions::from(DiscoveryConfig::default().with_timeout(Duration::from_secs(30)));
+        assert_eq!(options.scan_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(options.timeout_behavior, TimeoutBehavior::ReturnPartial);
+
+        let config = DiscoveryConfig::from_json_str(
+            r#"{"scan_timeout": {"secs": 5, "nanos": 0}, "timeout_behavior": "ReturnError"}"#
+        ).unwrap();
+        let merged = DiscoveryConfig::quick().merge(config);
+        assert_eq!(merged.scan_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(merged.timeout_behavior, TimeoutBehavior::ReturnError);
+        assert_eq!(merged.max_total_calls, Some(1000));
+    }
+}
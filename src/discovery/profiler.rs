@@ -0,0 +1,194 @@
+//! Struct layout inference for unknown IOCTL arguments
+//!
+//! The argument buffer is filled with a known pattern before each call.
+//! Bytes that come back different were written by the kernel; grouping them
+//! by natural alignment gives a first guess of the output fields.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::discovery::IoctlDiscovery;
+
+/// Sentinel patterns used in turn, starting with `0xA5A5A5A5`
+///
+/// Using several patterns catches fields where the kernel happens to write
+/// the same value as the sentinel.
+const PATTERNS: [u32; 4] = [0xA5A5_A5A5, 0x5A5A_5A5A, 0x0000_0000, 0xFFFF_FFFF];
+
+/// Guessed type of a field written by the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldType {
+    /// Single byte
+    U8,
+    /// 16-bit integer
+    U16,
+    /// 32-bit integer
+    U32,
+    /// 64-bit integer or pointer
+    U64,
+}
+
+impl FieldType {
+    /// Size of the field in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::U8 => 1,
+            FieldType::U16 => 2,
+            FieldType::U32 => 4,
+            FieldType::U64 => 8,
+        }
+    }
+}
+
+/// One field of an inferred struct layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldGuess {
+    /// Offset within the argument struct
+    pub offset: usize,
+    /// Field size in bytes
+    pub size: usize,
+    /// Most likely type for the field
+    pub likely_type: FieldType,
+}
+
+/// Inferred layout of an IOCTL argument struct
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructLayout {
+    /// Total size of the argument struct
+    pub size: usize,
+    /// Fields the kernel wrote, ordered by offset
+    pub fields: Vec<FieldGuess>,
+}
+
+/// Records which argument bytes an IOCTL writes across several calls
+pub struct StructProfiler {
+    cmd: u32,
+    size: usize,
+    written: Vec<bool>,
+    iterations: u32,
+}
+
+impl StructProfiler {
+    /// Profiler for `cmd` with an argument struct of `size` bytes
+    pub fn new(cmd: u32, size: usize) -> Self {
+        Self {
+            cmd,
+            size,
+            written: vec![false; size],
+            iterations: 0,
+        }
+    }
+
+    /// Call the IOCTL `iterations` times, each with the next sentinel pattern
+    ///
+    /// Stops at the first failing call.
+    pub fn run(&mut self, discovery: &mut IoctlDiscovery, iterations: u32) -> io::Result<()> {
+        for _ in 0..iterations {
+            let input = self.next_pattern();
+            let output = discovery.execute_ioctl_with_input(self.cmd, &input)?;
+            self.record(&input, &output);
+        }
+        Ok(())
+    }
+
+    /// Input buffer for the next iteration
+    pub fn next_pattern(&self) -> Vec<u8> {
+        let pattern = PATTERNS[self.iterations as usize % PATTERNS.len()].to_le_bytes();
+        pattern.iter().copied().cycle().take(self.size).collect()
+    }
+
+    /// Record one call: every byte of `output` that differs from `input` was written
+    pub fn record(&mut self, input: &[u8], output: &[u8]) {
+        for (written, (before, after)) in self.written.iter_mut().zip(input.iter().zip(output)) {
+            *written |= before != after;
+        }
+        self.iterations += 1;
+    }
+
+    /// Number of recorded calls
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// Guess the fields from the bytes written so far
+    ///
+    /// Each run of written bytes is split into the largest naturally aligned
+    /// integers that fit, e.g. 6 bytes at offset 8 become a `U32` and a `U16`.
+    pub fn infer_layout(&self) -> StructLayout {
+        let mut fields = Vec::new();
+        let mut offset = 0;
+
+        while offset < self.size {
+            if !self.written[offset] {
+                offset += 1;
+                continue;
+            }
+
+            let run_end = self.written[offset..].iter()
+                .position(|w| !w)
+                .map_or(self.size, |len| offset + len);
+            while offset < run_end {
+                let likely_type = [FieldType::U64, FieldType::U32, FieldType::U16, FieldType::U8]
+                    .into_iter()
+                    .find(|ty| offset % ty.size() == 0 && offset + ty.size() <= run_end)
+                    .unwrap_or(FieldType::U8);
+                fields.push(FieldGuess { offset, size: likely_type.size(), likely_type });
+                offset += likely_type.size();
+            }
+        }
+
+        StructLayout { size: self.size, fields }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_rotate() {
+        let mut profiler = StructProfiler::new(0xC0108003, 6);
+        assert_eq!(profiler.next_pattern(), vec![0xA5; 6]);
+        let input = profiler.next_pattern();
+        profiler.record(&input, &input);
+        assert_eq!(profiler.next_pattern(), vec![0x5A; 6]);
+        assert!(profiler.infer_layout().fields.is_empty());
+    }
+
+    #[test]
+    fn test_infer_layout() {
+        let mut profiler = StructProfiler::new(0xC0188003, 24);
+        for _ in 0..4 {
+            let input = profiler.next_pattern();
+            let mut output = input.clone();
+            // u32 version at 0, u64 address at 8, u16 flags at 16 and a lone byte at 19
+            output[0..4].copy_from_slice(&42u32.to_le_bytes());
+            output[8..16].copy_from_slice(&0x7fff_1234_5678u64.to_le_bytes());
+            output[16..18].copy_from_slice(&0x0101u16.to_le_bytes());
+            output[19] = 3;
+            profiler.record(&input, &output);
+        }
+
+        let layout = profiler.infer_layout();
+        assert_eq!(layout.size, 24);
+        let fields: Vec<(usize, FieldType)> = layout.fields.iter().map(|f| (f.offset, f.likely_type)).collect();
+        assert_eq!(fields, vec![
+            (0, FieldType::U32),
+            (8, FieldType::U64),
+            (16, FieldType::U16),
+            (19, FieldType::U8),
+        ]);
+    }
+
+    #[test]
+    fn test_unaligned_run_is_split() {
+        let mut profiler = StructProfiler::new(0, 16);
+        let input = vec![0u8; 16];
+        let mut output = input.clone();
+        output[2..9].fill(1);
+        profiler.record(&input, &output);
+
+        let sizes: Vec<(usize, usize)> = profiler.infer_layout().fields.iter().map(|f| (f.offset, f.size)).collect();
+        assert_eq!(sizes, vec![(2, 2), (4, 4), (8, 1)]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Structured progress events for IOCTL scans
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Receives progress events while a device is scanned
+///
+/// Implement this to drive progress bars or custom logging instead of the
+/// dots printed in debug verbosity.
+pub trait ProgressReporter: Send {
+    /// Scanning of `ty` started
+    fn on_type_start(&self, ty: u8);
+    /// All combinations of `nr` were tested, producing `results` results
+    fn on_nr_complete(&self, ty: u8, nr: u8, results: usize);
+    /// Scanning of `ty` finished with `valid_count` valid IOCTLs
+    fn on_type_complete(&self, ty: u8, valid_count: usize);
+}
+
+/// Prints a line per type and a dot every 16 NR values to stdout
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutProgressReporter;
+
+impl ProgressReporter for StdoutProgressReporter {
+    fn on_type_start(&self, ty: u8) {
+        println!("🔍 Scanning Type 0x{:02x}...", ty);
+    }
+
+    fn on_nr_complete(&self, _ty: u8, nr: u8, _results: usize) {
+        if nr % 16 == 0 {
+            print!(".");
+            let _ = io::stdout().flush();
+        }
+    }
+
+    fn on_type_complete(&self, _ty: u8, valid_count: usize) {
+        println!(" {} valid", valid_count);
+    }
+}
+
+/// Ignores all progress events
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullProgressReporter;
+
+impl ProgressReporter for NullProgressReporter {
+    fn on_type_start(&self, _ty: u8) {}
+    fn on_nr_complete(&self, _ty: u8, _nr: u8, _results: usize) {}
+    fn on_type_complete(&self, _ty: u8, _valid_count: usize) {}
+}
+
+/// Shared destination for the text output of a scanner, see `DiscoveryOptions::progress_output`
+#[derive(Clone)]
+pub struct ProgressOutput(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl ProgressOutput {
+    /// Send scanner output to `writer`
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    /// Write one line; errors are ignored like those of `println!` on a closed pipe
+    pub(crate) fn write_line(&self, args: fmt::Arguments) {
+        let mut writer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.write_fmt(args);
+        let _ = writer.write_all(b"\n");
+    }
+
+    fn write_str(&self, text: &str) {
+        let mut writer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.write_all(text.as_bytes());
+        let _ = writer.flush();
+    }
+}
+
+impl fmt::Debug for ProgressOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressOutput")
+    }
+}
+
+/// `Write` implementation collecting everything into a shared `String`
+///
+/// Meant for tests: keep a clone and read `contents` after the scan.
+#[derive(Debug, Clone, Default)]
+pub struct StringWriter(pub Arc<Mutex<String>>);
+
+impl StringWriter {
+    /// Create an empty writer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far
+    pub fn contents(&self) -> String {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Write for StringWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push_str(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `StdoutProgressReporter` writing to a `ProgressOutput` instead of stdout
+#[derive(Debug, Clone)]
+pub struct WriterProgressReporter(pub ProgressOutput);
+
+impl ProgressReporter for WriterProgressReporter {
+    fn on_type_start(&self, ty: u8) {
+        self.0.write_line(format_args!("🔍 Scanning Type 0x{:02x}...", ty));
+    }
+
+    fn on_nr_complete(&self, _ty: u8, nr: u8, _results: usize) {
+        if nr % 16 == 0 {
+            self.0.write_str(".");
+        }
+    }
+
+    fn on_type_complete(&self, _ty: u8, valid_count: usize) {
+        self.0.write_line(format_args!(" {} valid", valid_count));
+    }
+}
@@ -1,26 +1,125 @@
 //! Systematic and safe IOCTL scanner with comprehensive safety features
 
-use std::fs;
 use std::io::{self, Write};
 use libc;
 use std::os::unix::io::RawFd;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, Instant};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 use serde::{Serialize, Deserialize};
 use crate::discovery::Verbosity;
+use crate::discovery::drm::{self, DrmDriverInfo};
+use crate::discovery::progress::{NullProgressReporter, ProgressOutput, ProgressReporter, StdoutProgressReporter, WriterProgressReporter};
+#[cfg(feature = "diff")]
+use crate::discovery::diff::{self, BufferChange};
+
+// `println!` / `eprintln!` honoring `DiscoveryOptions::progress_output`
+macro_rules! progress {
+    ($options:expr, $($arg:tt)*) => {
+        $options.write_progress(false, format_args!($($arg)*))
+    };
+}
+
+macro_rules! progress_err {
+    ($options:expr, $($arg:tt)*) => {
+        $options.write_progress(true, format_args!($($arg)*))
+    };
+}
 
 /// IOCTL discovery scanner with built-in safety mechanisms
 pub struct IoctlDiscovery {
     fd: RawFd,
+    device: String,
+    /// All IOCTL results collected so far
     pub results: Vec<IoctlResult>,
     options: DiscoveryOptions,
-    call_counter: AtomicU32,
-    last_call_time: Instant,
+    // One slot per rayon thread, shared with the workers of `scan_type_parallel`
+    call_counters: Arc<Vec<ThreadCalls>>,
+    start_time: Instant,
     // Keep buffers alive for kernel to write into
     active_buffers: Vec<Box<[u8]>>,
+    // Mali nodes answer GET_VERSION, which makes a better liveness probe than fstat
+    is_mali: bool,
+    // (ty, nr, dir, size, error) of every combination that failed during a scan
+    scan_errors: Vec<(u8, u8, u8, u16, io::Error)>,
+    // Set by the SIGINT / SIGTERM handlers registered in `open`
+    #[cfg(feature = "signal-handling")]
+    shutdown: Arc<AtomicBool>,
+    #[cfg(feature = "signal-handling")]
+    signal_ids: Vec<signal_hook::SigId>,
+}
+
+/// Why a cancelable scan stopped
+#[derive(Debug)]
+pub enum ScanStopReason {
+    /// All NR values were scanned
+    Completed,
+    /// The cancel token was set
+    Cancelled,
+    /// `max_total_calls` was reached
+    RateLimitExceeded,
+    /// `scan_timeout` elapsed with `TimeoutBehavior::ReturnPartial`
+    TimedOut,
+    /// The scan could not continue, e.g. because the device went away
+    Error(io::Error),
+}
+
+/// What a scan does once `scan_timeout` has elapsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeoutBehavior {
+    /// Stop and keep the results collected so far
+    #[default]
+    ReturnPartial,
+    /// Stop with an `io::ErrorKind::TimedOut` error
+    ReturnError,
+}
+
+/// Creates cancel tokens for `scan_type_cancelable` and `scan_device`
+pub struct CancelToken;
+
+impl CancelToken {
+    /// Create a new token and the handle that sets it
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> (Arc<AtomicBool>, CancelHandle) {
+        let flag = Arc::new(AtomicBool::new(false));
+        (flag.clone(), CancelHandle { flag })
+    }
+}
+
+/// Handle to cancel a running scan from another thread
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    /// Request cancellation of the scan
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
 }
 
+/// Argument sizes tried for every NR value during a type scan
+const SCAN_SIZES: &[u16] = &[1, 2, 4, 8, 16, 32, 64, 128];
+
+/// Direction bits tried for every NR value during a type scan
+const SCAN_DIRS: &[u8] = &[0, 1, 2, 3];
+
+/// Consistency below which `scan_type_statistical` marks an IOCTL as stateful
+const MIN_CONSISTENCY: f32 = 0.8;
+
+/// Wait before the first retry of a busy IOCTL, doubled per retry
+const RETRY_BACKOFF_MS: u64 = 10;
+
+/// Mali kbase VERSION_CHECK, used with a null argument as a liveness probe
+const MALI_GET_VERSION: u32 = 0xC0048000;
+
 /// Configuration options for the discovery process
 #[derive(Debug, Clone, Serialize, Deserialize)]  // Added Serialize and Deserialize
 pub struct DiscoveryOptions {
@@ -39,6 +138,9 @@ pub struct DiscoveryOptions {
     pub allow_types: Option<Vec<u8>>,
     /// Safety: denied ioctl types (only applied when allow_types is None)
     pub deny_types: Vec<u8>,
+    /// Safety: NR values that are never tested, whatever their type
+    #[serde(default)]
+    pub deny_nrs: Vec<u8>,
     /// Safety: when true, only warn about dangerous types instead of failing
     pub warn_only_on_dangerous: bool,
     /// Safety: when true, attempt to find exact argument size on EFAULT
@@ -55,6 +157,55 @@ pub struct DiscoveryOptions {
     pub max_size_discovery_attempts: u8,
     /// Size discovery: candidate sizes to try
     pub size_discovery_candidates: Vec<u16>,
+
+    /// Memory: maximum bytes of IOCTL buffers kept alive at once
+    pub max_buffer_memory_bytes: usize,
+
+    /// Plan the scan without issuing any IOCTL calls (every call reports ENOTTY)
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Keep the raw argument buffer of every existing IOCTL for `export_json`
+    #[serde(default)]
+    pub forensics_mode: bool,
+    /// Wall-clock limit for the whole scan, measured from opening the device
+    #[serde(default)]
+    pub scan_timeout: Option<Duration>,
+    /// What to do once `scan_timeout` has elapsed
+    #[serde(default)]
+    pub timeout_behavior: TimeoutBehavior,
+    /// JSONL file every IOCTL call is appended to, for security audits
+    #[serde(default)]
+    pub audit_log_path: Option<String>,
+    /// Destination for progress and result output instead of stdout / stderr
+    ///
+    /// Not part of `DiscoveryConfig`, since a writer cannot be serialized.
+    #[serde(skip)]
+    pub progress_output: Option<ProgressOutput>,
+    /// Refuse to open the device when its kernel module is not loaded
+    #[serde(default)]
+    pub verify_driver: bool,
+    /// IOCTLs issued before the scan to wake a lazily initialized driver
+    ///
+    /// Results are discarded. Mali's `VERSION_CHECK` (`0x40108003`) is a good candidate.
+    #[serde(default)]
+    pub warm_up_ioctls: Vec<u32>,
+    /// How often the first of `warm_up_ioctls` is issued
+    #[serde(default = "default_warm_up_count")]
+    pub warm_up_count: u32,
+    /// Repeat scan calls failing with EAGAIN or EBUSY, see `test_ioctl_with_retry`
+    #[serde(default)]
+    pub retry_on_eagain: bool,
+    /// Retries per call when `retry_on_eagain` is set
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u8,
+}
+
+fn default_max_retries() -> u8 {
+    3
+}
+
+fn default_warm_up_count() -> u32 {
+    1
 }
 
 impl Default for DiscoveryOptions {
@@ -70,6 +221,7 @@ impl Default for DiscoveryOptions {
             // Known dangerous / high-risk ioctl type ranges
             // May cause data corruption, crashes, hardware damage, etc.
             deny_types: vec![0x12, 0x88, 0x8B, 0xFD, 0xFE, 0xFF],
+            deny_nrs: Vec::new(),
             warn_only_on_dangerous: false,
             try_find_size: false, // Disabled by default for safety
             
@@ -79,11 +231,34 @@ impl Default for DiscoveryOptions {
             
             max_size_discovery_attempts: 5,
             size_discovery_candidates: vec![4, 8, 16, 24, 32, 40, 48, 64, 80, 96, 128, 256],
+
+            max_buffer_memory_bytes: 16 * 1024 * 1024, // 16 MB
+
+            dry_run: false,
+            forensics_mode: false,
+            scan_timeout: None,
+            timeout_behavior: TimeoutBehavior::ReturnPartial,
+            audit_log_path: None,
+            verify_driver: false,
+            progress_output: None,
+            warm_up_ioctls: Vec::new(),
+            warm_up_count: 1,
+            retry_on_eagain: false,
+            max_retries: 3,
         }
     }
 }
 
 impl DiscoveryOptions {
+    /// Write one line to `progress_output`, or to stdout / stderr without one
+    pub(crate) fn write_progress(&self, to_stderr: bool, args: std::fmt::Arguments) {
+        match &self.progress_output {
+            Some(output) => output.write_line(args),
+            None if to_stderr => eprintln!("{}", args),
+            None => println!("{}", args),
+        }
+    }
+
     /// Validate configuration options - returns io::Result instead of Result<(), String>
     pub fn validate(&self) -> io::Result<()> {
         // Check for conflicting options
@@ -94,6 +269,13 @@ impl DiscoveryOptions {
             ));
         }
         
+        if let Some(nr) = self.focus_nr.iter().flatten().find(|nr| self.deny_nrs.contains(nr)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("NR 0x{:02x} is in both focus_nr and deny_nrs", nr)
+            ));
+        }
+
         // Validate size discovery safety
         if self.try_find_size && !self.warn_only_on_dangerous {
             return Err(io::Error::new(
@@ -129,6 +311,20 @@ impl DiscoveryOptions {
             }
         }
         
+        if self.scan_timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "scan_timeout must be greater than zero"
+            ));
+        }
+
+        if self.max_buffer_memory_bytes == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "max_buffer_memory_bytes must be at least 1"
+            ));
+        }
+        
         // Validate size discovery attempts
         if self.max_size_discovery_attempts == 0 {
             return Err(io::Error::new(
@@ -173,12 +369,70 @@ impl DiscoveryOptions {
             max_results: 3,
             skip_details: true,
             warn_only_on_dangerous: false,
+            delay_between_calls_ms: 10,
             max_calls_per_second: Some(50),  // Very conservative
             max_total_calls: Some(500),      // Very limited
             deny_types: vec![0x12, 0x88, 0x8B, 0xFD, 0xFE, 0xFF, 0x00, 0x01], // Extended blacklist
             ..Default::default()
         }
     }
+
+    /// Forensics options collecting as much data as possible for incident investigation
+    ///
+    /// Discovers argument sizes and keeps the raw buffer of every IOCTL that
+    /// exists, so the JSON export can be handed to kernel security teams.
+    pub fn forensics() -> Self {
+        Self {
+            verbosity: Verbosity::Debug,
+            max_results: usize::MAX,
+            skip_details: false,
+            warn_only_on_dangerous: true,
+            try_find_size: true,
+            max_calls_per_second: Some(200),
+            forensics_mode: true,
+            ..Default::default()
+        }
+    }
+
+    /// Abort the scan once it has run for `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.scan_timeout = Some(timeout);
+        self
+    }
+
+    /// Use argument struct sizes from the Mali kbase kernel headers
+    pub fn with_mali_sizes(mut self) -> Self {
+        // Sources: mali_base_kernel.h / mali_kbase_ioctl.h (r-series and
+        // later kbase UAPI), 64-bit userspace layout
+        self.size_discovery_candidates = vec![
+            12,  // legacy UK calls: uku_header plus a 32-bit payload
+            24,  // legacy UK calls: uku_header plus two 64-bit fields
+            40,  // kbase_uk_mem_import / mem query style arguments
+            48,  // base_jd_atom_v2
+            72,  // base_jd_atom (with renderpass id and jobslot)
+            80,  // legacy UK mem alloc / job submit arguments
+            88,  // legacy UK hwcnt setup
+            120, // legacy UK mem alias / tlstream arguments
+            128, // base_gpu_props chunks queried through GET_PROPS
+        ];
+        self
+    }
+
+    /// Use argument struct sizes from the Qualcomm KGSL kernel header
+    pub fn with_adreno_sizes(mut self) -> Self {
+        // Source: include/uapi/linux/msm_kgsl.h, 64-bit userspace layout
+        self.size_discovery_candidates = vec![
+            8,  // kgsl_drawctxt_create, kgsl_drawctxt_destroy (padded)
+            12, // kgsl_device_waittimestamp_ctxtid
+            24, // kgsl_device_getproperty
+            32, // kgsl_timestamp_event
+            40, // kgsl_devinfo (returned via GETPROPERTY)
+            48, // kgsl_gpumem_alloc_id, kgsl_gpuobj_alloc
+            64, // kgsl_gpu_command
+            72, // kgsl_gpumem_get_info
+        ];
+        self
+    }
 }
 
 /// Single IOCTL test result
@@ -206,10 +460,36 @@ pub struct IoctlResult {
     pub timestamp: Option<String>,
     /// Whether this IOCTL is considered potentially dangerous
     pub is_potentially_dangerous: bool,
+    /// When testing started, relative to the start of the scan
+    #[serde(default)]
+    pub scan_start: Duration,
+    /// When testing finished, relative to the start of the scan
+    #[serde(default)]
+    pub scan_end: Duration,
+    /// Share of repeated calls returning the most common errno, see `scan_type_statistical`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consistency: Option<f32>,
+    /// Whether repeated calls disagreed (`consistency` below 0.8)
+    #[serde(default)]
+    pub is_stateful: bool,
+    /// Input the argument buffer was prefilled with, see `scan_type_with_magic_buffers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_pattern: Option<Vec<u8>>,
+    /// Argument buffer after the call, kept in forensics mode for existing IOCTLs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_buffer: Option<Vec<u8>>,
+    /// Device the result came from, set when results of several devices are merged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_device: Option<String>,
+    /// Bytes the kernel wrote into the argument buffer (successful calls only)
+    #[cfg(feature = "diff")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_changes: Option<Vec<BufferChange>>,
 }
 
 impl IoctlResult {
     /// Create a new IoctlResult with optional discovered size
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         cmd: u32,
         dir: u8,
@@ -234,8 +514,45 @@ impl IoctlResult {
             discovered_size,
             timestamp: Some(chrono::Local::now().to_rfc3339()),
             is_potentially_dangerous,
+            scan_start: Duration::ZERO,
+            scan_end: Duration::ZERO,
+            consistency: None,
+            is_stateful: false,
+            input_pattern: None,
+            raw_buffer: None,
+            source_device: None,
+            #[cfg(feature = "diff")]
+            buffer_changes: None,
         }
     }
+
+    /// Result for `cmd` with direction, type, NR and size decoded from it
+    #[cfg(test)]
+    pub(crate) fn from_cmd_for_test(cmd: u32, interpretation: Interpretation) -> Self {
+        Self::new(
+            cmd,
+            (cmd >> 30) as u8,
+            (cmd >> 8) as u8,
+            cmd as u8,
+            ((cmd >> 16) & 0x3FFF) as u16,
+            (0, 0),
+            None,
+            interpretation,
+            None,
+            false,
+        )
+    }
+
+    /// Hex diff of the bytes the kernel changed, if any were recorded
+    #[cfg(feature = "diff")]
+    pub fn diff_buffer(&self) -> Option<String> {
+        self.buffer_changes.as_deref().map(diff::format_diff)
+    }
+
+    /// Time spent testing this IOCTL
+    pub fn scan_duration(&self) -> Duration {
+        self.scan_end.saturating_sub(self.scan_start)
+    }
     
     /// Check if this IOCTL appears to be valid (exists and might work)
     pub fn is_valid(&self) -> bool {
@@ -246,6 +563,181 @@ impl IoctlResult {
     pub fn is_successful(&self) -> bool {
         matches!(self.interpretation, Interpretation::Success)
     }
+
+    /// How interesting this result is as a profile detection IOCTL
+    ///
+    /// `Success` scores 4, `Exists` 2 and `Permission` 1. Dangerous IOCTLs
+    /// lose 2 points; a discovered size and a read+write direction add 1 each.
+    /// Anything else scores 0.
+    pub fn candidate_score(&self) -> i32 {
+        let mut score = match self.interpretation {
+            Interpretation::Success => 4,
+            Interpretation::Exists => 2,
+            Interpretation::Permission => 1,
+            Interpretation::NotExist | Interpretation::Unknown(_) => return 0,
+        };
+        if self.is_potentially_dangerous {
+            score -= 2;
+        }
+        if self.discovered_size.is_some() {
+            score += 1;
+        }
+        score + (self.dir.count_ones() as i32 - 1).max(0)
+    }
+
+    /// Format as a Linux `ioctl.h` macro invocation, e.g. `_IOWR(0x80, 0x03, u32)`
+    ///
+    /// Sizes without a matching primitive type are written as `char[N]`.
+    pub fn as_ioctl_macro(&self) -> String {
+        let arg_type = match self.size {
+            1 => "u8".to_string(),
+            2 => "u16".to_string(),
+            4 => "u32".to_string(),
+            8 => "u64".to_string(),
+            n => format!("char[{}]", n),
+        };
+
+        match self.dir {
+            1 => format!("_IOW(0x{:02x}, 0x{:02x}, {})", self.ty, self.nr, arg_type),
+            2 => format!("_IOR(0x{:02x}, 0x{:02x}, {})", self.ty, self.nr, arg_type),
+            3 => format!("_IOWR(0x{:02x}, 0x{:02x}, {})", self.ty, self.nr, arg_type),
+            _ => format!("_IO(0x{:02x}, 0x{:02x})", self.ty, self.nr),
+        }
+    }
+
+    /// Symbolic name of this command in `profiles`, see [`crate::profiles::cmd_name`]
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    pub fn cmd_name_in(&self, profiles: &[crate::profiles::IoctlProfile]) -> Option<String> {
+        crate::profiles::cmd_name(self.cmd, profiles)
+    }
+}
+
+/// How much of the planned `(ty, nr)` space a scan actually tested
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanCoverage {
+    /// Number of planned `(ty, nr)` combinations
+    pub planned_combinations: u32,
+    /// Planned combinations that were tested
+    pub actually_tested: u32,
+    /// `actually_tested` as a percentage of `planned_combinations`
+    pub coverage_percent: f64,
+    /// Contiguous untested NR values as `(ty, nr_start, nr_end)`
+    pub missing_ranges: Vec<(u8, u8, u8)>,
+}
+
+/// Machine-readable statistics of a discovery run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoverySummary {
+    /// Total number of tested IOCTLs
+    pub total_tested: usize,
+    /// IOCTLs that succeeded
+    pub successful: usize,
+    /// IOCTLs that exist but rejected the argument (EFAULT/EINVAL)
+    pub exists_efault: usize,
+    /// IOCTLs gated by permissions (EPERM/EACCES)
+    pub permission_gated: usize,
+    /// IOCTLs that do not exist
+    pub not_existent: usize,
+    /// IOCTLs of potentially dangerous types
+    pub dangerous_count: usize,
+    /// Safe type with the most valid IOCTLs
+    pub best_type: Option<u8>,
+    /// Sorted list of types with at least one valid IOCTL
+    pub unique_types_found: Vec<u8>,
+    /// Total number of IOCTL calls made
+    pub call_count: u32,
+    /// Time since the device was opened in milliseconds
+    pub elapsed_ms: u64,
+    /// Bytes of IOCTL buffers currently kept alive
+    pub active_buffer_bytes: usize,
+    /// Estimated heap memory used by the results
+    pub results_memory_bytes: usize,
+    /// Statistics per `source_device`, empty unless results were merged
+    pub per_device: BTreeMap<String, JsonStatistics>,
+}
+
+/// JSON document written by [`IoctlDiscovery::export_json`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonOutput {
+    /// All IOCTL results
+    pub results: Vec<IoctlResult>,
+    /// Aggregated statistics
+    pub statistics: JsonStatistics,
+    /// Export metadata
+    pub metadata: JsonMetadata,
+}
+
+/// Serializable state of a finished scan, without any open file descriptor
+///
+/// Produced by [`IoctlDiscovery::into_snapshot`], e.g. to scan once in a
+/// daemon and hand the results to clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoverySnapshot {
+    /// Device that was scanned
+    pub device: String,
+    /// Options the scan ran with
+    pub options: DiscoveryOptions,
+    /// All IOCTL results
+    pub results: Vec<IoctlResult>,
+    /// Total number of IOCTL calls made
+    pub call_count: u32,
+    /// Time between opening the device and taking the snapshot
+    pub elapsed: Duration,
+}
+
+/// Statistics section of a JSON export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonStatistics {
+    /// Total number of tested IOCTLs
+    pub total: usize,
+    /// IOCTLs that do not exist
+    pub not_existent: usize,
+    /// IOCTLs that exist in some form
+    pub potentially_existent: usize,
+    /// IOCTLs of potentially dangerous types
+    pub dangerous: usize,
+    /// IOCTLs that succeeded
+    pub successful: usize,
+    /// Statistics per `source_device`, only present for merged results
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub per_device: BTreeMap<String, JsonStatistics>,
+}
+
+impl JsonStatistics {
+    fn from_results<'a>(results: impl IntoIterator<Item = &'a IoctlResult>) -> Self {
+        let mut stats = Self {
+            total: 0,
+            not_existent: 0,
+            potentially_existent: 0,
+            dangerous: 0,
+            successful: 0,
+            per_device: BTreeMap::new(),
+        };
+        for r in results {
+            stats.total += 1;
+            match r.interpretation {
+                Interpretation::NotExist => stats.not_existent += 1,
+                Interpretation::Success => stats.successful += 1,
+                _ => {}
+            }
+            if r.is_potentially_dangerous {
+                stats.dangerous += 1;
+            }
+        }
+        stats.potentially_existent = stats.total - stats.not_existent;
+        stats
+    }
+}
+
+/// Metadata section of a JSON export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonMetadata {
+    /// Export timestamp (RFC 3339)
+    pub timestamp: String,
+    /// iodisco version that produced the export
+    pub iodisco_version: String,
+    /// Discovery options used for the scan
+    pub options: serde_json::Value,  // Use JSON value instead of DiscoveryOptions
 }
 
 /// Interpretation of an IOCTL call result
@@ -266,9 +758,13 @@ pub enum Interpretation {
 /// Structured result of IOCTL test
 #[derive(Debug)]
 pub struct IoctlTestResult {
+    /// Tested command number
     pub cmd: u32,
+    /// Raw return value of the ioctl call
     pub result: i32,
+    /// Error number (0 on success)
     pub errno: i32,
+    /// Whether the IOCTL exists and may return data
     pub returns_data: bool,
 }
 
@@ -290,11 +786,79 @@ impl IoctlTestResult {
     }
 }
 
+/// Result that behaves unexpectedly, see `IoctlDiscovery::anomalies`
+#[derive(Debug, Clone)]
+pub struct Anomaly<'a> {
+    /// The suspicious result
+    pub result: &'a IoctlResult,
+    /// Why it is suspicious
+    pub reason: String,
+}
+
+/// Kernel module behind a device node, see `IoctlDiscovery::verify_driver_loaded`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriverInfo {
+    /// Module name, e.g. "mali_kbase"
+    pub module_name: String,
+    /// Content of `/sys/module/<module>/version`, or "unknown"
+    pub version: String,
+    /// Whether the module is listed in `/proc/modules` or `/sys/module`
+    pub loaded: bool,
+}
+
+/// Module names by device node name, for nodes without a sysfs driver link
+const KNOWN_DRIVER_MODULES: &[(&str, &str)] = &[
+    ("mali", "mali_kbase"),
+    ("kgsl", "msm_kgsl"),
+    ("galcore", "galcore"),
+    ("pvrsrvkm", "pvrsrvkm"),
+];
+
 impl IoctlDiscovery {
+    /// Find the kernel module of `device_path` and check that it is loaded
+    ///
+    /// The module comes from the `/sys/dev/char` driver link of the node,
+    /// falling back to well-known names like `mali_kbase` for `/dev/mali0`.
+    /// Fails with `DeviceOpen` when no module can be determined.
+    pub fn verify_driver_loaded(device_path: &str) -> Result<DriverInfo, crate::error::DiscoveryError> {
+        let module_name = sysfs_driver_module(device_path)
+            .or_else(|| {
+                let node = device_path.rsplit('/').next().unwrap_or(device_path);
+                KNOWN_DRIVER_MODULES.iter()
+                    .find(|(prefix, _)| node.starts_with(prefix))
+                    .map(|(_, module)| module.to_string())
+            })
+            .ok_or_else(|| crate::error::DiscoveryError::DeviceOpen(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Cannot determine the kernel driver of {}", device_path),
+            )))?;
+
+        let sys_module = format!("/sys/module/{}", module_name);
+        let loaded = std::fs::read_to_string("/proc/modules")
+            .is_ok_and(|modules| module_listed(&modules, &module_name))
+            || std::fs::metadata(&sys_module).is_ok();
+        let version = std::fs::read_to_string(format!("{}/version", sys_module))
+            .map(|v| v.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        Ok(DriverInfo { module_name, version, loaded })
+    }
+
     /// Open device file for IOCTL discovery with safety checks
     pub fn open(device: &str, options: DiscoveryOptions) -> io::Result<Self> {
         // Validate options before opening device
         options.validate()?;
+
+        if options.verify_driver {
+            let driver = Self::verify_driver_loaded(device)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            if !driver.loaded {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Kernel module {} for {} is not loaded", driver.module_name, device)
+                ));
+            }
+        }
         
         let c_path = std::ffi::CString::new(device)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
@@ -305,7 +869,7 @@ impl IoctlDiscovery {
             // Only try read-write if we have to and warnings are enabled
             if options.warn_only_on_dangerous {
                 if options.verbosity.is_at_least(Verbosity::Normal) {
-                    eprintln!("⚠️  Falling back to O_RDWR mode (read-only failed)");
+                    progress_err!(options, "⚠️  Falling back to O_RDWR mode (read-only failed)");
                 }
                 fd = unsafe { libc::open(c_path.as_ptr(), libc::O_RDWR) };
             }
@@ -315,16 +879,111 @@ impl IoctlDiscovery {
             return Err(io::Error::last_os_error());
         }
 
+        #[cfg(feature = "signal-handling")]
+        let (shutdown, signal_ids) = match register_shutdown_signals() {
+            Ok(registered) => registered,
+            Err(e) => {
+                unsafe { libc::close(fd) };
+                return Err(e);
+            }
+        };
+
         Ok(Self {
             fd,
+            device: device.to_string(),
             results: Vec::new(),
             options,
-            call_counter: AtomicU32::new(0),
-            last_call_time: Instant::now(),
+            call_counters: new_call_counters(0),
+            start_time: Instant::now(),
             active_buffers: Vec::new(),
+            is_mali: device.contains("mali"),
+            scan_errors: Vec::new(),
+            #[cfg(feature = "signal-handling")]
+            shutdown,
+            #[cfg(feature = "signal-handling")]
+            signal_ids,
         })
     }
 
+    /// Close the device and keep only the serializable scan state
+    pub fn into_snapshot(mut self) -> DiscoverySnapshot {
+        DiscoverySnapshot {
+            device: std::mem::take(&mut self.device),
+            options: self.options.clone(),
+            results: std::mem::take(&mut self.results),
+            call_count: self.get_call_count(),
+            elapsed: self.start_time.elapsed(),
+        }
+    }
+
+    /// Scanner without a device, holding the state of `snapshot`
+    ///
+    /// Every IOCTL call on it fails; exports and summaries work as usual.
+    pub(crate) fn from_snapshot(snapshot: &DiscoverySnapshot) -> Self {
+        let now = Instant::now();
+        Self {
+            fd: -1,
+            device: snapshot.device.clone(),
+            results: snapshot.results.clone(),
+            options: snapshot.options.clone(),
+            call_counters: new_call_counters(snapshot.call_count),
+            start_time: now.checked_sub(snapshot.elapsed).unwrap_or(now),
+            active_buffers: Vec::new(),
+            is_mali: snapshot.device.contains("mali"),
+            scan_errors: Vec::new(),
+            #[cfg(feature = "signal-handling")]
+            shutdown: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "signal-handling")]
+            signal_ids: Vec::new(),
+        }
+    }
+
+    /// Number of results collected before a SIGINT / SIGTERM stopped the scan
+    ///
+    /// Returns 0 when no signal arrived, so a non-zero value means the
+    /// results are partial.
+    pub fn partial_results_count(&self) -> usize {
+        if self.shutdown_requested() {
+            self.results.len()
+        } else {
+            0
+        }
+    }
+
+    #[cfg(feature = "signal-handling")]
+    fn shutdown_requested(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    #[cfg(not(feature = "signal-handling"))]
+    fn shutdown_requested(&self) -> bool {
+        false
+    }
+
+    /// Issue the configured `warm_up_ioctls`, discarding their results
+    ///
+    /// The first IOCTL is issued `warm_up_count` times (at least once), the
+    /// others once each. Returns the number of calls made.
+    pub fn warm_up(&mut self) -> io::Result<u32> {
+        let cmds = self.options.warm_up_ioctls.clone();
+        let mut calls = 0;
+        for (i, &cmd) in cmds.iter().enumerate() {
+            let repeats = if i == 0 { self.options.warm_up_count.max(1) } else { 1 };
+            let size = ((cmd >> 16) & 0x3FFF) as usize;
+            let arg = if size == 0 { 0 } else { self.alloc_buffer(size)? };
+            for _ in 0..repeats {
+                self.test_ioctl(cmd, arg)?;
+                calls += 1;
+            }
+        }
+        Ok(calls)
+    }
+
+    /// Path of the device this scanner was opened on
+    pub fn device_path(&self) -> &str {
+        &self.device
+    }
+
     /// Validate configuration
     pub fn validate_configuration(&self) -> io::Result<()> {
         self.options.validate()
@@ -340,8 +999,38 @@ impl IoctlDiscovery {
         self.active_buffers.clear();
     }
 
+    /// Check that the device still responds
+    ///
+    /// Mali devices get a `GET_VERSION` IOCTL with a null argument, where
+    /// EINVAL and EFAULT both count as alive, as does EPERM from a seccomp or
+    /// SELinux policy blocking the probe. Other devices use `fstat`.
+    pub fn ping(&self) -> bool {
+        if self.fd < 0 {
+            return false;
+        }
+        if self.options.dry_run {
+            return true;
+        }
+
+        if self.is_mali {
+            let result = unsafe { libc::ioctl(self.fd, MALI_GET_VERSION as _, 0usize) };
+            if result >= 0 {
+                return true;
+            }
+            matches!(io::Error::last_os_error().raw_os_error(), Some(1) | Some(14) | Some(22))
+        } else {
+            self.is_device_alive()
+        }
+    }
+
     /// Execute single ioctl call with rate limiting and safety checks
     pub fn test_ioctl(&self, cmd: u32, arg: usize) -> io::Result<(i32, i32)> {
+        // Dry run: record the call, but pretend nothing exists
+        if self.options.dry_run {
+            self.thread_call_counter().fetch_add(1, Ordering::SeqCst);
+            return Ok((0, 25));
+        }
+
         // Rate limiting
         self.enforce_rate_limit()?;
         
@@ -353,16 +1042,48 @@ impl IoctlDiscovery {
             ));
         }
         
-        let result = unsafe { libc::ioctl(self.fd, cmd as i32, arg) };
+        let result = unsafe { libc::ioctl(self.fd, cmd as _, arg) };
         let errno = if result < 0 {
             io::Error::last_os_error().raw_os_error().unwrap_or(-1)
         } else {
             0
         };
+
+        let buffer_size = self.active_buffers.iter()
+            .find(|b| b.as_ptr() as usize == arg)
+            .map_or(0, |b| b.len());
+        self.audit(cmd, arg == 0, result, errno, buffer_size);
         
         Ok((result, errno))
     }
 
+    /// `test_ioctl`, repeated while the driver answers EAGAIN or EBUSY
+    ///
+    /// Waits `backoff_ms` before the first retry and doubles the wait after
+    /// each one. Returns the last result after `max_retries` retries.
+    pub fn test_ioctl_with_retry(&self, cmd: u32, arg: usize, max_retries: u8, backoff_ms: u64) -> io::Result<(i32, i32)> {
+        let mut result = self.test_ioctl(cmd, arg)?;
+        let mut delay = backoff_ms;
+        for _ in 0..max_retries {
+            if !matches!(result.1, libc::EAGAIN | libc::EBUSY) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(delay));
+            delay = delay.saturating_mul(2);
+            result = self.test_ioctl(cmd, arg)?;
+        }
+        Ok(result)
+    }
+
+    /// Scan call honoring `retry_on_eagain`
+    fn scan_ioctl(&self, cmd: u32, arg: usize) -> io::Result<(i32, i32)> {
+        if self.options.retry_on_eagain {
+            self.test_ioctl_with_retry(cmd, arg, self.options.max_retries, RETRY_BACKOFF_MS)
+        } else {
+            self.test_ioctl(cmd, arg)
+        }
+    }
+
     /// Test single IOCTL command and return structured test result
     pub fn test_single_ioctl(&self, cmd: u32) -> io::Result<IoctlTestResult> {
         let (result, errno) = self.test_ioctl(cmd, 0)?;
@@ -375,39 +1096,182 @@ impl IoctlDiscovery {
         })
     }
 
+    /// Query the DRM driver name and version (DRI render nodes only)
+    pub fn query_drm_version(&self) -> io::Result<DrmDriverInfo> {
+        drm::query_version(|cmd, arg| self.test_ioctl(cmd, arg))
+    }
+
     /// Execute IOCTL with buffer and return the resulting data (if any)
     pub fn execute_ioctl(&mut self, cmd: u32, buffer_size: usize) -> io::Result<Vec<u8>> {
+        self.execute_ioctl_diff(cmd, buffer_size).map(|(_, after)| after)
+    }
+
+    /// Execute IOCTL with buffer and return the buffer as `(before, after)`
+    ///
+    /// Pass both halves to [`diff_buffers`](crate::discovery::diff_buffers)
+    /// to see which bytes the kernel wrote.
+    pub fn execute_ioctl_diff(&mut self, cmd: u32, buffer_size: usize) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        let before = vec![0u8; buffer_size];
+        let after = self.execute_ioctl_with_input(cmd, &before)?;
+        Ok((before, after))
+    }
+
+    /// Execute IOCTL on a buffer pre-filled with `input` and return the resulting data
+    ///
+    /// Goes through the same dry-run, rate limit and liveness checks as
+    /// `test_ioctl`; a dry run hands `input` back unchanged.
+    pub fn execute_ioctl_with_input(&mut self, cmd: u32, input: &[u8]) -> io::Result<Vec<u8>> {
+        // Dry run: record the call, but leave the buffer untouched
+        if self.options.dry_run {
+            self.thread_call_counter().fetch_add(1, Ordering::SeqCst);
+            return Ok(input.to_vec());
+        }
+
+        self.enforce_rate_limit()?;
+        if !self.is_device_alive() {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "Device appears to be unresponsive"
+            ));
+        }
+
         // Create buffer and keep it alive
-        let buffer = vec![0u8; buffer_size].into_boxed_slice();
-        let ptr = buffer.as_ptr() as usize;
-        
-        // Store buffer to keep it alive
-        self.active_buffers.push(buffer);
+        let ptr = self.alloc_buffer(input.len())?;
+        if let Some(buffer) = self.active_buffers.last_mut() {
+            buffer.copy_from_slice(input);
+        }
         
-        let result = unsafe { libc::ioctl(self.fd, cmd as i32, ptr) };
+        let result = unsafe { libc::ioctl(self.fd, cmd as _, ptr) };
+        let error = io::Error::last_os_error();
+        let errno = if result < 0 { error.raw_os_error().unwrap_or(-1) } else { 0 };
+        self.audit(cmd, false, result, errno, input.len());
 
+        // Retrieve the buffer data
+        let buffer = self.active_buffers.pop();
         if result < 0 {
-            Err(io::Error::last_os_error())
+            Err(error)
+        } else if let Some(buffer) = buffer {
+            Ok(buffer.to_vec())
         } else {
-            // Retrieve the buffer data
-            if let Some(buffer) = self.active_buffers.pop() {
-                Ok(buffer.to_vec())
-            } else {
-                Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Buffer lost - this should not happen"
-                ))
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Buffer lost - this should not happen"
+            ))
+        }
+    }
+
+    /// Allocate a zeroed buffer that stays alive for the kernel to write into
+    ///
+    /// Fails if the allocation would exceed `max_buffer_memory_bytes`.
+    fn alloc_buffer(&mut self, size: usize) -> io::Result<usize> {
+        let in_use = self.active_buffer_bytes();
+        if in_use + size > self.options.max_buffer_memory_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!(
+                    "Buffer memory limit of {} bytes exceeded ({} in use, {} requested)",
+                    self.options.max_buffer_memory_bytes, in_use, size
+                )
+            ));
+        }
+
+        let buffer = vec![0u8; size].into_boxed_slice();
+        let ptr = buffer.as_ptr() as usize;
+        self.active_buffers.push(buffer);
+        Ok(ptr)
+    }
+
+    /// Total size of all buffers currently kept alive for the kernel
+    pub fn active_buffer_bytes(&self) -> usize {
+        self.active_buffers.iter().map(|b| b.len()).sum()
+    }
+
+    /// Estimated heap memory used by the collected results
+    pub fn results_memory_bytes(&self) -> usize {
+        let strings: usize = self.results.iter()
+            .filter_map(|r| r.timestamp.as_ref())
+            .map(|t| t.capacity())
+            .sum();
+        self.results.capacity() * std::mem::size_of::<IoctlResult>() + strings
+    }
+
+    /// Estimated total heap memory used by this scanner (buffers and results)
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.active_buffer_bytes() + self.results_memory_bytes()
+    }
+
+    /// Execute IOCTL and interpret the returned buffer as a plain-old-data struct
+    ///
+    /// Allocates a zeroed buffer of `size_of::<T>()` bytes, issues the IOCTL
+    /// and reinterprets the returned bytes as `T`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bytemuck::{Pod, Zeroable};
+    /// use iodisco::discovery::{DiscoveryConfig, IoctlDiscovery};
+    ///
+    /// #[repr(C)]
+    /// #[derive(Debug, Clone, Copy, Pod, Zeroable)]
+    /// struct MaliVersionInfo {
+    ///     major: u32,
+    ///     minor: u32,
+    /// }
+    ///
+    /// let mut discovery = IoctlDiscovery::open("/dev/mali0", DiscoveryConfig::quick().into())?;
+    /// let version: MaliVersionInfo = discovery.execute_ioctl_typed(0xC0088000)?;
+    /// println!("Driver version: {}.{}", version.major, version.minor);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "typed-ioctl")]
+    pub fn execute_ioctl_typed<T: bytemuck::Pod>(&mut self, cmd: u32) -> io::Result<T> {
+        let data = self.execute_ioctl(cmd, std::mem::size_of::<T>())?;
+
+        match bytemuck::try_from_bytes::<T>(&data) {
+            Ok(value) => Ok(*value),
+            // A Vec<u8> gives no alignment guarantee for T - copy out instead
+            Err(bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned) => {
+                Ok(bytemuck::pod_read_unaligned(&data))
             }
+            Err(e) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to interpret IOCTL 0x{:08x} result: {:?}", cmd, e)
+            )),
         }
     }
 
     /// Analyze one specific IOCTL combination
     fn analyze_ioctl(&mut self, dir: u8, ty: u8, nr: u8, size: u16) -> io::Result<()> {
+        if self.options.deny_nrs.contains(&nr) {
+            return Ok(());
+        }
+        let result = self.probe_ioctl(dir, ty, nr, size)?;
+        self.results.push(result);
+        Ok(())
+    }
+
+    /// Test one IOCTL command and return the result without storing it
+    fn probe_ioctl(&mut self, dir: u8, ty: u8, nr: u8, size: u16) -> io::Result<IoctlResult> {
+        self.probe_ioctl_with_pattern(dir, ty, nr, size, None)
+    }
+
+    /// `probe_ioctl` with the argument buffer prefilled from `pattern`
+    ///
+    /// The pattern is zero-extended or truncated to the buffer size.
+    fn probe_ioctl_with_pattern(
+        &mut self,
+        dir: u8,
+        ty: u8,
+        nr: u8,
+        size: u16,
+        pattern: Option<&[u8]>,
+    ) -> io::Result<IoctlResult> {
         let is_dangerous = self.is_potentially_dangerous(ty);
         let cmd = ((dir as u32) << 30) | ((size as u32) << 16) | ((ty as u32) << 8) | (nr as u32);
+        let scan_start = self.start_time.elapsed();
 
         // Test with null pointer first
-        let null_result = self.test_ioctl(cmd, 0)?;
+        let null_result = self.scan_ioctl(cmd, 0)?;
 
         let mut ptr_result = None;
         let mut discovered_size = None;
@@ -415,11 +1279,10 @@ impl IoctlDiscovery {
 
         if null_result.1 != 25 {  // Not ENOTTY - exists in some form
             // Test with buffer of specified size
-            let buffer = vec![0u8; size as usize].into_boxed_slice();
-            let ptr = buffer.as_ptr() as usize;
-            self.active_buffers.push(buffer); // Keep buffer alive
+            let ptr = self.alloc_buffer(size as usize)?;
+            self.fill_last_buffer(pattern);
             
-            ptr_result = Some(self.test_ioctl(cmd, ptr)?);
+            ptr_result = Some(self.scan_ioctl(cmd, ptr)?);
             
             let initial_ptr = ptr_result.unwrap_or(null_result);
             final_interpretation = match initial_ptr {
@@ -440,11 +1303,10 @@ impl IoctlDiscovery {
                     discovered_size = Some(found_size);
                     // Update cmd and result with discovered size
                     let new_cmd = ((dir as u32) << 30) | ((found_size as u32) << 16) | ((ty as u32) << 8) | (nr as u32);
-                    let new_buffer = vec![0u8; found_size as usize].into_boxed_slice();
-                    let new_ptr = new_buffer.as_ptr() as usize;
-                    self.active_buffers.push(new_buffer);
+                    let new_ptr = self.alloc_buffer(found_size as usize)?;
+                    self.fill_last_buffer(pattern);
                     
-                    ptr_result = Some(self.test_ioctl(new_cmd, new_ptr)?);
+                    ptr_result = Some(self.scan_ioctl(new_cmd, new_ptr)?);
                     
                     // Re-evaluate interpretation with new size
                     final_interpretation = match ptr_result.unwrap() {
@@ -456,7 +1318,7 @@ impl IoctlDiscovery {
             }
         }
 
-        self.results.push(IoctlResult::new(
+        let mut result = IoctlResult::new(
             cmd,
             dir,
             ty,
@@ -467,16 +1329,45 @@ impl IoctlDiscovery {
             final_interpretation,
             discovered_size,
             is_dangerous,
-        ));
+        );
+        result.scan_start = scan_start;
+        result.scan_end = self.start_time.elapsed();
+        result.input_pattern = pattern.map(|p| p.to_vec());
 
-        Ok(())
+        if self.options.forensics_mode && ptr_result.is_some() && result.is_valid() {
+            result.raw_buffer = self.active_buffers.last().map(|b| b.to_vec());
+        }
+
+        // Buffers start zeroed or patterned, so any other byte was written by the kernel
+        #[cfg(feature = "diff")]
+        if result.is_successful() {
+            if let Some(buffer) = self.active_buffers.last() {
+                let mut before = vec![0; buffer.len()];
+                if let Some(pattern) = pattern {
+                    let len = pattern.len().min(before.len());
+                    before[..len].copy_from_slice(&pattern[..len]);
+                }
+                result.buffer_changes = Some(diff::diff_buffers(&before, buffer));
+            }
+        }
+
+        Ok(result)
+    }
+
+    // Copy `pattern` into the zeroed buffer allocated last, truncated to its size
+    fn fill_last_buffer(&mut self, pattern: Option<&[u8]>) {
+        if let (Some(pattern), Some(buffer)) = (pattern, self.active_buffers.last_mut()) {
+            let len = pattern.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&pattern[..len]);
+        }
     }
 
     /// Try to discover correct buffer size for an IOCTL
     fn try_discover_size(&mut self, dir: u8, ty: u8, nr: u8, original_size: u16) -> io::Result<Option<u16>> {
         let mut attempts = 0;
         
-        for &test_size in &self.options.size_discovery_candidates {
+        let candidates = self.options.size_discovery_candidates.clone();
+        for test_size in candidates {
             if test_size == original_size { 
                 continue; 
             }
@@ -486,9 +1377,7 @@ impl IoctlDiscovery {
             }
             
             let cmd = ((dir as u32) << 30) | ((test_size as u32) << 16) | ((ty as u32) << 8) | (nr as u32);
-            let buffer = vec![0u8; test_size as usize].into_boxed_slice();
-            let ptr = buffer.as_ptr() as usize;
-            self.active_buffers.push(buffer);
+            let ptr = self.alloc_buffer(test_size as usize)?;
             
             let result = self.test_ioctl(cmd, ptr)?;
             
@@ -534,63 +1423,511 @@ impl IoctlDiscovery {
 
     /// Scan all possible IOCTLs for one specific type/magic number
     pub fn scan_type(&mut self, ty: u8) -> io::Result<()> {
-        if !self.is_allowed(ty) {
-            let msg = if let Some(risk_desc) = self.get_risk_description(ty) {
-                format!("IOCTL type 0x{:02x}: {}", ty, risk_desc)
-            } else {
-                format!("IOCTL type 0x{:02x} is not allowed by configuration", ty)
-            };
-
-            if self.options.warn_only_on_dangerous {
-                if self.options.verbosity.is_at_least(Verbosity::Normal) {
-                    eprintln!("⚠️  WARNING: {}", msg);
-                    eprintln!("   Continuing at user's risk...");
-                }
-                // Log warning
-                self.log_warning(&msg);
-            } else {
-                return Err(io::Error::new(
-                    io::ErrorKind::PermissionDenied,
-                    format!("{}\nUse 'allow_types' configuration or 'warn_only_on_dangerous' to override", msg)
-                ));
-            }
+        match self.scan_type_until(ty, None)? {
+            ScanStopReason::Error(e) => Err(e),
+            _ => Ok(()),
         }
+    }
 
-        if self.options.verbosity.is_at_least(Verbosity::Debug) {
-            println!("🔍 Scanning Type 0x{:02x}...", ty);
+    /// Scan one type like `scan_type`, stopping early once `cancel` is set
+    ///
+    /// The token is checked every 16 NR values. Disallowed types are still
+    /// reported as `Err`; problems during the scan end up in the stop reason.
+    pub fn scan_type_cancelable(&mut self, ty: u8, cancel: Arc<AtomicBool>) -> io::Result<ScanStopReason> {
+        self.scan_type_until(ty, Some(&cancel))
+    }
+
+    /// Scan one type with every NR value processed as its own rayon task
+    ///
+    /// Each rayon thread works on its own `dup` of the device fd. The call
+    /// limits are shared with this scanner; `max_calls_per_second` is split
+    /// evenly across the threads. Results end up in `self.results`, sorted by
+    /// `(ty, nr, dir, size)` so the order does not depend on scheduling.
+    #[cfg(feature = "parallel")]
+    pub fn scan_type_parallel(&mut self, ty: u8) -> io::Result<()> {
+        self.check_type_allowed(ty)?;
+        if !self.ping() {
+            return Err(device_unresponsive(format!("Device not responding before scanning type 0x{:02x}", ty)));
         }
 
-        let sizes = [1u16, 2, 4, 8, 16, 32, 64, 128];
-        let dirs = [0u8, 1, 2, 3];
+        let threads = rayon::current_num_threads().max(1);
+        self.grow_call_counters(threads);
+        let workers = (0..threads)
+            .map(|_| self.worker(threads).map(Mutex::new))
+            .collect::<io::Result<Vec<_>>>()?;
+        let results = Mutex::new(Vec::new());
 
-        for nr in 0x00..=0xFFu8 {
-            // Check if we should focus on specific NR values
+        rayon::scope(|scope| {
+            for nr in 0x00..=0xFFu8 {
+                if self.options.focus_nr.as_ref().is_some_and(|focus| !focus.contains(&nr)) {
+                    continue;
+                }
+
+                let (workers, results) = (&workers, &results);
+                scope.spawn(move |_| {
+                    // Each pool thread only ever locks its own worker, so this never contends
+                    let index = rayon::current_thread_index().unwrap_or(0) % workers.len();
+                    let mut worker = workers[index].lock().unwrap_or_else(|e| e.into_inner());
+                    if worker.call_limit_reached() {
+                        return;
+                    }
+                    worker.scan_nr(ty, nr);
+                    let found = std::mem::take(&mut worker.results);
+                    results.lock().unwrap_or_else(|e| e.into_inner()).extend(found);
+                });
+            }
+        });
+
+        for worker in workers {
+            let mut worker = worker.into_inner().unwrap_or_else(|e| e.into_inner());
+            self.scan_errors.append(&mut worker.scan_errors);
+        }
+
+        let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        results.sort_by_key(|r| (r.ty, r.nr, r.dir, r.size));
+        self.results.append(&mut results);
+        Ok(())
+    }
+
+    /// Scanner for one thread of `scan_type_parallel`, working on a `dup` of our fd
+    #[cfg(feature = "parallel")]
+    fn worker(&self, threads: usize) -> io::Result<Self> {
+        // SAFETY: `dup` only returns a new descriptor for the same open file
+        // description, so every worker talks to the same driver-side context.
+        // Concurrent `ioctl` calls on one open file are routine for GPU
+        // drivers: userspace drivers submit work and query state from many
+        // threads on a single fd, and Mali kbase serializes per-context state
+        // (`kbase_context` / `kbase_file`) with its own locks. Using separate
+        // descriptors therefore adds no new races; it only keeps the threads
+        // from depending on a single fd number.
+        let fd = unsafe { libc::dup(self.fd) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut options = self.options.clone();
+        options.max_calls_per_second = options.max_calls_per_second
+            .map(|max| (max / threads as u32).max(1));
+
+        Ok(Self {
+            fd,
+            device: self.device.clone(),
+            results: Vec::new(),
+            options,
+            call_counters: Arc::clone(&self.call_counters),
+            start_time: self.start_time,
+            active_buffers: Vec::new(),
+            is_mali: self.is_mali,
+            scan_errors: Vec::new(),
+            #[cfg(feature = "signal-handling")]
+            shutdown: Arc::clone(&self.shutdown),
+            #[cfg(feature = "signal-handling")]
+            signal_ids: Vec::new(),
+        })
+    }
+
+    /// Scan only the NR values `nr_start..=nr_end` of one type
+    ///
+    /// Useful when a driver allocates NRs in blocks, e.g. 0x00-0x1F for
+    /// queries and 0x20-0x3F for job submission.
+    pub fn scan_nr_range(&mut self, ty: u8, nr_start: u8, nr_end: u8) -> io::Result<()> {
+        if nr_start > nr_end {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid NR range 0x{:02x}..=0x{:02x}: start is after end", nr_start, nr_end)
+            ));
+        }
+
+        match self.scan_range_until(ty, nr_start..=nr_end, None)? {
+            ScanStopReason::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Scan several non-overlapping NR ranges of one type, in the given order
+    ///
+    /// All ranges are validated before the first IOCTL call.
+    pub fn scan_nr_ranges(&mut self, ty: u8, ranges: &[(u8, u8)]) -> io::Result<()> {
+        if let Some((start, end)) = ranges.iter().find(|(start, end)| start > end) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid NR range 0x{:02x}..=0x{:02x}: start is after end", start, end)
+            ));
+        }
+
+        let mut sorted = ranges.to_vec();
+        sorted.sort_unstable();
+        if let Some(pair) = sorted.windows(2).find(|pair| pair[0].1 >= pair[1].0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "NR ranges 0x{:02x}..=0x{:02x} and 0x{:02x}..=0x{:02x} overlap",
+                    pair[0].0, pair[0].1, pair[1].0, pair[1].1
+                )
+            ));
+        }
+
+        for &(start, end) in ranges {
+            self.scan_nr_range(ty, start, end)?;
+        }
+        Ok(())
+    }
+
+    /// Scan the neighbourhood of every IOCTL a profile knows about
+    ///
+    /// All commands of the profile are split into `(type, nr)`; for each type
+    /// only `nr - 10..=nr + 10` around the known NRs is scanned, which finds
+    /// vendor extensions next to the documented commands in a fraction of the
+    /// time of a full `scan_type`.
+    #[cfg(any(feature = "mali", feature = "adreno"))]
+    pub fn scan_with_known_profile(&mut self, profile: &crate::profiles::IoctlProfile) -> io::Result<()> {
+        const RADIUS: u8 = 10;
+
+        let mut nrs_by_type: std::collections::BTreeMap<u8, Vec<u8>> = std::collections::BTreeMap::new();
+        let known = profile.detection_ioctls.iter()
+            .chain(&profile.version_ioctl)
+            .chain(&profile.gpu_info_ioctl)
+            .chain(&profile.features_ioctl);
+        for ioctl in known {
+            nrs_by_type.entry((ioctl.cmd >> 8) as u8).or_default().push(ioctl.cmd as u8);
+        }
+
+        for (ty, mut nrs) in nrs_by_type {
+            nrs.sort_unstable();
+            // Merge overlapping windows so every NR is scanned once
+            let mut ranges: Vec<(u8, u8)> = Vec::new();
+            for nr in nrs {
+                let (start, end) = (nr.saturating_sub(RADIUS), nr.saturating_add(RADIUS));
+                match ranges.last_mut() {
+                    Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                    _ => ranges.push((start, end)),
+                }
+            }
+            self.scan_nr_ranges(ty, &ranges)?;
+        }
+        Ok(())
+    }
+
+    fn scan_type_until(&mut self, ty: u8, cancel: Option<&AtomicBool>) -> io::Result<ScanStopReason> {
+        self.scan_range_until(ty, 0x00..=0xFF, cancel)
+    }
+
+    /// Scan one type like `scan_type_cancelable`, sending progress events to `reporter`
+    pub fn scan_type_with_progress(
+        &mut self,
+        ty: u8,
+        cancel: Option<&AtomicBool>,
+        reporter: &dyn ProgressReporter,
+    ) -> io::Result<ScanStopReason> {
+        self.scan_range_reporting(ty, 0x00..=0xFF, cancel, reporter)
+    }
+
+    fn scan_range_until(
+        &mut self,
+        ty: u8,
+        nrs: std::ops::RangeInclusive<u8>,
+        cancel: Option<&AtomicBool>,
+    ) -> io::Result<ScanStopReason> {
+        // Debug verbosity keeps the classic dots on stdout
+        if !self.options.verbosity.is_at_least(Verbosity::Debug) {
+            return self.scan_range_reporting(ty, nrs, cancel, &NullProgressReporter);
+        }
+        match self.options.progress_output.clone() {
+            Some(output) => self.scan_range_reporting(ty, nrs, cancel, &WriterProgressReporter(output)),
+            None => self.scan_range_reporting(ty, nrs, cancel, &StdoutProgressReporter),
+        }
+    }
+
+    fn scan_range_reporting(
+        &mut self,
+        ty: u8,
+        nrs: std::ops::RangeInclusive<u8>,
+        cancel: Option<&AtomicBool>,
+        reporter: &dyn ProgressReporter,
+    ) -> io::Result<ScanStopReason> {
+        self.check_type_allowed(ty)?;
+        reporter.on_type_start(ty);
+
+        let first = self.results.len();
+        let reason = self.scan_nrs(ty, nrs, cancel, reporter);
+        if !matches!(reason, ScanStopReason::Error(_)) {
+            let valid_count = self.results[first..].iter().filter(|r| r.is_valid()).count();
+            reporter.on_type_complete(ty, valid_count);
+        }
+        Ok(reason)
+    }
+
+    fn scan_nrs(
+        &mut self,
+        ty: u8,
+        nrs: std::ops::RangeInclusive<u8>,
+        cancel: Option<&AtomicBool>,
+        reporter: &dyn ProgressReporter,
+    ) -> ScanStopReason {
+        for (i, nr) in nrs.enumerate() {
+            if i % 16 == 0 && cancel.is_some_and(|c| c.load(Ordering::SeqCst)) {
+                return ScanStopReason::Cancelled;
+            }
+
+            if i % 16 == 0 && self.timed_out() {
+                return match self.options.timeout_behavior {
+                    TimeoutBehavior::ReturnPartial => ScanStopReason::TimedOut,
+                    TimeoutBehavior::ReturnError => ScanStopReason::Error(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("Scan timeout exceeded while scanning type 0x{:02x} at nr=0x{:02x}", ty, nr)
+                    )),
+                };
+            }
+
+            if i % 16 == 0 && self.shutdown_requested() {
+                if self.options.verbosity.is_at_least(Verbosity::Normal) {
+                    progress_err!(self.options, 
+                        "⚠️  Scan interrupted by signal, {} partial results collected",
+                        self.results.len()
+                    );
+                }
+                return ScanStopReason::Cancelled;
+            }
+
+            // The GPU node can vanish mid-scan (driver crash, thermal shutdown)
+            if i % 64 == 0 && !self.ping() {
+                return ScanStopReason::Error(device_unresponsive(
+                    format!("Device stopped responding while scanning type 0x{:02x} at nr=0x{:02x}", ty, nr)
+                ));
+            }
+
+            if self.call_limit_reached() {
+                return ScanStopReason::RateLimitExceeded;
+            }
+
+            // Check if we should focus on specific NR values
             if let Some(focus_nrs) = &self.options.focus_nr {
                 if !focus_nrs.contains(&nr) {
                     continue;
                 }
             }
 
-            if self.options.verbosity.is_at_least(Verbosity::Debug) && nr % 16 == 0 {
-                print!(".");
-                let _ = io::stdout().flush();
+            let before = self.results.len();
+            self.scan_nr(ty, nr);
+            reporter.on_nr_complete(ty, nr, self.results.len() - before);
+        }
+
+        ScanStopReason::Completed
+    }
+
+    /// Scan one type lazily, yielding each result instead of storing it
+    ///
+    /// Results are not added to `self.results`. A disallowed type yields a
+    /// single error; the stream ends early once `max_total_calls` is reached.
+    pub fn scan_type_stream(&mut self, ty: u8) -> impl Iterator<Item = io::Result<IoctlResult>> + '_ {
+        let mut rejected = self.check_type_allowed(ty).err();
+        let mut done = false;
+
+        let focus_nr = self.options.focus_nr.clone();
+        let deny_nrs = self.options.deny_nrs.clone();
+        let mut combinations = (0x00..=0xFFu8)
+            .filter(move |nr| focus_nr.as_ref().map_or(true, |focus| focus.contains(nr)) && !deny_nrs.contains(nr))
+            .flat_map(|nr| {
+                SCAN_SIZES.iter().flat_map(move |&size| SCAN_DIRS.iter().map(move |&dir| (nr, size, dir)))
+            });
+
+        std::iter::from_fn(move || {
+            if let Some(e) = rejected.take() {
+                done = true;
+                return Some(Err(e));
+            }
+            if done || self.call_limit_reached() {
+                return None;
+            }
+            let (nr, size, dir) = combinations.next()?;
+            Some(self.probe_ioctl(dir, ty, nr, size))
+        })
+    }
+
+    /// Number of IOCTL calls a full `scan_type` of `types` would issue at most
+    ///
+    /// Accounts for `focus_nr` and `deny_nrs` and skips types rejected by the
+    /// allow/deny configuration, also under `warn_only_on_dangerous`. Each NR
+    /// value is tried with every size and direction.
+    pub fn planned_scan_count(&self, types: &[u8]) -> u64 {
+        let nr_count = (0x00..=0xFFu8).filter(|&nr| self.is_planned_nr(nr)).count() as u64;
+        let per_nr = (SCAN_SIZES.len() * SCAN_DIRS.len()) as u64;
+
+        let scanned_types = types.iter().filter(|&&ty| self.is_allowed(ty)).count() as u64;
+        scanned_types * nr_count * per_nr
+    }
+
+    /// Whether a full scan tests `nr`, given `focus_nr` and `deny_nrs`
+    fn is_planned_nr(&self, nr: u8) -> bool {
+        self.options.focus_nr.as_ref().map_or(true, |focus| focus.contains(&nr))
+            && !self.options.deny_nrs.contains(&nr)
+    }
+
+    /// Scan only the given NR values for one specific type/magic number
+    ///
+    /// Unlike the global `focus_nr` option this is chosen per call, which is
+    /// useful when the interesting NR values are already known from kernel headers.
+    pub fn scan_focused(&mut self, ty: u8, nrs: &[u8]) -> io::Result<()> {
+        self.check_type_allowed(ty)?;
+
+        if self.options.verbosity.is_at_least(Verbosity::Debug) {
+            progress!(self.options, "🔍 Scanning Type 0x{:02x} ({} NR values)...", ty, nrs.len());
+        }
+
+        for &nr in nrs {
+            if self.call_limit_reached() {
+                break;
+            }
+            self.scan_nr(ty, nr);
+        }
+
+        Ok(())
+    }
+
+    /// Test all direction/size combinations for a single NR value
+    fn scan_nr(&mut self, ty: u8, nr: u8) {
+        for &size in SCAN_SIZES {
+            for &dir in SCAN_DIRS {
+                if let Err(e) = self.analyze_ioctl(dir, ty, nr, size) {
+                    if self.options.verbosity.is_at_least(Verbosity::Normal) {
+                        progress_err!(self.options, "Error analyzing 0x{:02x}.{:02x} (dir={}, size={}): {}", ty, nr, dir, size, e);
+                    }
+                    self.scan_errors.push((ty, nr, dir, size, e));
+                    // Only skip this combination, unless every further call would fail too
+                    if self.call_limit_reached() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Scan one type, trying each of `patterns` as input before the usual zero fill
+    ///
+    /// Some drivers check a magic or version field (e.g. `0x4d414c49`, "MALI")
+    /// before doing anything else and answer EINVAL to zeroed buffers. For every
+    /// `(nr, size, dir)` combination the patterns are tried in order, zero-extended
+    /// or truncated to the argument size; the first successful call is recorded
+    /// with its `input_pattern`. Without a success the standard zero-filled
+    /// probe is recorded. Patterned input reaches write IOCTLs too, so only use
+    /// this on test devices.
+    pub fn scan_type_with_magic_buffers(&mut self, ty: u8, patterns: &[Vec<u8>]) -> io::Result<()> {
+        self.scan_combinations(ty, |scanner, dir, nr, size| {
+            scanner.analyze_ioctl_with_patterns(dir, ty, nr, size, patterns)
+        })
+    }
+
+    /// Scan one type, calling every `(nr, size, dir)` combination `samples` times
+    ///
+    /// The first call is recorded, with `consistency` set to the share of
+    /// samples returning its most common errno. Below 0.8 the IOCTL is marked
+    /// `is_stateful` and should not be used for fingerprinting. Combinations
+    /// answering ENOTTY are not repeated and get no `consistency`.
+    pub fn scan_type_statistical(&mut self, ty: u8, samples: u8) -> io::Result<()> {
+        if samples == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "samples must be at least 1"));
+        }
+        self.scan_combinations(ty, |scanner, dir, nr, size| {
+            scanner.analyze_ioctl_statistical(dir, ty, nr, size, samples)
+        })
+    }
+
+    fn analyze_ioctl_statistical(&mut self, dir: u8, ty: u8, nr: u8, size: u16, samples: u8) -> io::Result<()> {
+        let mut result = self.probe_ioctl(dir, ty, nr, size)?;
+        if result.is_valid() {
+            let errno = |r: &IoctlResult| r.ptr_result.unwrap_or(r.null_result).1;
+            let mut errno_counts: HashMap<i32, u32> = HashMap::new();
+            *errno_counts.entry(errno(&result)).or_default() += 1;
+            for _ in 1..samples {
+                let sample = self.probe_ioctl(dir, ty, nr, size)?;
+                *errno_counts.entry(errno(&sample)).or_default() += 1;
+            }
+            let most_common = errno_counts.values().copied().max().unwrap_or(0);
+            let consistency = most_common as f32 / samples as f32;
+            result.consistency = Some(consistency);
+            result.is_stateful = consistency < MIN_CONSISTENCY;
+        }
+        self.results.push(result);
+        Ok(())
+    }
+
+    /// Run `analyze` for every `(nr, size, dir)` combination of one type
+    ///
+    /// Failed combinations end up in `scan_errors`; the scan stops early
+    /// once `max_total_calls` is used up.
+    fn scan_combinations(
+        &mut self,
+        ty: u8,
+        mut analyze: impl FnMut(&mut Self, u8, u8, u16) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.check_type_allowed(ty)?;
+        if !self.ping() {
+            return Err(device_unresponsive(format!("Device not responding before scanning type 0x{:02x}", ty)));
+        }
+
+        for nr in 0x00..=0xFFu8 {
+            if let Some(focus_nrs) = &self.options.focus_nr {
+                if !focus_nrs.contains(&nr) {
+                    continue;
+                }
             }
 
-            for &size in &sizes {
-                for &dir in &dirs {
-                    if let Err(e) = self.analyze_ioctl(dir, ty, nr, size) {
+            for &size in SCAN_SIZES {
+                for &dir in SCAN_DIRS {
+                    if let Err(e) = analyze(self, dir, nr, size) {
                         if self.options.verbosity.is_at_least(Verbosity::Normal) {
-                            eprintln!("Error analyzing 0x{:02x}.{:02x}: {}", ty, nr, e);
+                            progress_err!(self.options, "Error analyzing 0x{:02x}.{:02x} (dir={}, size={}): {}", ty, nr, dir, size, e);
                         }
-                        // Continue with next NR on error
-                        break;
+                        self.scan_errors.push((ty, nr, dir, size, e));
+                    }
+                    if self.call_limit_reached() {
+                        return Ok(());
                     }
                 }
             }
         }
+        Ok(())
+    }
 
-        if self.options.verbosity.is_at_least(Verbosity::Debug) {
-            println!();
+    fn analyze_ioctl_with_patterns(&mut self, dir: u8, ty: u8, nr: u8, size: u16, patterns: &[Vec<u8>]) -> io::Result<()> {
+        for pattern in patterns {
+            let result = self.probe_ioctl_with_pattern(dir, ty, nr, size, Some(pattern))?;
+            // ENOTTY does not depend on the input, so the zero fill would not help either
+            if result.is_successful() || !result.is_valid() {
+                self.results.push(result);
+                return Ok(());
+            }
+        }
+        self.analyze_ioctl(dir, ty, nr, size)
+    }
+
+    /// Errors of all `(ty, nr, dir, size)` combinations that failed during scans
+    pub fn scan_errors(&self) -> &[(u8, u8, u8, u16, io::Error)] {
+        &self.scan_errors
+    }
+
+    /// Apply the allow/deny configuration to a type before scanning it
+    fn check_type_allowed(&self, ty: u8) -> io::Result<()> {
+        if !self.is_allowed(ty) {
+            let msg = if let Some(risk_desc) = self.get_risk_description(ty) {
+                format!("IOCTL type 0x{:02x}: {}", ty, risk_desc)
+            } else {
+                format!("IOCTL type 0x{:02x} is not allowed by configuration", ty)
+            };
+
+            if self.options.warn_only_on_dangerous {
+                if self.options.verbosity.is_at_least(Verbosity::Normal) {
+                    progress_err!(self.options, "⚠️  WARNING: {}", msg);
+                    progress_err!(self.options, "   Continuing at user's risk...");
+                }
+                // Log warning
+                self.log_warning(&msg);
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{}\nUse 'allow_types' configuration or 'warn_only_on_dangerous' to override", msg)
+                ));
+            }
         }
 
         Ok(())
@@ -603,22 +1940,30 @@ impl IoctlDiscovery {
             return;
         }
         
-        println!("\n📊 DISCOVERY RESULTS:");
-        println!("======================");
+        progress!(self.options, "\n📊 DISCOVERY RESULTS:");
+        progress!(self.options, "======================");
         
+        // Built-in profiles, loaded once to name the known commands
+        #[cfg(any(feature = "mali", feature = "adreno"))]
+        let profiles = {
+            let mut profiles = crate::profiles::load_mali_profiles();
+            profiles.extend(crate::profiles::load_adreno_profiles());
+            profiles
+        };
+
         // Categorize results
         let (dangerous, safe): (Vec<&IoctlResult>, Vec<&IoctlResult>) = 
             self.results.iter().partition(|r| r.is_potentially_dangerous);
         
         if !dangerous.is_empty() && self.options.verbosity.is_at_least(Verbosity::Normal) {
-            println!("\n⚠️  POTENTIALLY DANGEROUS IOCTLs ({}):", dangerous.len());
+            progress!(self.options, "\n⚠️  POTENTIALLY DANGEROUS IOCTLs ({}):", dangerous.len());
             for result in dangerous.iter().take(self.options.max_results.min(5)) {
-                println!("  0x{:08x} (type=0x{:02x}, nr=0x{:02x}) - {}",
+                progress!(self.options, "  0x{:08x} (type=0x{:02x}, nr=0x{:02x}) - {}",
                         result.cmd, result.ty, result.nr,
                         self.get_risk_description(result.ty).unwrap_or("Unknown risk"));
             }
             if dangerous.len() > 5 {
-                println!("    ... and {} more dangerous IOCTLs", dangerous.len() - 5);
+                progress!(self.options, "    ... and {} more dangerous IOCTLs", dangerous.len() - 5);
             }
         }
         
@@ -645,78 +1990,217 @@ impl IoctlDiscovery {
                     results.len()
                 };
                 
-                println!("\n{} ({}):", category, results.len());
+                progress!(self.options, "\n{} ({}):", category, results.len());
                 for result in results.iter().take(max_show) {
                     let disc_size = if let Some(ds) = result.discovered_size {
                         format!(" (discovered: {})", ds)
                     } else {
                         String::new()
                     };
-                    println!("  0x{:08x}: type=0x{:02x}, nr=0x{:02x}, size={}{}, dir={}",
-                            result.cmd, result.ty, result.nr, result.size, disc_size,
+                    #[cfg(any(feature = "mali", feature = "adreno"))]
+                    let name = result.cmd_name_in(&profiles)
+                        .map(|name| format!(" ({})", name))
+                        .unwrap_or_default();
+                    #[cfg(not(any(feature = "mali", feature = "adreno")))]
+                    let name = String::new();
+                    progress!(self.options, "  0x{:08x}{}: type=0x{:02x}, nr=0x{:02x}, size={}{}, dir={}",
+                            result.cmd, name, result.ty, result.nr, result.size, disc_size,
                             match result.dir { 
                                 0 => "NONE", 1 => "WRITE", 2 => "READ", 3 => "READ|WRITE", _ => "??" 
                             });
                 }
                 if results.len() > max_show && self.options.verbosity == Verbosity::Normal {
-                    println!("    ... and {} more", results.len() - max_show);
+                    progress!(self.options, "    ... and {} more", results.len() - max_show);
                 }
             }
         }
         
         // Statistics
-        let total = self.results.len();
-        let not_exist = by_status.get("Not existent").map_or(0, |v| v.len());
-        let exists = total - not_exist;
+        let summary = self.summarize();
         
-        println!("\n📈 STATISTICS:");
-        println!("  Total tested: {} IOCTLs", total);
-        println!("  Not existent: {} IOCTLs", not_exist);
-        println!("  Potentially existent: {} IOCTLs", exists);
-        println!("  Dangerous types found: {} IOCTLs", dangerous.len());
-        
-        if !dangerous.is_empty() {
-            println!("  ⚠️  WARNING: {} potentially dangerous IOCTLs were tested!", dangerous.len());
-        }
+        progress!(self.options, "\n📈 STATISTICS:");
+        progress!(self.options, "  Total tested: {} IOCTLs", summary.total_tested);
+        progress!(self.options, "  Not existent: {} IOCTLs", summary.not_existent);
+        progress!(self.options, "  Potentially existent: {} IOCTLs", summary.total_tested - summary.not_existent);
+        progress!(self.options, "  Dangerous types found: {} IOCTLs", summary.dangerous_count);
         
-        // Find most common type
-        let mut type_count = HashMap::new();
-        for result in &self.results {
-            if result.is_valid() {
-                *type_count.entry(result.ty).or_insert(0) += 1;
-            }
+        if summary.dangerous_count > 0 {
+            progress!(self.options, "  ⚠️  WARNING: {} potentially dangerous IOCTLs were tested!", summary.dangerous_count);
         }
         
-        if let Some((best_type, count)) = type_count.iter().max_by_key(|(_, &c)| c) {
-            println!("  Most common type: 0x{:02x} ({} valid IOCTLs)", best_type, count);
+        if let Some(best_type) = summary.best_type {
+            let count = self.results.iter()
+                .filter(|r| r.ty == best_type && r.is_valid() && !r.is_potentially_dangerous)
+                .count();
+            progress!(self.options, "  Most common type: 0x{:02x} ({} valid IOCTLs)", best_type, count);
         }
     }
 
     /// Print minimal summary
     fn print_minimal_summary(&self) {
-        let mut by_status = HashMap::new();
-        let dangerous_count = self.results.iter()
-            .filter(|r| r.is_potentially_dangerous)
-            .count();
+        let summary = self.summarize();
+        let unknown = summary.total_tested
+            - summary.successful
+            - summary.exists_efault
+            - summary.permission_gated
+            - summary.not_existent;
         
-        for result in &self.results {
-            let status = match &result.interpretation {
-                Interpretation::NotExist => "NotExist",
-                Interpretation::Exists => "Exists",
-                Interpretation::Permission => "Permission",
-                Interpretation::Success => "Success",
-                Interpretation::Unknown(_) => "Unknown",
+        progress!(self.options, "\n📊 MINIMAL SUMMARY:");
+        progress!(self.options, "  Total: {}", summary.total_tested);
+        progress!(self.options, "  Success: {}", summary.successful);
+        progress!(self.options, "  Exists: {}", summary.exists_efault);
+        progress!(self.options, "  Permission: {}", summary.permission_gated);
+        progress!(self.options, "  NotExist: {}", summary.not_existent);
+        if unknown > 0 {
+            progress!(self.options, "  Unknown: {}", unknown);
+        }
+        if summary.dangerous_count > 0 {
+            progress!(self.options, "  ⚠️ Dangerous: {}", summary.dangerous_count);
+        }
+    }
+
+    /// Collapse results that differ only in the tested size
+    ///
+    /// For each `(ty, nr, dir)` keeps the `Success` result if any, otherwise
+    /// the `Exists` result with the largest size, otherwise a `Permission`
+    /// result, otherwise a single remaining entry. Order of first appearance
+    /// is preserved.
+    pub fn deduplicate_results(&mut self) {
+        // Higher rank wins; among Exists results the larger size wins
+        fn rank(result: &IoctlResult) -> (u8, u16) {
+            match result.interpretation {
+                Interpretation::Success => (4, 0),
+                Interpretation::Exists => (3, result.size),
+                Interpretation::Permission => (2, 0),
+                Interpretation::Unknown(_) => (1, 0),
+                Interpretation::NotExist => (0, 0),
+            }
+        }
+
+        let mut kept: Vec<IoctlResult> = Vec::new();
+        let mut index: HashMap<(u8, u8, u8), usize> = HashMap::new();
+
+        for result in self.results.drain(..) {
+            let key = (result.ty, result.nr, result.dir);
+            match index.get(&key) {
+                Some(&i) => {
+                    if rank(&result) > rank(&kept[i]) {
+                        kept[i] = result;
+                    }
+                }
+                None => {
+                    index.insert(key, kept.len());
+                    kept.push(result);
+                }
+            }
+        }
+
+        self.results = kept;
+    }
+
+    /// Render per-IOCTL scan times as folded stacks for `flamegraph.pl`
+    ///
+    /// Each line looks like `type0x80;nr0x03;size16;dir_RW 12345`, with the
+    /// time spent in nanoseconds.
+    pub fn to_flamegraph_data(&self) -> String {
+        let mut out = String::new();
+        for r in &self.results {
+            let dir = match r.dir {
+                0 => "NONE",
+                1 => "W",
+                2 => "R",
+                _ => "RW",
             };
-            *by_status.entry(status).or_insert(0) += 1;
+            out.push_str(&format!(
+                "type0x{:02x};nr0x{:02x};size{};dir_{} {}\n",
+                r.ty, r.nr, r.size, dir, r.scan_duration().as_nanos()
+            ));
         }
-        
-        println!("\n📊 MINIMAL SUMMARY:");
-        println!("  Total: {}", self.results.len());
-        for (status, count) in &by_status {
-            println!("  {}: {}", status, count);
+        out
+    }
+
+    /// Share of the `(ty, nr)` space of `planned_types` that was tested
+    ///
+    /// Shows whether `max_total_calls` or a cancellation cut the scan short.
+    /// With `focus_nr` set, only those NR values count as planned, and
+    /// `deny_nrs` never do. Combinations that failed with an error still count
+    /// as tested.
+    pub fn scan_coverage(&self, planned_types: &[u8]) -> ScanCoverage {
+        let tested: std::collections::HashSet<(u8, u8)> = self.results.iter()
+            .map(|r| (r.ty, r.nr))
+            .chain(self.scan_errors.iter().map(|(ty, nr, ..)| (*ty, *nr)))
+            .collect();
+
+        let mut types = planned_types.to_vec();
+        types.sort_unstable();
+        types.dedup();
+
+        let mut planned_combinations = 0u32;
+        let mut actually_tested = 0u32;
+        let mut missing_ranges = Vec::new();
+        for ty in types {
+            let mut missing_start = None;
+            for nr in 0..=0xFFu8 {
+                let planned = self.is_planned_nr(nr);
+                let is_tested = tested.contains(&(ty, nr));
+                if planned {
+                    planned_combinations += 1;
+                    if is_tested {
+                        actually_tested += 1;
+                    }
+                }
+
+                let missing = planned && !is_tested;
+                match (missing, missing_start) {
+                    (true, None) => missing_start = Some(nr),
+                    (false, Some(start)) => {
+                        missing_ranges.push((ty, start, nr - 1));
+                        missing_start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(start) = missing_start {
+                missing_ranges.push((ty, start, 0xFF));
+            }
         }
-        if dangerous_count > 0 {
-            println!("  ⚠️ Dangerous: {}", dangerous_count);
+
+        let coverage_percent = if planned_combinations == 0 {
+            100.0
+        } else {
+            actually_tested as f64 * 100.0 / planned_combinations as f64
+        };
+
+        ScanCoverage { planned_combinations, actually_tested, coverage_percent, missing_ranges }
+    }
+
+    /// Compute machine-readable statistics for the results collected so far
+    pub fn summarize(&self) -> DiscoverySummary {
+        let count = |f: fn(&Interpretation) -> bool| {
+            self.results.iter().filter(|r| f(&r.interpretation)).count()
+        };
+
+        let mut unique_types_found: Vec<u8> = self.results.iter()
+            .filter(|r| r.is_valid())
+            .map(|r| r.ty)
+            .collect();
+        unique_types_found.sort_unstable();
+        unique_types_found.dedup();
+
+        DiscoverySummary {
+            total_tested: self.results.len(),
+            successful: count(|i| matches!(i, Interpretation::Success)),
+            exists_efault: count(|i| matches!(i, Interpretation::Exists)),
+            permission_gated: count(|i| matches!(i, Interpretation::Permission)),
+            not_existent: count(|i| matches!(i, Interpretation::NotExist)),
+            dangerous_count: self.results.iter().filter(|r| r.is_potentially_dangerous).count(),
+            best_type: self.find_best_type(),
+            unique_types_found,
+            call_count: self.get_call_count(),
+            elapsed_ms: self.start_time.elapsed().as_millis() as u64,
+            active_buffer_bytes: self.active_buffer_bytes(),
+            results_memory_bytes: self.results_memory_bytes(),
+            per_device: self.per_device_statistics(),
         }
     }
 
@@ -744,81 +2228,104 @@ impl IoctlDiscovery {
         sorted_nrs
     }
 
+    // Statistics of every `source_device` seen in the results
+    fn per_device_statistics(&self) -> BTreeMap<String, JsonStatistics> {
+        let mut by_device: BTreeMap<&str, Vec<&IoctlResult>> = BTreeMap::new();
+        for r in &self.results {
+            if let Some(device) = &r.source_device {
+                by_device.entry(device.as_str()).or_default().push(r);
+            }
+        }
+        by_device.into_iter()
+            .map(|(device, results)| (device.to_string(), JsonStatistics::from_results(results)))
+            .collect()
+    }
+
     /// Export results to JSON
     pub fn export_json(&self, path: &str) -> io::Result<()> {
-        use std::fs::File;
-        
-        #[derive(Serialize)]
-        struct JsonOutput {
-            results: Vec<IoctlResult>,
-            statistics: JsonStatistics,
-            metadata: JsonMetadata,
-        }
-        
-        #[derive(Serialize)]
-        struct JsonStatistics {
-            total: usize,
-            not_existent: usize,
-            potentially_existent: usize,
-            dangerous: usize,
-            successful: usize,
-        }
-        
-        #[derive(Serialize)]
-        struct JsonMetadata {
-            timestamp: String,
-            iodisco_version: &'static str,
-            options: serde_json::Value,  // Use JSON value instead of DiscoveryOptions
-        }
-        
-        let not_existent = self.results.iter()
-            .filter(|r| matches!(&r.interpretation, Interpretation::NotExist))
-            .count();
-        
-        let dangerous = self.results.iter()
-            .filter(|r| r.is_potentially_dangerous)
-            .count();
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, &self.json_output())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
         
-        let successful = self.results.iter()
-            .filter(|r| matches!(&r.interpretation, Interpretation::Success))
-            .count();
+        Ok(())
+    }
+
+    /// Export results as MessagePack, with the same layout as `export_json`
+    #[cfg(feature = "msgpack")]
+    pub fn export_msgpack(&self, path: &str) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        rmp_serde::encode::write_named(&mut file, &self.json_output())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        file.flush()
+    }
+
+    fn json_output(&self) -> JsonOutput {
+        let mut statistics = JsonStatistics::from_results(&self.results);
+        statistics.per_device = self.per_device_statistics();
         
         // Convert options to JSON value
         let options_json = serde_json::to_value(&self.options)
-            .unwrap_or_else(|_| serde_json::Value::Null);
+            .unwrap_or(serde_json::Value::Null);
         
-        let output = JsonOutput {
+        JsonOutput {
             results: self.results.clone(),
-            statistics: JsonStatistics {
-                total: self.results.len(),
-                not_existent,
-                potentially_existent: self.results.len() - not_existent,
-                dangerous,
-                successful,
-            },
+            statistics,
             metadata: JsonMetadata {
                 timestamp: chrono::Local::now().to_rfc3339(),
-                iodisco_version: crate::VERSION,
+                iodisco_version: crate::VERSION.to_string(),
                 options: options_json,
             },
-        };
-        
-        let file = File::create(path)?;
-        serde_json::to_writer_pretty(file, &output)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        
-        Ok(())
+        }
     }
 
-    /// Generate profile template
-    pub fn generate_profile_template(&self, output_path: &str) -> io::Result<()> {
-        use std::fs::File;
-        
-        #[derive(Serialize)]
-        struct ProfileTemplate {
-            profile_version: String,
-            vendor: String,
-            model: String,
+    /// The `k` results with the highest `candidate_score`, best first
+    ///
+    /// Results scoring 0 or less are never candidates. Ties keep scan order.
+    pub fn top_k_candidates(&self, k: usize) -> Vec<&IoctlResult> {
+        let mut candidates: Vec<&IoctlResult> = self.results.iter()
+            .filter(|r| r.candidate_score() > 0)
+            .collect();
+        candidates.sort_by_key(|r| std::cmp::Reverse(r.candidate_score()));
+        candidates.truncate(k);
+        candidates
+    }
+
+    /// Results that hint at a misclassified IOCTL or a wrong size in its command
+    ///
+    /// A result is listed once per reason found.
+    pub fn anomalies(&self) -> Vec<Anomaly<'_>> {
+        let mut anomalies = Vec::new();
+        for r in &self.results {
+            let mut flag = |reason: String| anomalies.push(Anomaly { result: r, reason });
+
+            // The command declares a buffer, so a null argument should not work
+            if r.dir != 0 && r.null_result.0 >= 0 {
+                flag(format!("succeeds with a null argument despite direction {}", r.dir));
+            }
+            if !matches!(r.interpretation, Interpretation::NotExist) && r.ptr_result == Some(r.null_result) {
+                flag("buffer argument had no effect on the result".to_string());
+            }
+            if let Some(discovered) = r.discovered_size {
+                if (discovered as u32) * 2 < r.size as u32 {
+                    flag(format!("discovered size {} is less than half of the encoded size {}", discovered, r.size));
+                }
+            }
+            if r.is_potentially_dangerous && matches!(r.interpretation, Interpretation::Success) {
+                flag("potentially dangerous IOCTL succeeded".to_string());
+            }
+        }
+        anomalies
+    }
+
+    /// Generate profile template
+    pub fn generate_profile_template(&self, output_path: &str) -> io::Result<()> {
+        use std::fs::File;
+        
+        #[derive(Serialize)]
+        struct ProfileTemplate {
+            profile_version: String,
+            vendor: String,
+            model: String,
             description: String,
             detection_ioctls: Vec<DetectedIoctlTemplate>,
             info_ioctls: Vec<IoctlTemplate>,
@@ -834,6 +2341,7 @@ impl IoctlDiscovery {
             dir: String,
             interpretation: String,
             is_dangerous: bool,
+            score: i32,
         }
         
         #[derive(Serialize)]
@@ -844,13 +2352,9 @@ impl IoctlDiscovery {
             description: String,
         }
         
-        // Find working IOCTLs (safe ones only)
-        let working_ioctls: Vec<&IoctlResult> = self.results.iter()
-            .filter(|r| r.is_successful() && !r.is_potentially_dangerous)
-            .collect();
+        let successful_results = self.results.iter().filter(|r| r.is_successful()).count();
         
-        let detection_ioctls: Vec<DetectedIoctlTemplate> = working_ioctls.iter()
-            .take(5)
+        let detection_ioctls: Vec<DetectedIoctlTemplate> = self.top_k_candidates(5).into_iter()
             .map(|r| DetectedIoctlTemplate {
                 name: format!("unknown_{:02x}_{:02x}", r.ty, r.nr),
                 cmd: format!("0x{:08x}", r.cmd),
@@ -863,8 +2367,9 @@ impl IoctlDiscovery {
                     3 => "READ|WRITE".to_string(),
                     _ => "UNKNOWN".to_string(),
                 },
-                interpretation: "Success".to_string(),
+                interpretation: format!("{:?}", r.interpretation),
                 is_dangerous: r.is_potentially_dangerous,
+                score: r.candidate_score(),
             })
             .collect();
         
@@ -878,7 +2383,7 @@ impl IoctlDiscovery {
             metadata: serde_json::json!({
                 "generated_at": chrono::Local::now().to_rfc3339(),
                 "total_results": self.results.len(),
-                "successful_results": working_ioctls.len(),
+                "successful_results": successful_results,
             }),
         };
         
@@ -889,15 +2394,303 @@ impl IoctlDiscovery {
         Ok(())
     }
 
+    /// Export discovered IOCTLs as an IDA Pro Python script
+    ///
+    /// Every valid `(type, nr)` pair becomes a constant named
+    /// `IOCTL_<MODULE>_<TYPE>_<NR>`. When run inside IDA, the script looks up
+    /// instructions using each constant as an immediate and names/comments them.
+    pub fn export_ida_script(&self, path: &str, module_name: &str) -> io::Result<()> {
+        use std::fs::File;
+
+        let module: String = module_name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+
+        let entries = self.best_result_per_type_nr();
+
+        let mut file = File::create(path)?;
+        writeln!(file, "# IOCTL constants for {} generated by iodisco v{}", module_name, crate::VERSION)?;
+        writeln!(file, "# Generated at {}", chrono::Local::now().to_rfc3339())?;
+        writeln!(file)?;
+        writeln!(file, "import idc")?;
+        writeln!(file)?;
+
+        for r in &entries {
+            let size = r.discovered_size.map_or_else(
+                || format!("{} (not discovered)", r.size),
+                |ds| format!("{} (discovered)", ds),
+            );
+            writeln!(file, "# type=0x{:02x} nr=0x{:02x} dir={} interpretation={:?}", r.ty, r.nr, r.dir, r.interpretation)?;
+            writeln!(file, "# size={}", size)?;
+            writeln!(file, "IOCTL_{}_{:02X}_{:02X} = 0x{:08X}", module, r.ty, r.nr, r.cmd)?;
+            writeln!(file)?;
+        }
+
+        writeln!(file, "IOCTLS = {{")?;
+        for r in &entries {
+            writeln!(file, "    \"IOCTL_{0}_{1:02X}_{2:02X}\": IOCTL_{0}_{1:02X}_{2:02X},", module, r.ty, r.nr)?;
+        }
+        writeln!(file, "}}")?;
+        writeln!(file)?;
+
+        writeln!(file, "def name_ioctl_references(name, value):")?;
+        writeln!(file, "    ea = idc.get_inf_attr(idc.INF_MIN_EA)")?;
+        writeln!(file, "    while True:")?;
+        writeln!(file, "        ea, _ = idc.find_imm(ea, idc.SEARCH_DOWN | idc.SEARCH_NEXT, value)")?;
+        writeln!(file, "        if ea == idc.BADADDR:")?;
+        writeln!(file, "            break")?;
+        writeln!(file, "        idc.set_cmt(ea, name, 0)")?;
+        writeln!(file, "        if not idc.get_name(ea):")?;
+        writeln!(file, "            idc.set_name(ea, \"%s_%x\" % (name, ea), idc.SN_NOWARN | idc.SN_NOCHECK)")?;
+        writeln!(file)?;
+        writeln!(file, "for name, value in IOCTLS.items():")?;
+        writeln!(file, "    name_ioctl_references(name, value)")?;
+
+        Ok(())
+    }
+
+    /// Export a GitHub Flavored Markdown report, e.g. for driver bug reports
+    ///
+    /// Contains a summary line, a table of successful IOCTLs, scan statistics
+    /// and the options used for the scan.
+    pub fn export_markdown(&self, path: &str, title: &str) -> io::Result<()> {
+        use std::fs::File;
+
+        let summary = self.summarize();
+        let mut file = File::create(path)?;
+
+        writeln!(file, "# {}", title.trim())?;
+        writeln!(file)?;
+        writeln!(
+            file,
+            "Scan of `{}` on {}, generated by iodisco v{}.",
+            self.device,
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S %Z"),
+            crate::VERSION
+        )?;
+        writeln!(file)?;
+
+        writeln!(file, "## Successful IOCTLs")?;
+        writeln!(file)?;
+        let successful: Vec<&IoctlResult> = self.results.iter().filter(|r| r.is_successful()).collect();
+        if successful.is_empty() {
+            writeln!(file, "No IOCTL call succeeded.")?;
+        } else {
+            writeln!(file, "| Command | Type | NR | Size | Dir | Notes |")?;
+            writeln!(file, "|---------|------|----|------|-----|-------|")?;
+            for r in successful {
+                let dir = match r.dir { 0 => "NONE", 1 => "WRITE", 2 => "READ", 3 => "READ\\|WRITE", _ => "??" };
+                let mut notes = vec![format!("`{}`", r.as_ioctl_macro())];
+                if let Some(ds) = r.discovered_size {
+                    notes.push(format!("discovered size {}", ds));
+                }
+                if r.is_potentially_dangerous {
+                    notes.push("⚠️ dangerous type".to_string());
+                }
+                writeln!(
+                    file,
+                    "| `0x{:08x}` | `0x{:02x}` | `0x{:02x}` | {} | {} | {} |",
+                    r.cmd, r.ty, r.nr, r.size, dir, notes.join(", ")
+                )?;
+            }
+        }
+        writeln!(file)?;
+
+        writeln!(file, "## Statistics")?;
+        writeln!(file)?;
+        writeln!(file, "| Metric | Value |")?;
+        writeln!(file, "|--------|-------|")?;
+        writeln!(file, "| Total tested | {} |", summary.total_tested)?;
+        writeln!(file, "| Successful | {} |", summary.successful)?;
+        writeln!(file, "| Exists (EFAULT/EINVAL) | {} |", summary.exists_efault)?;
+        writeln!(file, "| Permission-gated | {} |", summary.permission_gated)?;
+        writeln!(file, "| Not existent | {} |", summary.not_existent)?;
+        writeln!(file, "| Dangerous | {} |", summary.dangerous_count)?;
+        let types: Vec<String> = summary.unique_types_found.iter().map(|t| format!("`0x{:02x}`", t)).collect();
+        writeln!(file, "| Types found | {} |", if types.is_empty() { "-".to_string() } else { types.join(" ") })?;
+        writeln!(file, "| IOCTL calls | {} |", summary.call_count)?;
+        writeln!(file, "| Elapsed | {} ms |", summary.elapsed_ms)?;
+        writeln!(file)?;
+
+        writeln!(file, "## Configuration")?;
+        writeln!(file)?;
+        writeln!(file, "| Option | Value |")?;
+        writeln!(file, "|--------|-------|")?;
+        if let Ok(serde_json::Value::Object(options)) = serde_json::to_value(&self.options) {
+            for (key, value) in options {
+                writeln!(file, "| `{}` | `{}` |", key, value.to_string().replace('|', "\\|"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Export a Wireshark Lua dissector for the discovered IOCTLs
+    ///
+    /// The dissector is registered for the USER0 link type and expects each
+    /// record to be a little-endian `u32` command number followed by the
+    /// argument payload. Every discovered (type, nr) pair gets a name, and
+    /// payload words are labelled by their offset within the argument.
+    pub fn export_wireshark_lua(&self, path: &str, driver_name: &str) -> io::Result<()> {
+        use std::fs::File;
+
+        let proto: String = driver_name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        let proto = format!("iodisco_{}", proto);
+        let title: String = driver_name.chars()
+            .filter(|c| !matches!(c, '"' | '\\' | '\n' | '\r'))
+            .collect();
+        let module = proto.trim_start_matches("iodisco_").to_ascii_uppercase();
+
+        let mut file = File::create(path)?;
+        writeln!(file, "-- IOCTL dissector for {} generated by iodisco v{}", title, crate::VERSION)?;
+        writeln!(file, "-- Generated at {}", chrono::Local::now().to_rfc3339())?;
+        writeln!(file, "-- Load with: wireshark -X lua_script:{}", path.rsplit('/').next().unwrap_or(path))?;
+        writeln!(file, "-- Record layout: u32 command number (little endian) followed by the argument payload")?;
+        writeln!(file)?;
+        writeln!(file, "local p = Proto(\"{}\", \"{} IOCTL\")", proto, title)?;
+        writeln!(file)?;
+        writeln!(file, "local f_cmd = ProtoField.uint32(\"{}.cmd\", \"Command\", base.HEX)", proto)?;
+        writeln!(file, "local f_dir = ProtoField.uint32(\"{}.dir\", \"Direction\", base.DEC, {{ [0] = \"NONE\", [1] = \"WRITE\", [2] = \"READ\", [3] = \"READ|WRITE\" }}, 0xC0000000)", proto)?;
+        writeln!(file, "local f_size = ProtoField.uint32(\"{}.size\", \"Size\", base.DEC, nil, 0x3FFF0000)", proto)?;
+        writeln!(file, "local f_type = ProtoField.uint32(\"{}.type\", \"Type\", base.HEX, nil, 0x0000FF00)", proto)?;
+        writeln!(file, "local f_nr = ProtoField.uint32(\"{}.nr\", \"Number\", base.HEX, nil, 0x000000FF)", proto)?;
+        writeln!(file, "local f_name = ProtoField.string(\"{}.name\", \"Name\")", proto)?;
+        writeln!(file, "local f_payload = ProtoField.bytes(\"{}.payload\", \"Payload\")", proto)?;
+        writeln!(file, "local f_word = ProtoField.uint32(\"{}.word\", \"Argument word\", base.HEX)", proto)?;
+        writeln!(file)?;
+        writeln!(file, "p.fields = {{ f_cmd, f_dir, f_size, f_type, f_nr, f_name, f_payload, f_word }}")?;
+        writeln!(file)?;
+
+        // Keyed by (type << 8) | nr, i.e. the low 16 bits of the command
+        writeln!(file, "local ioctls = {{")?;
+        for r in self.best_result_per_type_nr() {
+            let size = r.discovered_size.unwrap_or(r.size);
+            writeln!(
+                file,
+                "    [0x{:02X}{:02X}] = {{ name = \"IOCTL_{}_{:02X}_{:02X}\", size = {} }}, -- {:?}",
+                r.ty, r.nr, module, r.ty, r.nr, size, r.interpretation
+            )?;
+        }
+        writeln!(file, "}}")?;
+        writeln!(file)?;
+
+        writeln!(file, "function p.dissector(tvb, pinfo, tree)")?;
+        writeln!(file, "    if tvb:len() < 4 then")?;
+        writeln!(file, "        return 0")?;
+        writeln!(file, "    end")?;
+        writeln!(file, "    pinfo.cols.protocol = \"{}\"", module)?;
+        writeln!(file)?;
+        writeln!(file, "    local cmd = tvb(0, 4):le_uint()")?;
+        writeln!(file, "    local known = ioctls[cmd % 65536]")?;
+        writeln!(file, "    local size = math.floor(cmd / 65536) % 16384")?;
+        writeln!(file, "    local subtree = tree:add(p, tvb(), \"{} IOCTL\")", title)?;
+        writeln!(file)?;
+        writeln!(file, "    subtree:add_le(f_cmd, tvb(0, 4))")?;
+        writeln!(file, "    subtree:add_le(f_dir, tvb(0, 4))")?;
+        writeln!(file, "    subtree:add_le(f_size, tvb(0, 4))")?;
+        writeln!(file, "    subtree:add_le(f_type, tvb(0, 4))")?;
+        writeln!(file, "    subtree:add_le(f_nr, tvb(0, 4))")?;
+        writeln!(file, "    if known then")?;
+        writeln!(file, "        subtree:add(f_name, known.name)")?;
+        writeln!(file, "        size = known.size")?;
+        writeln!(file, "        pinfo.cols.info = known.name")?;
+        writeln!(file, "    else")?;
+        writeln!(file, "        pinfo.cols.info = string.format(\"unknown IOCTL 0x%08x\", cmd)")?;
+        writeln!(file, "    end")?;
+        writeln!(file)?;
+        writeln!(file, "    local payload_len = tvb:len() - 4")?;
+        writeln!(file, "    if payload_len > 0 then")?;
+        writeln!(file, "        local payload = subtree:add(f_payload, tvb(4, payload_len))")?;
+        writeln!(file, "        local offset = 0")?;
+        writeln!(file, "        while offset + 4 <= payload_len and offset + 4 <= size do")?;
+        writeln!(file, "            local item = payload:add_le(f_word, tvb(4 + offset, 4))")?;
+        writeln!(file, "            item:set_text(string.format(\"arg+0x%02x: 0x%08x\", offset, tvb(4 + offset, 4):le_uint()))")?;
+        writeln!(file, "            offset = offset + 4")?;
+        writeln!(file, "        end")?;
+        writeln!(file, "    end")?;
+        writeln!(file)?;
+        writeln!(file, "    return tvb:len()")?;
+        writeln!(file, "end")?;
+        writeln!(file)?;
+        writeln!(file, "local encaps = wtap_encaps or wtap")?;
+        writeln!(file, "DissectorTable.get(\"wtap_encap\"):add(encaps.USER0, p)")?;
+
+        Ok(())
+    }
+
+    /// Export a bpftrace program printing every call of a discovered IOCTL
+    ///
+    /// Probes the driver's `<driver_name>_ioctl` function, e.g. `mali_ioctl`
+    /// or `kgsl_ioctl`, through `kfunc` so the `cmd` argument is available by
+    /// name; this needs a kernel with BTF. Successful and permission-gated
+    /// commands are traced, as both mean the driver knows them. Run the
+    /// result with `bpftrace <path>`.
+    pub fn export_bpftrace_script(&self, path: &str, driver_name: &str) -> io::Result<()> {
+        use std::fs::File;
+
+        let function: String = driver_name.trim().chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        let mut cmds: Vec<u32> = self.results.iter()
+            .filter(|r| matches!(r.interpretation, Interpretation::Success | Interpretation::Permission))
+            .map(|r| r.cmd)
+            .collect();
+        cmds.sort_unstable();
+        cmds.dedup();
+
+        let mut file = File::create(path)?;
+        writeln!(file, "#!/usr/bin/env bpftrace")?;
+        writeln!(file, "// Generated by iodisco v{} from a scan of {}", crate::VERSION, self.device)?;
+        writeln!(file, "// {} recognized IOCTL commands", cmds.len())?;
+        writeln!(file)?;
+        writeln!(file, "kfunc:{}_ioctl", function)?;
+        if !cmds.is_empty() {
+            let filter: Vec<String> = cmds.iter().map(|cmd| format!("args->cmd == 0x{:08x}", cmd)).collect();
+            writeln!(file, "/{}/", filter.join(" || "))?;
+        }
+        writeln!(file, "{{")?;
+        writeln!(file, "    printf(\"cmd=0x%08x pid=%d\\n\", args->cmd, pid);")?;
+        writeln!(file, "}}")?;
+
+        Ok(())
+    }
+
+    /// Most meaningful valid result per (type, nr), sorted by type and nr
+    fn best_result_per_type_nr(&self) -> Vec<&IoctlResult> {
+        let rank = |r: &IoctlResult| match r.interpretation {
+            Interpretation::Success => 0,
+            Interpretation::Exists => 1,
+            Interpretation::Permission => 2,
+            Interpretation::Unknown(_) => 3,
+            Interpretation::NotExist => 4,
+        };
+        let mut best: HashMap<(u8, u8), &IoctlResult> = HashMap::new();
+        for result in self.results.iter().filter(|r| r.is_valid()) {
+            let entry = best.entry((result.ty, result.nr)).or_insert(result);
+            if rank(result) < rank(entry) {
+                *entry = result;
+            }
+        }
+        let mut entries: Vec<&IoctlResult> = best.into_values().collect();
+        entries.sort_by_key(|r| (r.ty, r.nr));
+        entries
+    }
+
     // ========== SAFETY METHODS ==========
     
     /// Enforce rate limiting between calls
     fn enforce_rate_limit(&self) -> io::Result<()> {
-        let current_calls = self.call_counter.fetch_add(1, Ordering::SeqCst);
+        let slot = self.thread_calls();
+        slot.calls.fetch_add(1, Ordering::SeqCst);
+        let current_calls = self.get_call_count() - 1;
         
         // Check total calls limit
         if let Some(max_total) = self.options.max_total_calls {
             if current_calls >= max_total {
+                // The call is not made, so it does not count
+                slot.calls.fetch_sub(1, Ordering::SeqCst);
                 return Err(io::Error::new(
                     io::ErrorKind::Other,
                     format!("Exceeded maximum call limit of {}", max_total)
@@ -905,13 +2698,16 @@ impl IoctlDiscovery {
             }
         }
         
-        // Check calls per second
+        // Check calls per second, against the previous call of this thread
         if let Some(max_per_second) = self.options.max_calls_per_second {
-            let elapsed = self.last_call_time.elapsed();
-            if elapsed < Duration::from_secs(1) {
-                // Simpler rate limiting - just sleep a bit
-                std::thread::sleep(Duration::from_millis(1000 / max_per_second as u64));
+            let interval = Duration::from_secs(1) / max_per_second.max(1);
+            let mut last_call = slot.last_call.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(elapsed) = last_call.map(|at| at.elapsed()) {
+                if elapsed < interval {
+                    std::thread::sleep(interval - elapsed);
+                }
             }
+            *last_call = Some(Instant::now());
         }
         
         // Enforce delay between calls
@@ -922,6 +2718,18 @@ impl IoctlDiscovery {
         Ok(())
     }
     
+    /// Whether `scan_timeout` has elapsed since the device was opened
+    fn timed_out(&self) -> bool {
+        self.options.scan_timeout
+            .is_some_and(|timeout| Instant::now().duration_since(self.start_time) > timeout)
+    }
+
+    /// Whether `max_total_calls` has been used up
+    fn call_limit_reached(&self) -> bool {
+        self.options.max_total_calls
+            .is_some_and(|max_total| self.get_call_count() >= max_total)
+    }
+
     /// Check if device is still responsive
     fn is_device_alive(&self) -> bool {
         unsafe {
@@ -930,6 +2738,28 @@ impl IoctlDiscovery {
         }
     }
     
+    /// Append one call to `audit_log_path`; write failures are ignored like in `log_warning`
+    fn audit(&self, cmd: u32, arg_null: bool, result: i32, errno: i32, buffer_size: usize) {
+        let Some(path) = &self.options.audit_log_path else {
+            return;
+        };
+        if let Ok(mut file) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+        {
+            let entry = serde_json::json!({
+                "timestamp": chrono::Local::now().to_rfc3339(),
+                "cmd": format!("0x{:08x}", cmd),
+                "arg_null": arg_null,
+                "result": result,
+                "errno": errno,
+                "buffer_size": buffer_size,
+            });
+            let _ = writeln!(file, "{}", entry);
+        }
+    }
+
     /// Log warning message
     fn log_warning(&self, message: &str) {
         if let Ok(mut file) = std::fs::OpenOptions::new()
@@ -942,18 +2772,908 @@ impl IoctlDiscovery {
         }
     }
     
+    /// Slot of the calling rayon thread, slot 0 outside the pool
+    fn thread_calls(&self) -> &ThreadCalls {
+        #[cfg(feature = "parallel")]
+        let index = rayon::current_thread_index().unwrap_or(0) % self.call_counters.len();
+        #[cfg(not(feature = "parallel"))]
+        let index = 0;
+        &self.call_counters[index]
+    }
+
+    /// Call counter of the calling rayon thread
+    fn thread_call_counter(&self) -> &AtomicU32 {
+        &self.thread_calls().calls
+    }
+
+    /// Make room for one slot per thread of a pool with `threads` threads
+    ///
+    /// The pool running a scan can be bigger than the one `open` ran in.
+    /// Existing counts and last-call instants keep their slot.
+    #[cfg(feature = "parallel")]
+    fn grow_call_counters(&mut self, threads: usize) {
+        if self.call_counters.len() >= threads {
+            return;
+        }
+        let slots = (0..threads)
+            .map(|i| match self.call_counters.get(i) {
+                Some(slot) => ThreadCalls {
+                    calls: AtomicU32::new(slot.calls.load(Ordering::SeqCst)),
+                    last_call: Mutex::new(*slot.last_call.lock().unwrap_or_else(|e| e.into_inner())),
+                },
+                None => ThreadCalls::new(0),
+            })
+            .collect();
+        self.call_counters = Arc::new(slots);
+    }
+
     /// Get total number of IOCTL calls made
     pub fn get_call_count(&self) -> u32 {
-        self.call_counter.load(Ordering::SeqCst)
+        self.call_counters.iter().map(|c| c.calls.load(Ordering::SeqCst)).sum()
+    }
+
+    /// IOCTL calls made per rayon thread index as `(thread, calls)`
+    ///
+    /// Calls made outside the rayon pool are counted for thread 0. Without
+    /// the `parallel` feature there is a single entry.
+    pub fn per_thread_call_counts(&self) -> Vec<(usize, u32)> {
+        self.call_counters.iter()
+            .map(|c| c.calls.load(Ordering::SeqCst))
+            .enumerate()
+            .collect()
     }
 }
 
+/// Error for a device that failed `IoctlDiscovery::ping`
+///
+/// Scans return `io::Result`, so the `DiscoveryError::DeviceOpen` is carried
+/// as the inner error of an `io::ErrorKind::NotConnected` error; callers get
+/// it back with `get_ref()` and `downcast_ref::<DiscoveryError>()`.
+fn device_unresponsive(message: String) -> io::Error {
+    let cause = io::Error::new(io::ErrorKind::NotConnected, message);
+    io::Error::new(io::ErrorKind::NotConnected, crate::error::DiscoveryError::DeviceOpen(cause))
+}
+
+/// Module (or built-in driver) name from the sysfs node of a character device
+fn sysfs_driver_module(device_path: &str) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let rdev = std::fs::metadata(device_path).ok()?.rdev();
+    // glibc `major()` / `minor()` encoding of dev_t
+    let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+    let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+    let driver = format!("/sys/dev/char/{}:{}/device/driver", major, minor);
+
+    let link = std::fs::read_link(format!("{}/module", driver))
+        .or_else(|_| std::fs::read_link(&driver))
+        .ok()?;
+    link.file_name().map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Whether `/proc/modules` content lists `module`
+fn module_listed(proc_modules: &str, module: &str) -> bool {
+    proc_modules.lines().any(|line| line.split_whitespace().next() == Some(module))
+}
+
+/// Calls made by one rayon thread, for the call limits
+struct ThreadCalls {
+    calls: AtomicU32,
+    // When this thread last passed `enforce_rate_limit`
+    last_call: Mutex<Option<Instant>>,
+}
+
+impl ThreadCalls {
+    fn new(calls: u32) -> Self {
+        Self { calls: AtomicU32::new(calls), last_call: Mutex::new(None) }
+    }
+}
+
+/// Call counters with one slot per rayon thread, `initial` counted for thread 0
+fn new_call_counters(initial: u32) -> Arc<Vec<ThreadCalls>> {
+    #[cfg(feature = "parallel")]
+    let threads = rayon::current_num_threads().max(1);
+    #[cfg(not(feature = "parallel"))]
+    let threads = 1;
+    let counters = (0..threads)
+        .map(|i| ThreadCalls::new(if i == 0 { initial } else { 0 }))
+        .collect();
+    Arc::new(counters)
+}
+
 // Implement Drop to ensure device is closed
 impl Drop for IoctlDiscovery {
     fn drop(&mut self) {
         self.close();
+        #[cfg(feature = "signal-handling")]
+        for id in self.signal_ids.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
     }
 }
 
+/// Register SIGINT / SIGTERM handlers that set the returned flag
+///
+/// A second signal while the flag is already set terminates the process, so
+/// a stuck scan can still be killed with another Ctrl+C.
+#[cfg(feature = "signal-handling")]
+fn register_shutdown_signals() -> io::Result<(Arc<AtomicBool>, Vec<signal_hook::SigId>)> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut ids = Vec::new();
+    for signal in [SIGINT, SIGTERM] {
+        ids.push(signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&flag))?);
+        ids.push(signal_hook::flag::register(signal, Arc::clone(&flag))?);
+    }
+    Ok((flag, ids))
+}
+
 // Add Serialize and Deserialize for Verbosity if not already defined
-// (You might need to add these to the Verbosity enum definition in mod.rs)
\ No newline at end of file
+// (You might need to add these to the Verbosity enum definition in mod.rs)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(cmd: u32) -> IoctlResult {
+        IoctlResult::from_cmd_for_test(cmd, Interpretation::Success)
+    }
+
+    #[test]
+    fn test_scan_type_aborts_when_device_is_gone() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        assert!(discovery.ping());
+
+        discovery.close();
+        assert!(!discovery.ping());
+        let err = discovery.scan_type(0x80).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<crate::error::DiscoveryError>());
+        assert!(matches!(inner, Some(crate::error::DiscoveryError::DeviceOpen(_))));
+        assert!(discovery.results.is_empty());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_scan_type_parallel_matches_sequential() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            focus_nr: Some(vec![0x00, 0x07, 0x42, 0xFF]),
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+
+        let mut sequential = IoctlDiscovery::open("/dev/null", options.clone()).unwrap();
+        sequential.scan_type(0x80).unwrap();
+        let mut parallel = IoctlDiscovery::open("/dev/null", options).unwrap();
+        parallel.scan_type_parallel(0x80).unwrap();
+
+        let key = |r: &IoctlResult| (r.ty, r.nr, r.dir, r.size);
+        let mut expected: Vec<_> = sequential.results.iter().map(key).collect();
+        expected.sort();
+        let actual: Vec<_> = parallel.results.iter().map(key).collect();
+        assert_eq!(actual, expected);
+        assert_eq!(parallel.get_call_count(), sequential.get_call_count());
+
+        let per_thread = parallel.per_thread_call_counts();
+        assert_eq!(per_thread.len(), rayon::current_num_threads());
+        assert_eq!(per_thread.iter().map(|(_, calls)| calls).sum::<u32>(), parallel.get_call_count());
+        assert_eq!(sequential.per_thread_call_counts()[0].1, sequential.get_call_count());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_per_thread_call_counts_sum_to_calls_issued() {
+        use rayon::prelude::*;
+
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        let issued = 1000u32;
+        (0..issued).into_par_iter().for_each(|nr| {
+            let _ = discovery.test_ioctl(0x8004_8000 | (nr & 0xFF), 0);
+        });
+
+        let per_thread = discovery.per_thread_call_counts();
+        assert_eq!(per_thread.iter().map(|(_, calls)| calls).sum::<u32>(), issued);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_rate_limit_per_thread() {
+        // 20 calls per second: 50 ms between two calls of one thread
+        let options = DiscoveryOptions {
+            max_calls_per_second: Some(20),
+            ..Default::default()
+        };
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let discovery = pool.install(|| IoctlDiscovery::open("/dev/null", options.clone())).unwrap();
+
+        let start = Instant::now();
+        let per_thread = pool.broadcast(|_| {
+            let start = Instant::now();
+            for _ in 0..5 {
+                discovery.test_ioctl(0x8004_8001, 0).unwrap();
+            }
+            let throttled = start.elapsed();
+
+            // Once the interval has passed, the next call goes out right away
+            std::thread::sleep(Duration::from_millis(60));
+            let start = Instant::now();
+            discovery.test_ioctl(0x8004_8001, 0).unwrap();
+            (throttled, start.elapsed())
+        });
+        // Each thread waits for its own previous call only
+        for (throttled, unthrottled) in per_thread {
+            assert!(throttled >= Duration::from_millis(200));
+            assert!(unthrottled < Duration::from_millis(40));
+        }
+        assert!(start.elapsed() < Duration::from_millis(450));
+        assert_eq!(discovery.per_thread_call_counts(), vec![(0, 6), (1, 6)]);
+
+        // A bigger pool than the one the device was opened in gets a slot per thread
+        let threads = rayon::current_num_threads() + 2;
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions {
+            focus_nr: Some(vec![0x01]),
+            max_calls_per_second: None,
+            ..Default::default()
+        }).unwrap();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| discovery.scan_type_parallel(0x80)).unwrap();
+        assert_eq!(discovery.per_thread_call_counts().len(), threads);
+    }
+
+    #[test]
+    fn test_summarize_counts() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let with = IoctlResult::from_cmd_for_test;
+        let mut dangerous = with(0xC004FF00, Interpretation::Success);
+        dangerous.is_potentially_dangerous = true;
+        discovery.results = vec![
+            with(0xC0048000, Interpretation::Success),
+            with(0xC0048001, Interpretation::Success),
+            with(0xC0040901, Interpretation::Exists),
+            with(0xC0048002, Interpretation::Permission),
+            with(0xC0046403, Interpretation::NotExist),
+            dangerous,
+        ];
+
+        let summary = discovery.summarize();
+        assert_eq!(summary.total_tested, 6);
+        assert_eq!(summary.successful, 3);
+        assert_eq!(summary.exists_efault, 1);
+        assert_eq!(summary.permission_gated, 1);
+        assert_eq!(summary.not_existent, 1);
+        assert_eq!(summary.dangerous_count, 1);
+        assert_eq!(summary.best_type, Some(0x80));
+        assert_eq!(summary.unique_types_found, vec![0x09, 0x80, 0xFF]);
+        assert_eq!(summary.call_count, 0);
+    }
+
+    #[test]
+    fn test_scan_focused() {
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_focused(0x80, &[0x03, 0x07]).unwrap();
+
+        assert_eq!(discovery.results.len(), 2 * SCAN_SIZES.len() * SCAN_DIRS.len());
+        assert!(discovery.results.iter().all(|r| r.ty == 0x80 && (r.nr == 0x03 || r.nr == 0x07)));
+        assert!(discovery.results.iter().any(|r| r.nr == 0x03) && discovery.results.iter().any(|r| r.nr == 0x07));
+        assert!(discovery.scan_focused(0xFF, &[0x00]).is_err());
+
+        // Stops at max_total_calls instead of failing every remaining combination
+        let per_nr = (SCAN_SIZES.len() * SCAN_DIRS.len()) as u32;
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            max_total_calls: Some(per_nr + 2),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_focused(0x80, &[0x03, 0x07, 0x09]).unwrap();
+        assert_eq!(discovery.get_call_count(), per_nr + 2);
+        assert_eq!(discovery.results.len(), per_nr as usize + 2);
+        assert_eq!(discovery.scan_errors().len(), 1);
+        assert!(discovery.results.iter().all(|r| r.nr != 0x09));
+    }
+
+    #[test]
+    fn test_scan_errors_are_recorded() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            max_total_calls: Some(3),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_nr(0x80, 0x01);
+        assert_eq!(discovery.results.len(), 3);
+        assert_eq!(discovery.scan_errors().len(), 1);
+        let (ty, nr, dir, size, _) = &discovery.scan_errors()[0];
+        assert_eq!((*ty, *nr, *dir, *size), (0x80, 0x01, SCAN_DIRS[3], SCAN_SIZES[0]));
+    }
+
+    #[test]
+    fn test_scan_nr_ranges() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let per_nr = SCAN_SIZES.len() * SCAN_DIRS.len();
+
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_nr_range(0x80, 0x10, 0x13).unwrap();
+        assert_eq!(discovery.results.len(), 4 * per_nr);
+        assert!(discovery.results.iter().all(|r| (0x10..=0x13).contains(&r.nr)));
+
+        discovery.results.clear();
+        discovery.scan_nr_ranges(0x80, &[(0x20, 0x21), (0x00, 0x00)]).unwrap();
+        let nrs: Vec<u8> = discovery.results.iter().map(|r| r.nr).step_by(per_nr).collect();
+        assert_eq!(nrs, vec![0x20, 0x21, 0x00]);
+
+        let calls = discovery.get_call_count();
+        assert!(discovery.scan_nr_range(0x80, 0x05, 0x04).is_err());
+        assert!(discovery.scan_nr_ranges(0x80, &[(0x00, 0x1F), (0x30, 0x3F), (0x1F, 0x20)]).is_err());
+        assert!(discovery.scan_nr_ranges(0x80, &[(0x00, 0x1F), (0x30, 0x2F)]).is_err());
+        assert_eq!(discovery.get_call_count(), calls);
+    }
+
+    #[cfg(feature = "mali")]
+    #[test]
+    fn test_scan_with_known_profile() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        let mut profile = crate::profiles::load_mali_profiles().remove(0);
+        profile.detection_ioctls.truncate(1);
+        profile.detection_ioctls[0].cmd = 0xC0048003;
+        profile.version_ioctl.as_mut().unwrap().cmd = 0xC0048005;
+        profile.gpu_info_ioctl.as_mut().unwrap().cmd = 0xC00480F8;
+        profile.features_ioctl = None;
+
+        discovery.scan_with_known_profile(&profile).unwrap();
+
+        let mut nrs: Vec<u8> = discovery.results.iter().map(|r| r.nr).collect();
+        nrs.dedup();
+        let expected: Vec<u8> = (0x00..=0x0F).chain(0xEE..=0xFF).collect();
+        assert_eq!(nrs, expected);
+        assert!(discovery.results.iter().all(|r| r.ty == 0x80));
+    }
+
+    #[test]
+    fn test_deduplicate_results() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let with = |cmd: u32, interpretation: Interpretation| IoctlResult {
+            interpretation,
+            ..result(cmd)
+        };
+
+        discovery.results = vec![
+            // type 0x80 nr 0: two Exists sizes plus NotExist
+            with(0xC0018000, Interpretation::NotExist),
+            with(0xC0048000, Interpretation::Exists),
+            with(0xC0108000, Interpretation::Exists),
+            // type 0x80 nr 1: Permission beats NotExist
+            with(0xC0018001, Interpretation::Permission),
+            with(0xC0048001, Interpretation::NotExist),
+            // type 0x80 nr 2: Success beats everything
+            with(0xC0018002, Interpretation::Exists),
+            with(0xC0808002, Interpretation::Success),
+            // type 0x80 nr 3: only NotExist
+            with(0xC0018003, Interpretation::NotExist),
+            with(0xC0048003, Interpretation::NotExist),
+        ];
+
+        discovery.deduplicate_results();
+
+        let cmds: Vec<u32> = discovery.results.iter().map(|r| r.cmd).collect();
+        assert_eq!(cmds, vec![0xC0108000, 0xC0018001, 0xC0808002, 0xC0018003]);
+    }
+
+    #[test]
+    fn test_flamegraph_data() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let mut rw = result(0xC0108003);
+        rw.scan_start = Duration::from_nanos(100);
+        rw.scan_end = Duration::from_nanos(1600);
+        discovery.results = vec![rw, result(0x00008001)];
+
+        assert_eq!(
+            discovery.to_flamegraph_data(),
+            "type0x80;nr0x03;size16;dir_RW 1500\ntype0x80;nr0x01;size0;dir_NONE 0\n"
+        );
+    }
+
+    #[cfg(feature = "signal-handling")]
+    #[test]
+    fn test_shutdown_signal_stops_scan() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_nr_range(0x80, 0x00, 0x01).unwrap();
+        let collected = discovery.results.len();
+        assert_eq!(discovery.partial_results_count(), 0);
+
+        discovery.shutdown.store(true, Ordering::SeqCst);
+        discovery.scan_type(0x81).unwrap();
+        assert_eq!(discovery.results.len(), collected);
+        assert_eq!(discovery.partial_results_count(), collected);
+    }
+
+    #[test]
+    fn test_scan_type_with_magic_buffers() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x00, 0x01]),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        let patterns = vec![0x4d414c49u32.to_le_bytes().to_vec(), vec![0xFF; 256]];
+        discovery.scan_type_with_magic_buffers(0x80, &patterns).unwrap();
+
+        // /dev/null answers ENOTTY, so every combination stops after the first pattern
+        assert_eq!(discovery.results.len(), 2 * SCAN_SIZES.len() * SCAN_DIRS.len());
+        assert!(discovery.results.iter().all(|r| !r.is_valid()));
+        assert_eq!(discovery.results[0].input_pattern.as_deref(), Some(&patterns[0][..]));
+        assert!(discovery.scan_errors().is_empty());
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("audit.jsonl").to_string_lossy().into_owned();
+
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x03]),
+            audit_log_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options.clone()).unwrap();
+        discovery.scan_type(0x80).unwrap();
+        let calls = discovery.get_call_count() as u64;
+        assert_eq!(crate::discovery::DiscoveryResult::audit_entry_count(&path).unwrap(), calls);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let first: serde_json::Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(first["errno"], 25);
+        assert!(first["cmd"].as_str().unwrap().ends_with("8003"));
+
+        // Append mode: a second run accumulates
+        IoctlDiscovery::open("/dev/null", options).unwrap().scan_type(0x80).unwrap();
+        assert_eq!(crate::discovery::DiscoveryResult::audit_entry_count(&path).unwrap(), 2 * calls);
+    }
+
+    #[test]
+    fn test_dry_run_execute_ioctl() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("audit.jsonl").to_string_lossy().into_owned();
+        let options = DiscoveryOptions {
+            dry_run: true,
+            max_calls_per_second: None,
+            audit_log_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+
+        // /dev/null would fail both with ENOTTY if they reached the kernel
+        assert_eq!(discovery.execute_ioctl(0xC0088001, 8).unwrap(), vec![0u8; 8]);
+        assert_eq!(discovery.execute_ioctl_with_input(0xC0048002, &[1, 2, 3, 4]).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(discovery.get_call_count(), 2);
+        assert!(!std::path::Path::new(&path).exists());
+        assert_eq!(discovery.active_buffer_bytes(), 0);
+
+        // Real calls count against max_total_calls
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            max_total_calls: Some(1),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        assert_eq!(discovery.execute_ioctl(0xC0088001, 8).unwrap_err().raw_os_error(), Some(libc::ENOTTY));
+        assert!(discovery.execute_ioctl(0xC0088001, 8).unwrap_err().to_string().contains("maximum call limit"));
+    }
+
+    #[test]
+    fn test_top_k_candidates() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let mut write_only = result(0x40108003);
+        write_only.interpretation = Interpretation::Exists;
+        let mut sized = result(0xC0048000);
+        sized.discovered_size = Some(8);
+        let mut dangerous = result(0xC0048002);
+        dangerous.is_potentially_dangerous = true;
+        let mut missing = result(0xC0048001);
+        missing.interpretation = Interpretation::NotExist;
+        discovery.results = vec![write_only, missing, dangerous, sized, result(0x80048004)];
+
+        let scores: Vec<(u32, i32)> = discovery.top_k_candidates(10).iter()
+            .map(|r| (r.cmd, r.candidate_score()))
+            .collect();
+        assert_eq!(scores, vec![(0xC0048000, 6), (0x80048004, 4), (0xC0048002, 3), (0x40108003, 2)]);
+        assert_eq!(discovery.top_k_candidates(1).len(), 1);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("template.json");
+        discovery.generate_profile_template(path.to_str().unwrap()).unwrap();
+        let template: serde_json::Value = serde_json::from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(template["detection_ioctls"][0]["score"], 6);
+        assert_eq!(template["detection_ioctls"][3]["interpretation"], "Exists");
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let rejected = |cmd: u32| {
+            let mut r = result(cmd);
+            r.null_result = (-1, libc::EFAULT);
+            r
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let mut no_effect = rejected(0xC0048001);
+        no_effect.ptr_result = Some((-1, libc::EFAULT));
+        let mut shrunk = rejected(0xC0408002);
+        shrunk.discovered_size = Some(16);
+        let mut dangerous = rejected(0xC004FF03);
+        dangerous.is_potentially_dangerous = true;
+        let mut missing = rejected(0xC0048004);
+        missing.interpretation = Interpretation::NotExist;
+        missing.ptr_result = Some(missing.null_result);
+        discovery.results = vec![result(0xC0048000), no_effect, shrunk, dangerous, missing, rejected(0xC0048005)];
+
+        let flagged: Vec<u32> = discovery.anomalies().iter().map(|a| a.result.cmd).collect();
+        assert_eq!(flagged, vec![0xC0048000, 0xC0048001, 0xC0408002, 0xC004FF03]);
+        assert!(discovery.anomalies()[2].reason.contains("encoded size 64"));
+    }
+
+    #[test]
+    fn test_ioctl_with_retry() {
+        let options = DiscoveryOptions { max_calls_per_second: None, ..Default::default() };
+        let discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        // ENOTTY is final, so no retries are made
+        assert_eq!(discovery.test_ioctl_with_retry(0xC0048000, 0, 3, 1).unwrap().1, libc::ENOTTY);
+        assert_eq!(discovery.get_call_count(), 1);
+
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x00]),
+            retry_on_eagain: true,
+            ..Default::default()
+        };
+        let mut retrying = IoctlDiscovery::open("/dev/null", options).unwrap();
+        retrying.scan_type(0x80).unwrap();
+        assert!(retrying.results.iter().all(|r| r.null_result.1 == libc::ENOTTY));
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            warm_up_ioctls: vec![0x40108003, 0x80048001],
+            warm_up_count: 3,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        assert_eq!(discovery.warm_up().unwrap(), 4);
+        assert_eq!(discovery.get_call_count(), 4);
+
+        let mut idle = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        assert_eq!(idle.warm_up().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_verify_driver_loaded() {
+        assert!(module_listed("mali_kbase 679936 12 - Live 0x0000000000000000\n", "mali_kbase"));
+        assert!(!module_listed("mali_kbase_ext 4096 0 - Live 0x0\n", "mali_kbase"));
+
+        // /dev/null has no driver module
+        assert!(IoctlDiscovery::verify_driver_loaded("/dev/null").is_err());
+        let options = DiscoveryOptions { verify_driver: true, ..Default::default() };
+        assert!(IoctlDiscovery::open("/dev/null", options).is_err());
+
+        let info = IoctlDiscovery::verify_driver_loaded("/nonexistent/mali0").unwrap();
+        assert_eq!(info.module_name, "mali_kbase");
+    }
+
+    #[test]
+    fn test_scan_type_statistical() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x00]),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        assert!(discovery.scan_type_statistical(0x80, 0).is_err());
+
+        discovery.scan_type_statistical(0x80, 5).unwrap();
+        let combinations = SCAN_SIZES.len() * SCAN_DIRS.len();
+        assert_eq!(discovery.results.len(), combinations);
+        // ENOTTY is never repeated
+        assert_eq!(discovery.get_call_count() as usize, combinations);
+        assert!(discovery.results.iter().all(|r| r.consistency.is_none() && !r.is_stateful));
+    }
+
+    #[test]
+    fn test_progress_output() {
+        use crate::discovery::progress::StringWriter;
+
+        let writer = StringWriter::new();
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Debug,
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x00]),
+            progress_output: Some(ProgressOutput::new(writer.clone())),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_type(0x80).unwrap();
+        discovery.print_results();
+
+        let output = writer.contents();
+        assert!(output.contains("🔍 Scanning Type 0x80"));
+        assert!(output.contains("📈 STATISTICS:"));
+    }
+
+    #[test]
+    fn test_scan_coverage() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        discovery.results = (0x00..=0x0F).chain(0x20..=0xFF).map(|nr| result(0xC0048000 | nr)).collect();
+
+        let coverage = discovery.scan_coverage(&[0x80, 0x09]);
+        assert_eq!(coverage.planned_combinations, 512);
+        assert_eq!(coverage.actually_tested, 240);
+        assert!((coverage.coverage_percent - 46.875).abs() < 1e-9);
+        assert_eq!(coverage.missing_ranges, vec![(0x09, 0x00, 0xFF), (0x80, 0x10, 0x1F)]);
+
+        discovery.options.focus_nr = Some(vec![0x00, 0x10]);
+        let coverage = discovery.scan_coverage(&[0x80]);
+        assert_eq!((coverage.planned_combinations, coverage.actually_tested), (2, 1));
+        assert_eq!(coverage.missing_ranges, vec![(0x80, 0x10, 0x10)]);
+
+        // Combinations that only failed still count as tested for their own type
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            max_total_calls: Some(3),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_nr(0x80, 0x01);
+        discovery.results.clear();
+        assert!(!discovery.scan_errors().is_empty());
+        let coverage = discovery.scan_coverage(&[0x80]);
+        assert_eq!(coverage.actually_tested, 1);
+        assert_eq!(coverage.missing_ranges, vec![(0x80, 0x00, 0x00), (0x80, 0x02, 0xFF)]);
+    }
+
+    #[test]
+    fn test_scan_timeout() {
+        let options = DiscoveryOptions {
+            verbosity: Verbosity::Minimal,
+            max_calls_per_second: None,
+            ..Default::default()
+        }
+        .with_timeout(Duration::from_nanos(1));
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+
+        let reason = discovery.scan_type_with_progress(0x80, None, &NullProgressReporter).unwrap();
+        assert!(matches!(reason, ScanStopReason::TimedOut));
+        assert!(discovery.results.is_empty());
+
+        discovery.options.timeout_behavior = TimeoutBehavior::ReturnError;
+        let err = discovery.scan_type(0x80).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let invalid = DiscoveryOptions::default().with_timeout(Duration::ZERO);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_deny_nrs() {
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x00, 0x01]),
+            deny_nrs: vec![0x00],
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+
+        let options = DiscoveryOptions { focus_nr: Some(vec![0x01]), ..options };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options.clone()).unwrap();
+        discovery.options.focus_nr = Some(vec![0x00, 0x01]);
+        discovery.scan_type(0x80).unwrap();
+        assert!(!discovery.results.is_empty());
+        assert!(discovery.results.iter().all(|r| r.nr == 0x01));
+        let coverage = discovery.scan_coverage(&[0x80]);
+        assert_eq!((coverage.planned_combinations, coverage.actually_tested), (1, 1));
+        assert!(discovery.scan_type_stream(0x80).all(|r| r.unwrap().nr == 0x01));
+    }
+
+    #[test]
+    fn test_scan_type_cancelable() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let (token, handle) = CancelToken::new();
+
+        handle.cancel();
+        assert!(handle.is_cancelled());
+        let reason = discovery.scan_type_cancelable(0x80, token).unwrap();
+        assert!(matches!(reason, ScanStopReason::Cancelled));
+        assert!(discovery.results.is_empty());
+
+        // Disallowed types are rejected before the token matters
+        let (token, _handle) = CancelToken::new();
+        assert!(discovery.scan_type_cancelable(0xFF, token).is_err());
+    }
+
+    #[test]
+    fn test_scan_type_stream_does_not_buffer() {
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+
+        let streamed: Vec<IoctlResult> = discovery.scan_type_stream(0x80)
+            .take(5)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(streamed.len(), 5);
+        assert_eq!(streamed[1].cmd, 0x40018000);
+        assert!(discovery.results.is_empty());
+
+        let mut rejected = discovery.scan_type_stream(0xFF);
+        assert!(rejected.next().unwrap().is_err());
+        assert!(rejected.next().is_none());
+    }
+
+    #[test]
+    fn test_dry_run_plans_without_calls() {
+        let options = DiscoveryOptions {
+            dry_run: true,
+            max_total_calls: None,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+
+        // 0xFF is on the default deny list
+        assert_eq!(discovery.planned_scan_count(&[0x80, 0x64, 0xFF]), 2 * 256 * 4 * 8);
+
+        discovery.scan_type(0x80).unwrap();
+        assert_eq!(discovery.results.len(), 256 * 4 * 8);
+        assert!(discovery.results.iter().all(|r| matches!(r.interpretation, Interpretation::NotExist)));
+        assert_eq!(u64::from(discovery.get_call_count()), discovery.planned_scan_count(&[0x80]));
+
+        discovery.options.focus_nr = Some(vec![0, 3, 3]);
+        assert_eq!(discovery.planned_scan_count(&[0x80]), 2 * 4 * 8);
+
+        discovery.options.warn_only_on_dangerous = true;
+        assert_eq!(discovery.planned_scan_count(&[0x80, 0xFF]), 2 * 4 * 8);
+
+        discovery.options.focus_nr = None;
+        discovery.options.deny_nrs = vec![0x00, 0xFF];
+        assert_eq!(discovery.planned_scan_count(&[0x80]), 254 * 4 * 8);
+    }
+
+    #[test]
+    fn test_export_wireshark_lua() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        discovery.results = vec![result(0xC0048000), result(0x40108003)];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mali.lua");
+        let path = path.to_str().unwrap();
+        discovery.export_wireshark_lua(path, "Mali \"kbase\"").unwrap();
+
+        let lua = std::fs::read_to_string(path).unwrap();
+        assert!(lua.contains("local p = Proto(\"iodisco_mali__kbase_\", \"Mali kbase IOCTL\")"));
+        assert!(lua.contains("[0x8000] = { name = \"IOCTL_MALI__KBASE__80_00\", size = 4 }"));
+        assert!(lua.contains("[0x8003] = { name = \"IOCTL_MALI__KBASE__80_03\", size = 16 }"));
+        assert!(lua.trim_end().ends_with("DissectorTable.get(\"wtap_encap\"):add(encaps.USER0, p)"));
+        // Every block opened in the dissector is closed again
+        let opened = lua.matches(" then\n").count() + lua.matches(" do\n").count() + lua.matches("function ").count();
+        assert_eq!(opened, lua.matches("end\n").count());
+    }
+
+    #[test]
+    fn test_export_bpftrace_script() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        let mut gated = result(0xC0108006);
+        gated.interpretation = Interpretation::Permission;
+        let mut missing = result(0xC0108007);
+        missing.interpretation = Interpretation::NotExist;
+        discovery.results = vec![result(0x40108003), result(0xC0048000), gated, missing, result(0xC0048000)];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mali.bt");
+        let path = path.to_str().unwrap();
+        discovery.export_bpftrace_script(path, "mali").unwrap();
+
+        let script = std::fs::read_to_string(path).unwrap();
+        assert!(script.starts_with("#!/usr/bin/env bpftrace\n"));
+        assert!(script.contains(
+            "kfunc:mali_ioctl\n/args->cmd == 0x40108003 || args->cmd == 0xc0048000 || args->cmd == 0xc0108006/\n{\n"
+        ));
+        assert!(script.contains("    printf(\"cmd=0x%08x pid=%d\\n\", args->cmd, pid);\n}\n"));
+        assert!(!script.contains("0xc0108007"));
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let mut discovery = IoctlDiscovery::open("/dev/null", DiscoveryOptions::default()).unwrap();
+        discovery.results = vec![result(0xC0048000), result(0x40108003)];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.md");
+        let path = path.to_str().unwrap();
+        discovery.export_markdown(path, "Mali kbase scan").unwrap();
+
+        let md = std::fs::read_to_string(path).unwrap();
+        assert!(md.starts_with("# Mali kbase scan\n\nScan of `/dev/null` on "));
+        assert!(md.contains("| Command | Type | NR | Size | Dir | Notes |"));
+        assert!(md.contains("| `0xc0048000` | `0x80` | `0x00` | 4 | READ\\|WRITE | `_IOWR(0x80, 0x00, u32)` |"));
+        assert!(md.contains("| `0x40108003` | `0x80` | `0x03` | 16 | WRITE | `_IOW(0x80, 0x03, char[16])` |"));
+        assert!(md.contains("## Statistics\n\n| Metric | Value |"));
+        assert!(md.contains("| `max_total_calls` | `10000` |"));
+        // Every table row has the same number of columns as its header
+        for table in md.split("\n\n").filter(|block| block.starts_with('|')) {
+            let columns: Vec<usize> = table.lines().map(|l| l.replace("\\|", "").matches('|').count()).collect();
+            assert!(columns.iter().all(|c| *c == columns[0]), "{}", table);
+        }
+    }
+
+    #[test]
+    fn test_vendor_size_candidates() {
+        let mali = DiscoveryOptions::quick().with_mali_sizes();
+        assert_eq!(mali.size_discovery_candidates, vec![12, 24, 40, 48, 72, 80, 88, 120, 128]);
+        assert_eq!(mali.verbosity, Verbosity::Minimal);
+
+        let adreno = DiscoveryOptions::default().with_adreno_sizes();
+        assert!(adreno.size_discovery_candidates.contains(&24));
+    }
+
+    #[test]
+    fn test_execute_ioctl_respects_buffer_memory_limit() {
+        let options = DiscoveryOptions {
+            max_buffer_memory_bytes: 8,
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+
+        let err = discovery.execute_ioctl(0xC0108003, 16).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+        assert_eq!(discovery.active_buffer_bytes(), 0);
+    }
+
+    #[test]
+    fn test_as_ioctl_macro_known_mali_commands() {
+        // KBASE_IOCTL_VERSION_CHECK
+        assert_eq!(result(0x40108003).as_ioctl_macro(), "_IOW(0x80, 0x03, char[16])");
+        // GET_PROPS_00
+        assert_eq!(result(0xC0048000).as_ioctl_macro(), "_IOWR(0x80, 0x00, u32)");
+        assert_eq!(result(0x8004800c).as_ioctl_macro(), "_IOR(0x80, 0x0c, u32)");
+        assert_eq!(result(0x00008003).as_ioctl_macro(), "_IO(0x80, 0x03)");
+    }
+
+    #[cfg(feature = "mali")]
+    #[test]
+    fn test_cmd_name_in() {
+        let profiles = crate::profiles::load_mali_profiles();
+        assert_eq!(result(0x40108003).cmd_name_in(&profiles).as_deref(), Some("VERSION_CHECK"));
+        assert_eq!(result(0x00008003).cmd_name_in(&profiles), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! seccomp(2) whitelists for IOCTL commands
+//!
+//! A filter only applies to the thread that installs it and cannot be
+//! removed again, so [`IoctlDiscovery::scan_type_seccomp_safe`] scans on a
+//! short-lived thread. The rest of the process keeps its previous filter.
+
+use std::io;
+
+use crate::discovery::IoctlDiscovery;
+
+// Classic BPF opcodes, see linux/bpf_common.h
+const BPF_LD_W_ABS: u16 = 0x20; // BPF_LD | BPF_W | BPF_ABS
+const BPF_ALU_AND_K: u16 = 0x54; // BPF_ALU | BPF_AND | BPF_K
+const BPF_JMP_JEQ_K: u16 = 0x15; // BPF_JMP | BPF_JEQ | BPF_K
+const BPF_RET_K: u16 = 0x06; // BPF_RET | BPF_K
+
+// Offsets into `struct seccomp_data`
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+#[cfg(target_endian = "little")]
+const SECCOMP_DATA_ARG1_LOW: u32 = 24;
+#[cfg(target_endian = "big")]
+const SECCOMP_DATA_ARG1_LOW: u32 = 28;
+
+/// `AUDIT_ARCH_*` value of the native syscall ABI
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_NATIVE: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH_NATIVE: u32 = 0xC000_00B7;
+#[cfg(target_arch = "arm")]
+const AUDIT_ARCH_NATIVE: u32 = 0x4000_0028;
+#[cfg(target_arch = "x86")]
+const AUDIT_ARCH_NATIVE: u32 = 0x4000_0003;
+#[cfg(target_arch = "riscv64")]
+const AUDIT_ARCH_NATIVE: u32 = 0xC000_00F3;
+
+/// seccomp BPF program letting only whitelisted IOCTL commands through
+///
+/// Blocked IOCTLs fail with `EPERM`, the same errno an SELinux ioctl denial
+/// produces. All other syscalls are allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeccompFilter {
+    allowed: Vec<u32>,
+    mask: u32,
+}
+
+impl SeccompFilter {
+    /// Allow exactly the given IOCTL commands
+    pub fn new(allowed_cmds: &[u32]) -> Self {
+        Self { allowed: allowed_cmds.to_vec(), mask: u32::MAX }
+    }
+
+    /// Allow the given NR values of type `ty`, whatever their direction and size
+    pub fn for_type(ty: u8, allowed_nrs: &[u8]) -> Self {
+        let allowed = allowed_nrs.iter().map(|&nr| (ty as u32) << 8 | nr as u32).collect();
+        Self { allowed, mask: 0xFFFF }
+    }
+
+    /// Whether the filter lets `cmd` through
+    pub fn allows(&self, cmd: u32) -> bool {
+        self.allowed.contains(&(cmd & self.mask))
+    }
+
+    /// The BPF program: two instructions per allowed command plus a fixed prologue
+    pub fn bytecode(&self) -> Vec<libc::sock_filter> {
+        let stmt = |code: u16, k: u32| libc::sock_filter { code, jt: 0, jf: 0, k };
+        let jump = |k: u32, jt: u8, jf: u8| libc::sock_filter { code: BPF_JMP_JEQ_K, jt, jf, k };
+        let allow = stmt(BPF_RET_K, libc::SECCOMP_RET_ALLOW);
+
+        let mut program = vec![
+            // Syscall numbers of foreign ABIs mean something else
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARCH),
+            jump(AUDIT_ARCH_NATIVE, 1, 0),
+            allow,
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_NR),
+            jump(libc::SYS_ioctl as u32, 1, 0),
+            allow,
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARG1_LOW),
+            stmt(BPF_ALU_AND_K, self.mask),
+        ];
+        for &cmd in &self.allowed {
+            program.push(jump(cmd, 0, 1));
+            program.push(allow);
+        }
+        program.push(stmt(BPF_RET_K, libc::SECCOMP_RET_ERRNO | libc::EPERM as u32));
+        program
+    }
+
+    /// Install the filter on the calling thread
+    ///
+    /// Sets `no_new_privs` first, so no root or `CAP_SYS_ADMIN` is needed.
+    /// The filter stays in place until the thread exits.
+    pub fn install(&self) -> io::Result<()> {
+        let mut program = self.bytecode();
+        let prog = libc::sock_fprog {
+            len: program.len() as libc::c_ushort,
+            filter: program.as_mut_ptr(),
+        };
+
+        if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let result = unsafe {
+            libc::syscall(libc::SYS_seccomp, libc::SECCOMP_SET_MODE_FILTER, 0, &prog as *const libc::sock_fprog)
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl IoctlDiscovery {
+    /// Scan one type while a seccomp filter only allows `allowed_nrs` of it
+    ///
+    /// The scan runs on a separate thread that installs a
+    /// [`SeccompFilter::for_type`] filter, so the calling thread is not
+    /// restricted afterwards. Blocked IOCTLs show up as
+    /// `Interpretation::Permission` with errno `EPERM`.
+    pub fn scan_type_seccomp_safe(&mut self, ty: u8, allowed_nrs: &[u8]) -> io::Result<()> {
+        let filter = SeccompFilter::for_type(ty, allowed_nrs);
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    filter.install()?;
+                    self.scan_type(ty)
+                })
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::Other, "seccomp scan thread panicked")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{DiscoveryOptions, Interpretation};
+
+    #[test]
+    fn test_seccomp_filter() {
+        let filter = SeccompFilter::for_type(0x80, &[0x00, 0x03]);
+        assert!(filter.allows(0xC0108003));
+        assert!(filter.allows(0x40048000));
+        assert!(!filter.allows(0xC0108001));
+        assert!(!filter.allows(0xC0106403));
+        assert_eq!(filter.bytecode().len(), 8 + 2 * 2 + 1);
+        assert!(SeccompFilter::new(&[0xC0108003]).allows(0xC0108003));
+        assert!(!SeccompFilter::new(&[0xC0108003]).allows(0x40108003));
+
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x00, 0x01]),
+            ..Default::default()
+        };
+        let mut discovery = IoctlDiscovery::open("/dev/null", options).unwrap();
+        discovery.scan_type_seccomp_safe(0x80, &[0x00]).unwrap();
+
+        let results = &discovery.results;
+        assert!(results.iter().any(|r| r.nr == 0x00) && results.iter().any(|r| r.nr == 0x01));
+        assert!(results.iter().filter(|r| r.nr == 0x00).all(|r| r.null_result.1 == libc::ENOTTY));
+        assert!(results.iter()
+            .filter(|r| r.nr == 0x01)
+            .all(|r| r.null_result.1 == libc::EPERM && matches!(r.interpretation, Interpretation::Permission)));
+
+        // The filter died with the scan thread
+        assert_eq!(discovery.test_ioctl(0xC0108001, 0).unwrap().1, libc::ENOTTY);
+
+        // The Mali liveness probe (GET_VERSION, 0x80.0x00) is blocked too
+        let options = DiscoveryOptions {
+            max_calls_per_second: None,
+            focus_nr: Some(vec![0x01]),
+            ..Default::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mali = dir.path().join("mali0");
+        std::os::unix::fs::symlink("/dev/null", &mali).unwrap();
+        let mut discovery = IoctlDiscovery::open(mali.to_str().unwrap(), options).unwrap();
+        discovery.scan_type_seccomp_safe(0x80, &[0x01]).unwrap();
+        assert!(discovery.results.iter().all(|r| r.nr == 0x01 && r.null_result.1 == libc::ENOTTY));
+        assert!(!discovery.results.is_empty());
+    }
+}
@@ -12,7 +12,7 @@ pub enum DiscoveryError {
 
     /// Failed to open device
     #[error("Failed to open device: {0}")]
-    DeviceOpen(io::Error),
+    DeviceOpen(#[source] io::Error),
 
     /// No matching GPU profile found
     #[error("No matching GPU profile found")]
@@ -28,7 +28,7 @@ pub enum DiscoveryError {
 
     /// IOCTL execution failed
     #[error("IOCTL execution failed: {0}")]
-    IoctlFailed(io::Error),
+    IoctlFailed(#[source] io::Error),
 
     /// JSON serialization/deserialization error
     #[error("JSON error: {0}")]
@@ -61,8 +61,69 @@ impl From<io::Error> for DiscoveryError {
     }
 }
 
+impl DiscoveryError {
+    /// Innermost error of the `source()` chain, or `self` if there is none
+    pub fn root_cause(&self) -> &(dyn std::error::Error + 'static) {
+        let mut current: &(dyn std::error::Error + 'static) = self;
+        while let Some(source) = current.source() {
+            current = source;
+        }
+        current
+    }
+
+    /// Add context describing what was being done when the error occurred
+    ///
+    /// Similar to `anyhow::Context`: the result is always a `Discovery` error
+    /// whose message is prefixed with `msg`.
+    pub fn context(self, msg: impl Into<String>) -> Self {
+        let msg = msg.into();
+        match self {
+            DiscoveryError::Discovery(original) => DiscoveryError::Discovery(format!("{}: {}", msg, original)),
+            other => DiscoveryError::Discovery(format!("{}: {}", msg, other)),
+        }
+    }
+}
+
 /// Result type for iodisco operations
 pub type DiscoveryResult<T> = std::result::Result<T, DiscoveryError>;
 
 /// Alias for API compatibility
-pub type GpuInfoError = DiscoveryError;
\ No newline at end of file
+pub type GpuInfoError = DiscoveryError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let err = DiscoveryError::Discovery("no response".to_string())
+            .context("scanning type 0x80")
+            .context("/dev/mali0");
+        assert_eq!(err.to_string(), "Discovery error: /dev/mali0: scanning type 0x80: no response");
+
+        let err = DiscoveryError::from(io::Error::new(io::ErrorKind::PermissionDenied, "EACCES"))
+            .context("scanning type 0x80");
+        assert_eq!(err.to_string(), "Discovery error: scanning type 0x80: Permission denied: EACCES");
+    }
+
+    #[test]
+    fn test_source_chain() {
+        use std::error::Error;
+
+        let inner = || io::Error::new(io::ErrorKind::Other, "EIO");
+        for err in [DiscoveryError::DeviceOpen(inner()), DiscoveryError::IoctlFailed(inner())] {
+            let chain: Vec<String> = anyhow::Chain::new(&err).map(|e| e.to_string()).collect();
+            assert_eq!(chain.len(), 2);
+            assert_eq!(chain[1], "EIO");
+            assert_eq!(err.root_cause().to_string(), "EIO");
+        }
+
+        let json = DiscoveryError::from(serde_json::from_str::<u32>("x").unwrap_err());
+        assert!(json.source().is_some());
+        assert_eq!(anyhow::Chain::new(&json).count(), 2);
+
+        let plain = DiscoveryError::NoDevice;
+        assert!(plain.source().is_none());
+        assert_eq!(plain.root_cause().to_string(), plain.to_string());
+    }
+}
@@ -16,71 +16,77 @@
 //!
 //! ## Quick Start
 //!
-//! ```rust
-//! use iodisco;
-//!
+//! ```no_run
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let gpu_info = iodisco::get_gpu_info()?;
 //!     println!("GPU: {} {}", gpu_info.vendor, gpu_info.model);
 //!     Ok(())
 //! }
 //! ```
+//!
+//! ## `no_std`
+//!
+//! Without the default `std` feature only [`mappings`] is built, on `core`
+//! alone, e.g. `default-features = false, features = ["mali"]`.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 
+#[cfg(feature = "std")]
 pub mod api;
+#[cfg(feature = "std")]
 pub mod error;
 
 // Optional modules (feature-gated)
 #[cfg(any(feature = "mali", feature = "adreno"))]
 pub mod mappings;
 
-#[cfg(any(feature = "mali", feature = "adreno"))]
+#[cfg(all(feature = "std", any(feature = "mali", feature = "adreno")))]
 pub mod profiles;
 
+#[cfg(all(feature = "std", any(feature = "mali", feature = "adreno")))]
+pub mod vendor;
+
 #[cfg(feature = "discovery")]
 pub mod discovery;
 
+#[cfg(feature = "devicetree")]
+pub mod devicetree;
+
 // Re-export main API for easy access
-pub use api::{get_gpu_info, get_gpu_info_with_device, GpuInfo, GpuInfoError};
+#[cfg(feature = "std")]
+pub use api::{get_gpu_info, get_gpu_info_cached, get_gpu_info_with_device, parse_features_with_table, FieldChange, GpuInfo, GpuInfoDiff, GpuInfoError, GpuPerformanceSpec, FeatureBitTable, MaliCoreMask, PowerClass, VulkanDeviceHint, VulkanDeviceType, GPU_INFO_SCHEMA_URL};
+#[cfg(feature = "std")]
 pub use error::DiscoveryError;
 
 #[cfg(feature = "discovery")]
 pub use discovery::{scan_device, DiscoveryConfig, DiscoveryResult};
 
+#[cfg(feature = "parallel")]
+pub use discovery::scan_devices_parallel;
+
+#[cfg(feature = "devicetree")]
+pub use devicetree::read_gpu_from_devicetree;
+
+#[cfg(all(feature = "std", feature = "mali"))]
+pub use api::identify_mali_gpu_from_sysfs;
+
 #[cfg(any(feature = "mali", feature = "adreno"))]
 pub use mappings::{identify_mali_gpu, identify_adreno_gpu, GpuVendor};
 
-#[cfg(any(feature = "mali", feature = "adreno"))]
+#[cfg(all(feature = "std", any(feature = "mali", feature = "adreno")))]
+pub use mappings::identify_mali_gpu_range;
+
+#[cfg(all(feature = "std", any(feature = "mali", feature = "adreno")))]
 pub use profiles::{load_mali_profiles, load_adreno_profiles, IoctlProfile};
 
+#[cfg(all(feature = "std", any(feature = "mali", feature = "adreno")))]
+pub use vendor::{VendorExtensions, VendorRegistry};
+
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Main entry point for high-level GPU information
-///
-/// This function automatically detects the GPU device and returns
-/// structured information about it using pre-defined profiles.
-///
-/// # Example
-///
-/// ```no_run
-/// use iodisco;
-///
-/// match iodisco::get_gpu_info() {
-///     Ok(info) => {
-///         println!("Found GPU: {} {}", info.vendor, info.model);
-///     }
-///     Err(iodisco::GpuInfoError::NoProfile) => {
-///         eprintln!("GPU not recognized. Consider running discovery mode.");
-///     }
-///     Err(e) => {
-///         eprintln!("Error: {}", e);
-///     }
-/// }
-/// ```
-
 /// Initialize the library with custom configuration
 ///
 /// # Example
@@ -118,7 +124,7 @@ pub fn scan_devices() -> Vec<String> {
 /// Scan for available GPU devices on the system
 ///
 /// Returns an empty vector when discovery feature is disabled.
-#[cfg(not(feature = "discovery"))]
+#[cfg(all(feature = "std", not(feature = "discovery")))]
 pub fn scan_devices() -> Vec<String> {
     vec![]
 }
@@ -139,11 +145,11 @@ pub fn scan_devices() -> Vec<String> {
 /// }
 /// ```
 pub fn is_supported() -> bool {
-    #[cfg(any(feature = "mali", feature = "adreno"))]
+    #[cfg(all(feature = "std", any(feature = "mali", feature = "adreno")))]
     {
         !scan_devices().is_empty()
     }
-    #[cfg(not(any(feature = "mali", feature = "adreno")))]
+    #[cfg(not(all(feature = "std", any(feature = "mali", feature = "adreno"))))]
     {
         false
     }
@@ -184,6 +190,7 @@ mod tests {
         let _ = is_supported();
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_scan_devices() {
         // Just ensure it compiles
@@ -201,9 +208,11 @@ mod tests {
 /// use iodisco::prelude::*;
 ///
 /// let gpu_info = get_gpu_info()?;
+/// # Ok::<(), iodisco::GpuInfoError>(())
 /// ```
+#[cfg(feature = "std")]
 pub mod prelude {
-    pub use crate::api::{get_gpu_info, get_gpu_info_with_device, GpuInfo, GpuInfoError};
+    pub use crate::api::{get_gpu_info, get_gpu_info_cached, get_gpu_info_with_device, GpuInfo, GpuInfoError};
     pub use crate::{init, is_supported, scan_devices, version};
     
     #[cfg(feature = "discovery")]
@@ -11,6 +11,8 @@ pub struct MaliGpuModel {
     pub id_mask: u16,
     /// Minimum number of cores required for this marketing name/variant
     pub min_cores: u8,
+    /// Largest core count the model is offered with
+    pub max_cores: u8,
     /// Marketing name (e.g. "Mali-G78", "Immortalis-G715")
     pub name: &'static str,
     /// Architecture generation/family
@@ -25,8 +27,83 @@ pub struct MaliGpuModel {
     pub texels_per_cycle: u8,
     /// Pixels processed per cycle per core
     pub pixels_per_cycle: u8,
+    /// Approximate shader core area per core in mm², where published
+    pub die_size_mm2: Option<f64>,
+    /// Typical memory bus width in bits of SoCs using this model
+    pub bus_width_bits: Option<u16>,
+    /// Process node in nm of the first SoCs shipping this model, where published
+    pub process_node_nm: Option<u16>,
+    /// Approximate transistor count per core in millions, where published
+    pub estimated_transistors_million: Option<u32>,
 }
 
+impl MaliGpuModel {
+    /// FP32 FMAs per cycle of a single core
+    pub fn fp32_fmas_per_core(&self) -> u32 {
+        self.execution_engines as u32 * self.fma_per_engine as u32
+    }
+
+    /// FP32 FMAs per cycle per mm² of shader core area
+    ///
+    /// The core count is derived from `fp32_fmas_total` and the per-core
+    /// throughput of this model. Returns `None` without a known die size.
+    pub fn performance_per_mm2(&self, fp32_fmas_total: u32) -> Option<f64> {
+        let area_per_core = self.die_size_mm2?;
+        let per_core = self.fp32_fmas_per_core().max(1);
+        let cores = (fp32_fmas_total.saturating_add(per_core - 1) / per_core).max(1);
+        Some(fp32_fmas_total as f64 / (area_per_core * cores as f64))
+    }
+
+    /// Peak memory bandwidth in GB/s at a memory clock of `clock_mhz`
+    ///
+    /// Assumes one transfer per clock over `bus_width_bits`. Returns `None`
+    /// without a known bus width.
+    pub fn bandwidth_gbps_at(&self, clock_mhz: u32) -> Option<f64> {
+        let bytes_per_transfer = self.bus_width_bits? as f64 / 8.0;
+        Some(bytes_per_transfer * clock_mhz as f64 / 1000.0)
+    }
+
+    /// Transistors per core in millions
+    ///
+    /// Uses `estimated_transistors_million` when published, otherwise
+    /// `estimate_transistors` from the core area and process node.
+    pub fn transistor_count_estimate(&self) -> Option<u32> {
+        self.estimated_transistors_million.or_else(|| {
+            Some(estimate_transistors(self.die_size_mm2?, self.process_node_nm?))
+        })
+    }
+}
+
+/// Logic density in million transistors per mm² by TSMC process node
+const PROCESS_DENSITY_MTR_PER_MM2: &[(u16, f64)] = &[
+    (28, 15.3),
+    (16, 28.9),
+    (12, 33.8),
+    (10, 52.5),
+    (7, 91.2),
+    (6, 114.2),
+    (5, 171.3),
+    (4, 196.6),
+    (3, 292.2),
+];
+
+/// Estimate the transistor count in millions of `die_size_mm2` on `process_node_nm`
+///
+/// Nodes missing from the density table use the nearest listed node.
+pub fn estimate_transistors(die_size_mm2: f64, process_node_nm: u16) -> u32 {
+    let mut density = PROCESS_DENSITY_MTR_PER_MM2[0].1;
+    let mut best_distance = u16::MAX;
+    for &(node, node_density) in PROCESS_DENSITY_MTR_PER_MM2 {
+        let distance = node.abs_diff(process_node_nm);
+        if distance < best_distance {
+            best_distance = distance;
+            density = node_density;
+        }
+    }
+    (die_size_mm2 * density + 0.5) as u32
+}
+
+/// Performance/power tier of a Mali GPU model
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum GpuTier {
     /// Very low-end / ultra power efficient
@@ -42,50 +119,50 @@ pub enum GpuTier {
 /// Complete database of known Mali GPU models
 pub const MALI_GPU_MODELS: &[MaliGpuModel] = &[
     // Midgard Architecture
-    MaliGpuModel { id: 0x6956, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T600",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0620, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T620",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0720, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T720",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 1, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0750, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T760",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0820, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T820",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 1, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0830, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T830",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0860, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T860",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x0880, id_mask: 0xFFF0, min_cores: 1, name: "Mali-T880",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 3, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
+    MaliGpuModel { id: 0x6956, id_mask: 0xFFF0, min_cores: 1, max_cores: 4,  name: "Mali-T600",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0620, id_mask: 0xFFF0, min_cores: 1, max_cores: 4,  name: "Mali-T620",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0720, id_mask: 0xFFF0, min_cores: 1, max_cores: 8,  name: "Mali-T720",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 1, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0750, id_mask: 0xFFF0, min_cores: 1, max_cores: 16, name: "Mali-T760",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0820, id_mask: 0xFFF0, min_cores: 1, max_cores: 4,  name: "Mali-T820",   architecture: "Midgard", tier: GpuTier::UltraLowPower, execution_engines: 1, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0830, id_mask: 0xFFF0, min_cores: 1, max_cores: 4,  name: "Mali-T830",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0860, id_mask: 0xFFF0, min_cores: 1, max_cores: 16, name: "Mali-T860",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x0880, id_mask: 0xFFF0, min_cores: 1, max_cores: 16, name: "Mali-T880",   architecture: "Midgard", tier: GpuTier::LowPower,      execution_engines: 3, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
 
     // Bifrost Architecture
-    MaliGpuModel { id: 0x6000, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G71",    architecture: "Bifrost", tier: GpuTier::Mainstream,    execution_engines: 3, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x6001, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G72",    architecture: "Bifrost", tier: GpuTier::Mainstream,    execution_engines: 3, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1 },
-    MaliGpuModel { id: 0x7000, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G51",    architecture: "Bifrost", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 2, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x7001, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G76",    architecture: "Bifrost", tier: GpuTier::Mainstream,    execution_engines: 3, fma_per_engine: 8,  texels_per_cycle: 2, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x7002, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G52",    architecture: "Bifrost", tier: GpuTier::LowPower,      execution_engines: 3, fma_per_engine: 8,  texels_per_cycle: 2, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x7003, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G31",    architecture: "Bifrost", tier: GpuTier::UltraLowPower, execution_engines: 1, fma_per_engine: 4,  texels_per_cycle: 2, pixels_per_cycle: 2 },
+    MaliGpuModel { id: 0x6000, id_mask: 0xFFFF, min_cores: 1, max_cores: 32, name: "Mali-G71",    architecture: "Bifrost", tier: GpuTier::Mainstream,    execution_engines: 3, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x6001, id_mask: 0xFFFF, min_cores: 1, max_cores: 32, name: "Mali-G72",    architecture: "Bifrost", tier: GpuTier::Mainstream,    execution_engines: 3, fma_per_engine: 4,  texels_per_cycle: 1, pixels_per_cycle: 1, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x7000, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G51",    architecture: "Bifrost", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 4,  texels_per_cycle: 2, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x7001, id_mask: 0xFFFF, min_cores: 1, max_cores: 20, name: "Mali-G76",    architecture: "Bifrost", tier: GpuTier::Mainstream,    execution_engines: 3, fma_per_engine: 8,  texels_per_cycle: 2, pixels_per_cycle: 2, die_size_mm2: Some(3.0), bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x7002, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G52",    architecture: "Bifrost", tier: GpuTier::LowPower,      execution_engines: 3, fma_per_engine: 8,  texels_per_cycle: 2, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x7003, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G31",    architecture: "Bifrost", tier: GpuTier::UltraLowPower, execution_engines: 1, fma_per_engine: 4,  texels_per_cycle: 2, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
 
     // Valhall Architecture
-    MaliGpuModel { id: 0x9000, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G77",    architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x9001, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G57",    architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x9003, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G57",    architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x9004, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G68",    architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x9002, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G78",    architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0x9005, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G78AE",  architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2 },
-    MaliGpuModel { id: 0xa002, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G710",   architecture: "Valhall", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xa007, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G610",   architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xa003, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G510",   architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xa004, id_mask: 0xFFFF, min_cores: 1, name: "Mali-G310",   architecture: "Valhall", tier: GpuTier::UltraLowPower, execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4 },
+    MaliGpuModel { id: 0x9000, id_mask: 0xFFFF, min_cores: 1, max_cores: 16, name: "Mali-G77",    architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: Some(5), estimated_transistors_million: Some(600) },
+    MaliGpuModel { id: 0x9001, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G57",    architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x9003, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G57",    architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x9004, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G68",    architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0x9002, id_mask: 0xFFFF, min_cores: 1, max_cores: 24, name: "Mali-G78",    architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2, die_size_mm2: Some(5.0), bus_width_bits: Some(256), process_node_nm: Some(5), estimated_transistors_million: Some(800) },
+    MaliGpuModel { id: 0x9005, id_mask: 0xFFFF, min_cores: 1, max_cores: 24, name: "Mali-G78AE",  architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 16, texels_per_cycle: 4, pixels_per_cycle: 2, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xa002, id_mask: 0xFFFF, min_cores: 1, max_cores: 16, name: "Mali-G710",   architecture: "Valhall", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xa007, id_mask: 0xFFFF, min_cores: 1, max_cores: 6,  name: "Mali-G610",   architecture: "Valhall", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xa003, id_mask: 0xFFFF, min_cores: 1, max_cores: 3,  name: "Mali-G510",   architecture: "Valhall", tier: GpuTier::LowPower,      execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xa004, id_mask: 0xFFFF, min_cores: 1, max_cores: 1,  name: "Mali-G310",   architecture: "Valhall", tier: GpuTier::UltraLowPower, execution_engines: 2, fma_per_engine: 32, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
 
     // 5th Generation / Immortalis
-    MaliGpuModel { id: 0xb002, id_mask: 0xFFFF, min_cores: 10, name: "Immortalis-G715", architecture: "Valhall",     tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xb002, id_mask: 0xFFFF, min_cores: 7,  name: "Mali-G715",       architecture: "Valhall",     tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xb002, id_mask: 0xFFFF, min_cores: 1,  name: "Mali-G615",       architecture: "Valhall",     tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xb003, id_mask: 0xFFFF, min_cores: 1,  name: "Mali-G615",       architecture: "Valhall",     tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xc000, id_mask: 0xFFFF, min_cores: 10, name: "Immortalis-G720", architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xc000, id_mask: 0xFFFF, min_cores: 6,  name: "Mali-G720",       architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xc000, id_mask: 0xFFFF, min_cores: 1,  name: "Mali-G620",       architecture: "Arm 5th Gen", tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xc001, id_mask: 0xFFFF, min_cores: 1,  name: "Mali-G620",       architecture: "Arm 5th Gen", tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xd000, id_mask: 0xFFFF, min_cores: 10, name: "Immortalis-G925", architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xd000, id_mask: 0xFFFF, min_cores: 6,  name: "Mali-G725",       architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xd001, id_mask: 0xFFFF, min_cores: 1,  name: "Mali-G625",       architecture: "Arm 5th Gen", tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xe000, id_mask: 0xFFFF, min_cores: 10, name: "Mali G1-Ultra",   architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xe001, id_mask: 0xFFFF, min_cores: 6,  name: "Mali G1-Premium", architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
-    MaliGpuModel { id: 0xe003, id_mask: 0xFFFF, min_cores: 1,  name: "Mali G1-Pro",     architecture: "Arm 5th Gen", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4 },
+    MaliGpuModel { id: 0xb002, id_mask: 0xFFFF, min_cores: 10, max_cores: 16, name: "Immortalis-G715", architecture: "Valhall",     tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xb002, id_mask: 0xFFFF, min_cores: 7,  max_cores: 9,  name: "Mali-G715",       architecture: "Valhall",     tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xb002, id_mask: 0xFFFF, min_cores: 1,  max_cores: 6,  name: "Mali-G615",       architecture: "Valhall",     tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xb003, id_mask: 0xFFFF, min_cores: 1,  max_cores: 6,  name: "Mali-G615",       architecture: "Valhall",     tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xc000, id_mask: 0xFFFF, min_cores: 10, max_cores: 16, name: "Immortalis-G720", architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: Some(7.0), bus_width_bits: Some(256), process_node_nm: Some(4), estimated_transistors_million: Some(1200) },
+    MaliGpuModel { id: 0xc000, id_mask: 0xFFFF, min_cores: 6,  max_cores: 9,  name: "Mali-G720",       architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xc000, id_mask: 0xFFFF, min_cores: 1,  max_cores: 5,  name: "Mali-G620",       architecture: "Arm 5th Gen", tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xc001, id_mask: 0xFFFF, min_cores: 1,  max_cores: 5,  name: "Mali-G620",       architecture: "Arm 5th Gen", tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xd000, id_mask: 0xFFFF, min_cores: 10, max_cores: 24, name: "Immortalis-G925", architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xd000, id_mask: 0xFFFF, min_cores: 6,  max_cores: 9,  name: "Mali-G725",       architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xd001, id_mask: 0xFFFF, min_cores: 1,  max_cores: 5,  name: "Mali-G625",       architecture: "Arm 5th Gen", tier: GpuTier::LowPower,        execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xe000, id_mask: 0xFFFF, min_cores: 10, max_cores: 24, name: "Mali G1-Ultra",   architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xe001, id_mask: 0xFFFF, min_cores: 6,  max_cores: 9,  name: "Mali G1-Premium", architecture: "Arm 5th Gen", tier: GpuTier::HighPerformance, execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(256), process_node_nm: None, estimated_transistors_million: None },
+    MaliGpuModel { id: 0xe003, id_mask: 0xFFFF, min_cores: 1,  max_cores: 5,  name: "Mali G1-Pro",     architecture: "Arm 5th Gen", tier: GpuTier::Mainstream,    execution_engines: 2, fma_per_engine: 64, texels_per_cycle: 8, pixels_per_cycle: 4, die_size_mm2: None, bus_width_bits: Some(128), process_node_nm: None, estimated_transistors_million: None },
 ];
 
 /// Try to identify a Mali GPU model from the combined 32-bit GPU identifier
@@ -95,13 +172,161 @@ pub fn identify_mali_gpu(gpu_id: u32) -> Option<&'static MaliGpuModel> {
     let hw_id = (gpu_id & 0xFFFF) as u16;
     let core_count = ((gpu_id >> 16) & 0xFF) as u8;
 
-    for model in MALI_GPU_MODELS {
-        if (hw_id & model.id_mask) == (model.id & model.id_mask) {
-            if core_count >= model.min_cores {
-                return Some(model);
-            }
-        }
+    MALI_GPU_MODELS.iter().find(|model| {
+        (hw_id & model.id_mask) == (model.id & model.id_mask) && core_count >= model.min_cores
+    })
+}
+
+/// All models matching `hw_id` whose `min_cores` lies in `min_cores..=max_cores`
+///
+/// For DeviceTree blobs that only give a core range. Sorted by `min_cores`
+/// descending, like the lookup order of `identify_mali_gpu`.
+#[cfg(feature = "std")]
+pub fn identify_mali_gpu_range(hw_id: u16, min_cores: u8, max_cores: u8) -> Vec<&'static MaliGpuModel> {
+    let mut models: Vec<_> = MALI_GPU_MODELS.iter()
+        .filter(|model| (hw_id & model.id_mask) == (model.id & model.id_mask))
+        .filter(|model| (min_cores..=max_cores).contains(&model.min_cores))
+        .collect();
+    models.sort_by_key(|model| core::cmp::Reverse(model.min_cores));
+    models
+}
+
+/// Find a Mali GPU model by marketing name (case-insensitive)
+///
+/// Returns the first matching entry, e.g. for `"immortalis-g720"`.
+pub fn find_mali_model_by_name(name: &str) -> Option<&'static MaliGpuModel> {
+    let name = name.trim();
+    MALI_GPU_MODELS.iter().find(|model| model.name.eq_ignore_ascii_case(name))
+}
+
+/// All Mali GPU models of one architecture (case-insensitive), e.g. `"Valhall"`
+///
+/// `MALI_GPU_MODELS` is grouped by architecture, so the result is a single
+/// contiguous slice. Unknown architectures yield an empty slice.
+pub fn find_mali_models_by_architecture(arch: &str) -> &'static [MaliGpuModel] {
+    let arch = arch.trim();
+    let matches = |model: &MaliGpuModel| model.architecture.eq_ignore_ascii_case(arch);
+
+    let Some(start) = MALI_GPU_MODELS.iter().position(matches) else {
+        return &[];
+    };
+    let len = MALI_GPU_MODELS[start..].iter().take_while(|m| matches(m)).count();
+    &MALI_GPU_MODELS[start..start + len]
+}
+
+/// Rank models by FP32 performance per mm², best first
+///
+/// `fp32_total[i]` is the total FP32 FMA count for `models[i]`. Returns indices
+/// into `models`; models without a known die size are placed last.
+#[cfg(feature = "std")]
+pub fn rank_by_efficiency(models: &[&MaliGpuModel], fp32_total: &[u32]) -> Vec<usize> {
+    let scores: Vec<Option<f64>> = models.iter()
+        .zip(fp32_total)
+        .map(|(model, &total)| model.performance_per_mm2(total))
+        .collect();
+
+    let mut indices: Vec<usize> = (0..scores.len()).collect();
+    indices.sort_by(|&a, &b| match (scores[a], scores[b]) {
+        (Some(x), Some(y)) => y.total_cmp(&x),
+        (Some(_), None) => core::cmp::Ordering::Less,
+        (None, Some(_)) => core::cmp::Ordering::Greater,
+        (None, None) => core::cmp::Ordering::Equal,
+    });
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_performance_per_mm2() {
+        let g76 = find_mali_model_by_name("Mali-G76").unwrap();
+        // 12 cores * 24 FMAs over 12 * 3 mm²
+        assert_eq!(g76.performance_per_mm2(12 * 24), Some(8.0));
+        assert!(find_mali_model_by_name("Mali-G71").unwrap().performance_per_mm2(96).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rank_by_efficiency() {
+        let g71 = find_mali_model_by_name("Mali-G71").unwrap();
+        let g76 = find_mali_model_by_name("Mali-G76").unwrap();
+        let g78 = find_mali_model_by_name("Mali-G78").unwrap();
+        let g720 = find_mali_model_by_name("Immortalis-G720").unwrap();
+
+        // per mm²: G76 24/3 = 8, G78 32/5 = 6.4, G720 128/7 ≈ 18.3
+        let ranking = rank_by_efficiency(&[g71, g76, g78, g720], &[96, 240, 768, 1536]);
+        assert_eq!(ranking, vec![3, 1, 2, 0]);
     }
 
-    None
-}
\ No newline at end of file
+    #[test]
+    fn test_transistor_count_estimate() {
+        // 5 mm² at 171.3 MTr/mm²
+        assert_eq!(estimate_transistors(5.0, 5), 857);
+        // 8 nm falls back to the 7 nm density
+        assert_eq!(estimate_transistors(1.0, 8), 91);
+
+        let g78 = find_mali_model_by_name("Mali-G78").unwrap();
+        assert_eq!(g78.process_node_nm, Some(5));
+        assert_eq!(find_mali_model_by_name("Mali-G77").unwrap().process_node_nm, Some(5));
+        assert_eq!(g78.transistor_count_estimate(), Some(800));
+        assert_eq!(find_mali_model_by_name("Immortalis-G720").unwrap().transistor_count_estimate(), Some(1200));
+
+        let mut g76 = find_mali_model_by_name("Mali-G76").unwrap().clone();
+        assert_eq!(g76.transistor_count_estimate(), None);
+        g76.process_node_nm = Some(7);
+        assert_eq!(g76.transistor_count_estimate(), Some(274));
+    }
+
+    #[test]
+    fn test_bandwidth_gbps_at() {
+        let g720 = find_mali_model_by_name("Mali-G720").unwrap();
+        assert_eq!(g720.bus_width_bits, Some(256));
+        // 32 bytes per transfer at 2133 MHz
+        assert!((g720.bandwidth_gbps_at(2133).unwrap() - 68.256).abs() < 1e-9);
+        assert!(MALI_GPU_MODELS.iter().all(|m| m.bus_width_bits.is_some()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_identify_mali_gpu_range() {
+        let names: Vec<&str> = identify_mali_gpu_range(0xc000, 1, 12).iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["Immortalis-G720", "Mali-G720", "Mali-G620"]);
+
+        let names: Vec<&str> = identify_mali_gpu_range(0xc000, 2, 8).iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["Mali-G720"]);
+        assert!(identify_mali_gpu_range(0xffff, 1, 16).is_empty());
+    }
+
+    #[test]
+    fn test_find_mali_model_by_name() {
+        let model = find_mali_model_by_name("immortalis-g720").unwrap();
+        assert_eq!(model.name, "Immortalis-G720");
+        assert_eq!(model.architecture, "Arm 5th Gen");
+        assert!(find_mali_model_by_name("Mali-X1").is_none());
+    }
+
+    #[test]
+    fn test_find_mali_models_by_architecture() {
+        let models = find_mali_models_by_architecture("arm 5th gen");
+        assert_eq!(models.len(), 10);
+        assert!(models.iter().all(|m| m.architecture == "Arm 5th Gen"));
+        assert!(find_mali_models_by_architecture("Utgard").is_empty());
+    }
+
+    #[test]
+    fn test_core_count_ranges() {
+        assert!(MALI_GPU_MODELS.iter().all(|m| m.min_cores <= m.max_cores));
+        let g720 = find_mali_model_by_name("Mali-G720").unwrap();
+        assert_eq!((g720.min_cores, g720.max_cores), (6, 9));
+    }
+
+    #[test]
+    fn test_models_are_grouped_by_architecture() {
+        for arch in ["Midgard", "Bifrost", "Valhall", "Arm 5th Gen"] {
+            let total = MALI_GPU_MODELS.iter().filter(|m| m.architecture == arch).count();
+            assert_eq!(find_mali_models_by_architecture(arch).len(), total, "{} is not contiguous", arch);
+        }
+    }
+}
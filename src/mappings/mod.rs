@@ -8,12 +8,22 @@ pub use mali::{
     MaliGpuModel,
     GpuTier,
     identify_mali_gpu,
+    find_mali_model_by_name,
+    find_mali_models_by_architecture,
+    estimate_transistors,
     MALI_GPU_MODELS,
 };
+#[cfg(feature = "std")]
+pub use mali::{identify_mali_gpu_range, rank_by_efficiency};
 
 pub mod adreno;
 pub use adreno::{AdrenoGpuModel, identify_adreno_gpu};
 
+pub mod vivante;
+pub use vivante::{VivanteGpuModel, identify_vivante_gpu};
+
+pub mod no_std;
+
 // Optional: Add Adreno later by uncommenting
 // pub use adreno::{AdrenoGpuModel, identify_adreno_gpu};
 
@@ -24,20 +34,66 @@ pub enum GpuVendor {
     Mali,
     /// Qualcomm Adreno GPUs (not yet fully implemented)
     Adreno,
+    /// Intel GPUs (detected via DRM only)
+    Intel,
+    /// AMD GPUs (detected via DRM only)
+    AMD,
+    /// Vivante GPUs on NXP i.MX SoCs (not yet fully implemented)
+    Vivante,
+    /// Imagination PowerVR GPUs (detected via device node only)
+    PowerVR,
     /// Unknown or unsupported vendor
     Unknown,
 }
 
-impl std::fmt::Display for GpuVendor {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for GpuVendor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             GpuVendor::Mali => write!(f, "ARM Mali"),
             GpuVendor::Adreno => write!(f, "Qualcomm Adreno"),
+            GpuVendor::Intel => write!(f, "Intel"),
+            GpuVendor::AMD => write!(f, "AMD"),
+            GpuVendor::Vivante => write!(f, "Vivante"),
+            GpuVendor::PowerVR => write!(f, "Imagination PowerVR"),
             GpuVendor::Unknown => write!(f, "Unknown"),
         }
     }
 }
 
+impl GpuVendor {
+    /// Map a DRM driver name (from `DRM_IOCTL_VERSION`) to a vendor
+    pub fn from_drm_driver(name: &str) -> GpuVendor {
+        match name {
+            "mali" | "panfrost" | "panthor" => GpuVendor::Mali,
+            "msm" | "kgsl" => GpuVendor::Adreno,
+            "i915" | "xe" => GpuVendor::Intel,
+            "amdgpu" | "radeon" => GpuVendor::AMD,
+            "etnaviv" | "galcore" => GpuVendor::Vivante,
+            _ => GpuVendor::Unknown,
+        }
+    }
+
+    /// Guess the vendor from a device node path, without any IOCTL calls
+    ///
+    /// DRI render nodes (`/dev/dri/renderD*`) are shared by all vendors and
+    /// yield `Unknown`; query `DRM_IOCTL_VERSION` and use
+    /// [`from_drm_driver`](Self::from_drm_driver) for those.
+    pub fn from_device_path(path: &str) -> GpuVendor {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if name.starts_with("mali") {
+            GpuVendor::Mali
+        } else if name.starts_with("kgsl") {
+            GpuVendor::Adreno
+        } else if name.starts_with("pvr") {
+            GpuVendor::PowerVR
+        } else if name.starts_with("galcore") {
+            GpuVendor::Vivante
+        } else {
+            GpuVendor::Unknown
+        }
+    }
+}
+
 /// Extract the hardware ID (lower 16 bits) from a 32-bit Mali GPU identifier
 ///
 /// Format: [variant:8 | core_count:8 | hw_id:16]
@@ -45,6 +101,13 @@ pub fn parse_mali_gpu_id(gpu_id: u32) -> u16 {
     (gpu_id & 0xFFFF) as u16
 }
 
+/// Extract the hardware ID from a GPU identifier that was read with the wrong byte order
+///
+/// Use this when the raw ioctl bytes are big-endian but were decoded as little-endian.
+pub fn parse_mali_gpu_id_be(gpu_id: u32) -> u16 {
+    parse_mali_gpu_id(gpu_id.swap_bytes())
+}
+
 /// Extract the number of shader cores from a 32-bit Mali GPU identifier
 pub fn parse_mali_core_count(gpu_id: u32) -> u8 {
     ((gpu_id >> 16) & 0xFF) as u8
@@ -53,4 +116,20 @@ pub fn parse_mali_core_count(gpu_id: u32) -> u8 {
 /// Extract the variant number from a 32-bit Mali GPU identifier
 pub fn parse_mali_variant(gpu_id: u32) -> u8 {
     ((gpu_id >> 24) & 0xFF) as u8
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vendor_from_device_path() {
+        assert_eq!(GpuVendor::from_device_path("/dev/mali0"), GpuVendor::Mali);
+        assert_eq!(GpuVendor::from_device_path("/dev/kgsl-3d0"), GpuVendor::Adreno);
+        assert_eq!(GpuVendor::from_device_path("/dev/pvrsrvkm"), GpuVendor::PowerVR);
+        assert_eq!(GpuVendor::from_device_path("/dev/pvr_sync"), GpuVendor::PowerVR);
+        assert_eq!(GpuVendor::from_device_path("/dev/galcore"), GpuVendor::Vivante);
+        assert_eq!(GpuVendor::from_device_path("/dev/dri/renderD128"), GpuVendor::Unknown);
+        assert_eq!(GpuVendor::from_device_path("mali0"), GpuVendor::Mali);
+    }
+}
@@ -0,0 +1,36 @@
+//! Allocation-free subset of the GPU model database
+//!
+//! Everything re-exported here only works on `'static` tables and primitive
+//! types and uses nothing beyond `core`. With `default-features = false` the
+//! crate builds without `std` and [`crate::mappings`] is all that remains,
+//! for UEFI or other `no_std` code.
+//!
+//! Functions that return a `Vec` (`identify_mali_gpu_range`,
+//! `rank_by_efficiency`) are left out; they need the `std` feature.
+
+pub use super::{parse_mali_core_count, parse_mali_gpu_id, parse_mali_gpu_id_be, parse_mali_variant};
+pub use super::adreno::{identify_adreno_gpu, AdrenoGpuModel};
+pub use super::mali::{
+    estimate_transistors,
+    find_mali_model_by_name,
+    find_mali_models_by_architecture,
+    identify_mali_gpu,
+    GpuTier,
+    MaliGpuModel,
+    MALI_GPU_MODELS,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_std_subset() {
+        assert_eq!((parse_mali_gpu_id(0x000c_c000), parse_mali_core_count(0x000c_c000)), (0xc000, 12));
+        let model = identify_mali_gpu(0x000c_c000).unwrap();
+        assert_eq!(model.name, "Immortalis-G720");
+        assert_eq!(find_mali_model_by_name("immortalis-g720").map(|m| m.id), Some(model.id));
+        assert!(find_mali_models_by_architecture(model.architecture).len() > 1);
+        assert!(identify_adreno_gpu(&[]).is_none());
+    }
+}
@@ -0,0 +1,20 @@
+//! Placeholder for Vivante GPU identification (NXP i.MX SoCs)
+//! (Support is experimental/not implemented yet)
+
+/// Stub function: Always returns None until real Vivante identification is implemented
+///
+/// The galcore driver reports a chip model (e.g. 0x7000 for GC7000) and
+/// revision; mapping those to models can be added here later.
+pub fn identify_vivante_gpu(_chip_model: u32) -> Option<&'static VivanteGpuModel> {
+    None
+}
+
+/// Minimal placeholder type for Vivante GPU models
+#[derive(Debug, Clone)]
+pub struct VivanteGpuModel {
+    /// Marketing name (e.g. "GC7000")
+    pub name: &'static str,
+
+    /// Chip model number reported by galcore
+    pub chip_model: u32,
+}
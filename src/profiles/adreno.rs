@@ -17,6 +17,14 @@ fn create_placeholder_adreno_profile() -> IoctlProfile {
     IoctlProfile {
         vendor: "Qualcomm".to_string(),
         model: "Adreno (Placeholder)".to_string(),
+        version: "1.0.0".to_string(),
+        min_kernel_version: None,
+        compatible_kernels: Vec::new(),
+        deprecated: false,
+        superseded_by: None,
+        parent_profile: None,
+        sample_confidence: None,
+        sha256sum: None,
         detection_ioctls: vec![
             IoctlDefinition {
                 name: "KGSL_PROPERTY".to_string(),
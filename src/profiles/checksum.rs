@@ -0,0 +1,33 @@
+//! Canonical JSON for profile checksums
+
+use serde_json::Value;
+
+/// JSON text with object keys sorted and no whitespace
+pub(super) fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let entries: Vec<String> = entries.into_iter()
+                .map(|(key, value)| format!("{}:{}", Value::String(key.clone()), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        scalar => scalar.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_json() {
+        let value = serde_json::json!({ "b": [1, { "d": null, "c": "x\"y" }], "a": 1.5 });
+        assert_eq!(canonical_json(&value), r#"{"a":1.5,"b":[1,{"c":"x\"y","d":null}]}"#);
+    }
+}
@@ -2,18 +2,15 @@
 //!
 //! Contains IOCTL profiles for various Mali GPU models.
 
-use super::{IoctlProfile, IoctlDefinition};
+use super::{IoctlProfile, IoctlDefinition, KernelVersionRange};
 
 /// Load all embedded Mali profiles
 pub fn load_mali_profiles() -> Vec<IoctlProfile> {
-    let mut profiles = Vec::new();
-
-    // Add all Mali profiles
-    profiles.push(create_mali_g71_profile());
-    profiles.push(create_mali_g720_profile());
-    profiles.push(create_generic_mali_profile());
-
-    profiles
+    vec![
+        create_mali_g71_profile(),
+        create_mali_g720_profile(),
+        create_generic_mali_profile(),
+    ]
 }
 
 /// Create Mali-G71 profile based on libgpuinfo strace (Samsung SM-T510)
@@ -21,6 +18,14 @@ fn create_mali_g71_profile() -> IoctlProfile {
     IoctlProfile {
         vendor: "Mali".to_string(),
         model: "Mali-G71".to_string(),
+        version: "1.0.0".to_string(),
+        min_kernel_version: None,
+        compatible_kernels: vec![KernelVersionRange { min: "4.4".to_string(), max: Some("4.4".to_string()) }],
+        deprecated: false,
+        superseded_by: None,
+        parent_profile: None,
+        sample_confidence: None,
+        sha256sum: None,
         detection_ioctls: vec![
             IoctlDefinition {
                 name: "GET_PROPS_00".to_string(),
@@ -92,6 +97,14 @@ fn create_mali_g720_profile() -> IoctlProfile {
     IoctlProfile {
         vendor: "Mali".to_string(),
         model: "Mali-G720".to_string(),
+        version: "1.0.0".to_string(),
+        min_kernel_version: None,
+        compatible_kernels: vec![KernelVersionRange { min: "6.0".to_string(), max: Some("6".to_string()) }],
+        deprecated: false,
+        superseded_by: None,
+        parent_profile: None,
+        sample_confidence: None,
+        sha256sum: None,
         detection_ioctls: vec![
             IoctlDefinition {
                 name: "GET_PROPS_34".to_string(),
@@ -158,6 +171,14 @@ fn create_generic_mali_profile() -> IoctlProfile {
     IoctlProfile {
         vendor: "Mali".to_string(),
         model: "Generic Mali".to_string(),
+        version: "1.0.0".to_string(),
+        min_kernel_version: None,
+        compatible_kernels: Vec::new(),
+        deprecated: false,
+        superseded_by: None,
+        parent_profile: None,
+        sample_confidence: None,
+        sha256sum: None,
         detection_ioctls: vec![
             IoctlDefinition {
                 name: "GET_VERSION".to_string(),
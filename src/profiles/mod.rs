@@ -5,11 +5,17 @@
 
 mod mali;
 mod adreno;
+mod checksum;
 
 pub use mali::load_mali_profiles;
 pub use adreno::load_adreno_profiles;
 
+use crate::error::DiscoveryError;
+use crate::mappings::{GpuTier, MALI_GPU_MODELS};
 use serde::{Deserialize, Serialize};
+use sha2::Digest;
+#[cfg(feature = "watch")]
+use std::{collections::HashMap, io, path::{Path, PathBuf}, sync::Mutex};
 
 /// IOCTL profile for a GPU model
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +24,30 @@ pub struct IoctlProfile {
     pub vendor: String,
     /// GPU model name
     pub model: String,
+    /// Profile version (semantic versioning, e.g. "1.0.0")
+    #[serde(default = "default_profile_version")]
+    pub version: String,
+    /// Minimum kernel version this profile applies to (e.g. "4.4")
+    #[serde(default)]
+    pub min_kernel_version: Option<String>,
+    /// Kernel versions this profile was tested on, see `check_kernel_compatibility`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compatible_kernels: Vec<KernelVersionRange>,
+    /// Whether this profile is outdated and should no longer be used
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Model name of the profile that replaces this one
+    #[serde(default)]
+    pub superseded_by: Option<String>,
+    /// Model name of a profile to inherit detection IOCTLs and metadata from
+    #[serde(default)]
+    pub parent_profile: Option<String>,
+    /// Share of samples (0.0 - 1.0) the detection IOCTLs were seen in, for generated profiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_confidence: Option<f32>,
+    /// Expected `checksum()` of this profile, verified by `from_json_file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256sum: Option<String>,
     /// Detection IOCTLs for identifying this GPU
     pub detection_ioctls: Vec<IoctlDefinition>,
     /// Version query IOCTL (optional)
@@ -30,6 +60,60 @@ pub struct IoctlProfile {
     pub metadata: serde_json::Value,
 }
 
+/// Inclusive range of kernel versions, e.g. `4.4` to `4.9`
+///
+/// Components missing from `max` match anything, so `"6"` covers all 6.x
+/// kernels. Without `max` the range is open-ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KernelVersionRange {
+    /// Oldest kernel version in the range
+    pub min: String,
+    /// Newest kernel version in the range
+    #[serde(default)]
+    pub max: Option<String>,
+}
+
+impl KernelVersionRange {
+    /// Whether `version` lies in this range; unparsable bounds never match
+    pub fn contains(&self, version: &KernelVersion) -> bool {
+        let Some(min) = KernelVersion::parse(&self.min) else {
+            return false;
+        };
+        if *version < min {
+            return false;
+        }
+        let Some(max) = &self.max else {
+            return true;
+        };
+        let Some(max_version) = KernelVersion::parse(max) else {
+            return false;
+        };
+        let precision = max.split('.').take(3).count();
+        let running = [version.0, version.1, version.2];
+        let max_parts = [max_version.0, max_version.1, max_version.2];
+        running[..precision] <= max_parts[..precision]
+    }
+}
+
+/// Result of [`check_kernel_compatibility`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatibilityStatus {
+    /// The running kernel lies in one of the profile's `compatible_kernels`
+    Compatible,
+    /// The profile lists no kernels, or the running kernel is unknown
+    Untested,
+    /// The running kernel lies outside every listed range
+    Incompatible,
+}
+
+/// Check the running kernel from `/proc/version` against `compatible_kernels`
+pub fn check_kernel_compatibility(profile: &IoctlProfile) -> CompatibilityStatus {
+    match KernelVersion::running() {
+        Some(kernel) => profile.kernel_compatibility(&kernel),
+        None => CompatibilityStatus::Untested,
+    }
+}
+
 /// Definition of an IOCTL command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IoctlDefinition {
@@ -43,4 +127,968 @@ pub struct IoctlDefinition {
     pub parser: String,
     /// Additional parameters
     pub params: serde_json::Value,
-}
\ No newline at end of file
+}
+
+impl IoctlDefinition {
+    /// Size of the argument structure encoded in the command number
+    pub fn encoded_size(&self) -> u16 {
+        ((self.cmd >> 16) & 0x3FFF) as u16
+    }
+
+    /// Check the definition for structural errors
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(format!("IOCTL 0x{:08x} has no name", self.cmd));
+        }
+        if self.cmd == 0 {
+            return Err(format!("IOCTL {} has command number 0", self.name));
+        }
+        if self.parser.trim().is_empty() {
+            return Err(format!("IOCTL {} has no parser", self.name));
+        }
+        if self.buffer_size < self.encoded_size() {
+            return Err(format!(
+                "IOCTL {} buffer_size {} is smaller than the encoded size {}",
+                self.name, self.buffer_size, self.encoded_size()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check a live response against the expectations in `params`
+    ///
+    /// `expected_return` is compared with `ret_val`, `expected_core_mask`
+    /// (number or hex string) with the first 4 bytes of `response`.
+    pub fn validate_response(&self, response: &[u8], ret_val: i32) -> ValidationResult {
+        let mut checked = false;
+
+        if let Some(expected) = self.params.get("expected_return").and_then(|v| v.as_i64()) {
+            if expected != ret_val as i64 {
+                return ValidationResult::Mismatch {
+                    expected: expected.to_string(),
+                    actual: ret_val.to_string(),
+                };
+            }
+            checked = true;
+        }
+
+        if let Some(expected) = self.params.get("expected_core_mask").and_then(parse_json_u32) {
+            if let Some(bytes) = response.get(..4) {
+                let actual = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                if actual != expected {
+                    return ValidationResult::Mismatch {
+                        expected: format!("0x{:x}", expected),
+                        actual: format!("0x{:x}", actual),
+                    };
+                }
+                checked = true;
+            }
+        }
+
+        if checked {
+            ValidationResult::Valid
+        } else {
+            ValidationResult::Inconclusive
+        }
+    }
+}
+
+/// Outcome of checking a live IOCTL response against profile expectations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationResult {
+    /// All expectations present in `params` were met
+    Valid,
+    /// A value did not match the profile
+    Mismatch {
+        /// Value from the profile
+        expected: String,
+        /// Value returned by the device
+        actual: String,
+    },
+    /// No expectations to check, or the response was too short
+    Inconclusive,
+}
+
+/// Read a JSON number or "0x"-prefixed hex string as u32
+pub(crate) fn parse_json_u32(value: &serde_json::Value) -> Option<u32> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn default_profile_version() -> String {
+    "1.0.0".to_string()
+}
+
+impl IoctlProfile {
+    /// Build a profile from raw strace output of a GPU userspace library
+    ///
+    /// Understands lines like `ioctl(3, 0x40108003, 0x7ff4321000) = 749`,
+    /// optionally prefixed with a PID, as well as symbolic commands: names of
+    /// built-in profile IOCTLs (with or without a `KBASE_IOCTL_` /
+    /// `MALI_IOCTL_` / `IOCTL_KGSL_` prefix) and `_IOC(dir, ty, nr, size)`.
+    /// Direction and buffer size come from the command bits. Commands are
+    /// grouped by `(ty, nr)`, keeping the first one seen.
+    pub fn from_strace_output(strace_text: &str, vendor: &str, model: &str) -> Result<IoctlProfile, DiscoveryError> {
+        let mut known = load_mali_profiles();
+        known.extend(load_adreno_profiles());
+
+        let mut seen = std::collections::HashSet::new();
+        let mut detection_ioctls = Vec::new();
+        let mut directions = serde_json::Map::new();
+        for (cmd, name) in strace_text.lines().filter_map(|line| parse_strace_ioctl(line, &known)) {
+            let (ty, nr) = ((cmd >> 8) as u8, cmd as u8);
+            if !seen.insert((ty, nr)) {
+                continue;
+            }
+
+            let direction = match cmd >> 30 {
+                0 => "none",
+                1 => "write",
+                2 => "read",
+                _ => "read_write",
+            };
+            directions.insert(format!("0x{:08x}", cmd), direction.into());
+            detection_ioctls.push(IoctlDefinition {
+                name: name.unwrap_or_else(|| format!("unknown_{:02x}_{:02x}", ty, nr)),
+                cmd,
+                buffer_size: ((cmd >> 16) & 0x3FFF) as u16,
+                parser: "parse_generic".to_string(),
+                params: serde_json::json!({}),
+            });
+        }
+
+        if detection_ioctls.is_empty() {
+            return Err(DiscoveryError::Profile("No ioctl() calls found in strace output".to_string()));
+        }
+
+        Ok(IoctlProfile {
+            vendor: vendor.to_string(),
+            model: model.to_string(),
+            version: default_profile_version(),
+            min_kernel_version: None,
+            compatible_kernels: Vec::new(),
+            deprecated: false,
+            superseded_by: None,
+            parent_profile: None,
+            sample_confidence: None,
+            sha256sum: None,
+            detection_ioctls,
+            version_ioctl: None,
+            gpu_info_ioctl: None,
+            features_ioctl: None,
+            metadata: serde_json::json!({
+                "confirmed_by": "strace",
+                "directions": directions,
+            }),
+        })
+    }
+
+    /// Load a profile from a JSON file, verifying `sha256sum` if present
+    pub fn from_json_file(path: &str) -> Result<Self, DiscoveryError> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| DiscoveryError::Profile(format!("Failed to read {}: {}", path, e)))?;
+        let profile: IoctlProfile = serde_json::from_str(&data)?;
+        if let Some(expected) = &profile.sha256sum {
+            if !profile.verify_checksum(expected) {
+                return Err(DiscoveryError::Profile("checksum mismatch".to_string()));
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Hex SHA-256 of the profile as canonical JSON
+    ///
+    /// Object keys are sorted and the `sha256sum` field itself is left out,
+    /// so the digest does not depend on the formatting of the file.
+    pub fn checksum(&self) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let serde_json::Value::Object(map) = &mut value {
+            map.remove("sha256sum");
+        }
+        let digest = sha2::Sha256::digest(checksum::canonical_json(&value).as_bytes());
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Whether `checksum()` equals `expected`, ignoring case
+    pub fn verify_checksum(&self, expected: &str) -> bool {
+        self.checksum().eq_ignore_ascii_case(expected.trim())
+    }
+
+    /// Parse the profile version as a semantic version
+    #[cfg(feature = "semver")]
+    pub fn semver(&self) -> Option<semver::Version> {
+        semver::Version::parse(&self.version).ok()
+    }
+
+    /// Check the profile for structural errors
+    ///
+    /// Verifies that vendor, model and version are set, that at least one
+    /// detection IOCTL exists, and that every IOCTL buffer is large enough for
+    /// the size encoded in its command number.
+    pub fn validate(&self) -> Result<(), DiscoveryError> {
+        if self.vendor.trim().is_empty() {
+            return Err(DiscoveryError::Profile("vendor must not be empty".to_string()));
+        }
+        if self.model.trim().is_empty() {
+            return Err(DiscoveryError::Profile("model must not be empty".to_string()));
+        }
+        let version_ok = self.version.split('.').count() == 3
+            && self.version.split('.').all(|p| p.parse::<u64>().is_ok());
+        if !version_ok {
+            return Err(DiscoveryError::Profile(format!(
+                "{}: version '{}' is not in major.minor.patch format", self.model, self.version
+            )));
+        }
+        if self.detection_ioctls.is_empty() {
+            return Err(DiscoveryError::Profile(format!(
+                "{}: at least one detection IOCTL is required", self.model
+            )));
+        }
+
+        let optional = [&self.version_ioctl, &self.gpu_info_ioctl, &self.features_ioctl];
+        for ioctl in self.detection_ioctls.iter().chain(optional.into_iter().flatten()) {
+            ioctl.validate().map_err(|e| DiscoveryError::Profile(format!("{}: {}", self.model, e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Check whether this profile applies to the given kernel version
+    ///
+    /// Profiles without `min_kernel_version`, or with versions that cannot be
+    /// parsed, are considered applicable.
+    pub fn is_applicable_for_kernel(&self, kernel_ver: &str) -> bool {
+        let Some(min_version) = &self.min_kernel_version else {
+            return true;
+        };
+
+        match (KernelVersion::parse(min_version), KernelVersion::parse(kernel_ver)) {
+            (Some(min), Some(running)) => running >= min,
+            _ => true,
+        }
+    }
+
+    /// Check `kernel` against `compatible_kernels`
+    pub fn kernel_compatibility(&self, kernel: &KernelVersion) -> CompatibilityStatus {
+        if self.compatible_kernels.is_empty() {
+            CompatibilityStatus::Untested
+        } else if self.compatible_kernels.iter().any(|range| range.contains(kernel)) {
+            CompatibilityStatus::Compatible
+        } else {
+            CompatibilityStatus::Incompatible
+        }
+    }
+
+    /// Performance tier of the profiled GPU
+    ///
+    /// Taken from `metadata.tier` if present, otherwise looked up by model
+    /// name in the Mali hardware database.
+    pub fn tier(&self) -> Option<GpuTier> {
+        if let Some(tier) = self.metadata.get("tier") {
+            return serde_json::from_value(tier.clone()).ok();
+        }
+        MALI_GPU_MODELS.iter()
+            .find(|m| m.name == self.model)
+            .map(|m| m.tier.clone())
+    }
+
+    /// Confidence factor for this profile on the given kernel
+    ///
+    /// Starts at 1.0 when the running kernel matches the
+    /// `confirmed_kernel_version` (or `kernel_version`) in `metadata`, drops by
+    /// 0.1 per major version of difference and never goes below 0.3. Profiles
+    /// without a confirmed kernel get 1.0.
+    pub fn confidence(&self, running_kernel_version: &KernelVersion) -> f64 {
+        let confirmed = self.metadata.get("confirmed_kernel_version")
+            .or_else(|| self.metadata.get("kernel_version"))
+            .and_then(|v| v.as_str())
+            .and_then(KernelVersion::parse);
+
+        match confirmed {
+            Some(confirmed) => {
+                let distance = confirmed.major().abs_diff(running_kernel_version.major());
+                (1.0 - 0.1 * distance as f64).max(0.3)
+            }
+            None => 1.0,
+        }
+    }
+}
+
+/// Merge built-in and user-provided profiles into one ranked list
+///
+/// User profiles take precedence over built-in ones with the same vendor and
+/// model; detection IOCTLs of both are combined without duplicate commands.
+/// The result is ordered by GPU tier, HighPerformance first, because firmware
+/// often answers the signatures of several tiers and the specific profile
+/// yields more data. Profiles without a known tier come last.
+pub fn merge_profiles(builtin: Vec<IoctlProfile>, user: Vec<IoctlProfile>) -> Vec<IoctlProfile> {
+    let mut merged: Vec<IoctlProfile> = Vec::new();
+
+    for profile in user.into_iter().chain(builtin) {
+        match merged.iter_mut().find(|p| p.vendor == profile.vendor && p.model == profile.model) {
+            Some(existing) => {
+                for ioctl in profile.detection_ioctls {
+                    if !existing.detection_ioctls.iter().any(|d| d.cmd == ioctl.cmd) {
+                        existing.detection_ioctls.push(ioctl);
+                    }
+                }
+            }
+            None => {
+                let mut profile = profile;
+                let mut seen = Vec::new();
+                profile.detection_ioctls.retain(|d| {
+                    let first = !seen.contains(&d.cmd);
+                    seen.push(d.cmd);
+                    first
+                });
+                merged.push(profile);
+            }
+        }
+    }
+
+    // Stable sort keeps user profiles ahead within the same tier
+    merged.sort_by_key(|p| match p.tier() {
+        Some(GpuTier::HighPerformance) => 0,
+        Some(GpuTier::Mainstream) => 1,
+        Some(GpuTier::LowPower) => 2,
+        Some(GpuTier::UltraLowPower) => 3,
+        None => 4,
+    });
+    merged
+}
+
+/// Symbolic name of `cmd`, e.g. "VERSION_CHECK" for 0x40108003
+///
+/// Searches the detection and query IOCTLs of every profile and returns the
+/// first matching name.
+pub fn cmd_name(cmd: u32, profiles: &[IoctlProfile]) -> Option<String> {
+    profile_ioctls(profiles)
+        .find(|ioctl| ioctl.cmd == cmd)
+        .map(|ioctl| ioctl.name.clone())
+}
+
+/// Detection and query IOCTLs of all `profiles`
+fn profile_ioctls(profiles: &[IoctlProfile]) -> impl Iterator<Item = &IoctlDefinition> {
+    profiles.iter().flat_map(|p| {
+        let optional = [&p.version_ioctl, &p.gpu_info_ioctl, &p.features_ioctl];
+        p.detection_ioctls.iter().chain(optional.into_iter().flatten())
+    })
+}
+
+/// Command number and symbolic name of one strace `ioctl(...)` line
+fn parse_strace_ioctl(line: &str, known: &[IoctlProfile]) -> Option<(u32, Option<String>)> {
+    let args = &line[line.find("ioctl(")? + "ioctl(".len()..];
+    let (_fd, rest) = args.split_once(',')?;
+    let rest = rest.trim_start();
+
+    if let Some(ioc) = rest.strip_prefix("_IOC(") {
+        let fields: Vec<&str> = ioc[..ioc.find(')')?].split(',').map(str::trim).collect();
+        let [dir, ty, nr, size] = fields[..] else {
+            return None;
+        };
+        let dir = dir.split('|').try_fold(0u32, |acc, flag| match flag.trim() {
+            "_IOC_NONE" => Some(acc),
+            "_IOC_WRITE" => Some(acc | 1),
+            "_IOC_READ" => Some(acc | 2),
+            _ => None,
+        })?;
+        let cmd = (dir << 30) | (parse_strace_number(size)? << 16)
+            | (parse_strace_number(ty)? << 8) | parse_strace_number(nr)?;
+        return Some((cmd, None));
+    }
+
+    let token = rest.split([',', ')']).next()?.trim();
+    if let Some(cmd) = parse_strace_number(token) {
+        return Some((cmd, cmd_name(cmd, known)));
+    }
+
+    let name = ["KBASE_IOCTL_", "MALI_IOCTL_", "IOCTL_KGSL_"].iter()
+        .find_map(|prefix| token.strip_prefix(prefix))
+        .unwrap_or(token);
+    profile_ioctls(known)
+        .find(|ioctl| ioctl.name == name)
+        .map(|ioctl| (ioctl.cmd, Some(ioctl.name.clone())))
+}
+
+fn parse_strace_number(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Resolve `parent_profile` references by merging every profile over its ancestors
+///
+/// Detection IOCTLs are combined, with child entries replacing parent entries
+/// of the same command. Query IOCTLs of the child win when set, and child
+/// metadata keys override the parent's. Parents are looked up by model name
+/// among `profiles`.
+pub fn resolve_profile_inheritance(profiles: &[IoctlProfile]) -> Result<Vec<IoctlProfile>, DiscoveryError> {
+    profiles.iter().map(|profile| {
+        // Walk up to the root, remembering the chain
+        let mut chain = vec![profile];
+        while let Some(parent_name) = &chain[chain.len() - 1].parent_profile {
+            let parent = profiles.iter()
+                .find(|p| &p.model == parent_name)
+                .ok_or_else(|| DiscoveryError::Profile(format!("unknown parent profile {}", parent_name)))?;
+            if chain.iter().any(|p| p.model == parent.model) {
+                return Err(DiscoveryError::Profile("circular inheritance".to_string()));
+            }
+            chain.push(parent);
+        }
+
+        let mut chain = chain.into_iter().rev();
+        let root = chain.next().cloned().unwrap_or_else(|| profile.clone());
+        Ok(chain.fold(root, |parent, child| inherit_profile(parent, child.clone())))
+    }).collect()
+}
+
+/// Merge `child` over an already resolved `parent`
+fn inherit_profile(parent: IoctlProfile, mut child: IoctlProfile) -> IoctlProfile {
+    let mut detection_ioctls: Vec<IoctlDefinition> = parent.detection_ioctls.into_iter()
+        .map(|ioctl| match child.detection_ioctls.iter().position(|d| d.cmd == ioctl.cmd) {
+            Some(i) => child.detection_ioctls.remove(i),
+            None => ioctl,
+        })
+        .collect();
+    detection_ioctls.append(&mut child.detection_ioctls);
+    child.detection_ioctls = detection_ioctls;
+
+    child.version_ioctl = child.version_ioctl.or(parent.version_ioctl);
+    child.gpu_info_ioctl = child.gpu_info_ioctl.or(parent.gpu_info_ioctl);
+    child.features_ioctl = child.features_ioctl.or(parent.features_ioctl);
+
+    if let serde_json::Value::Object(mut metadata) = parent.metadata {
+        if let serde_json::Value::Object(child_metadata) = child.metadata {
+            metadata.extend(child_metadata);
+        }
+        child.metadata = serde_json::Value::Object(metadata);
+    }
+    child
+}
+
+/// Kernel version as (major, minor, patch)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KernelVersion(pub u32, pub u32, pub u32);
+
+impl KernelVersion {
+    /// Parse a kernel release string such as "5.10.43-android12-9"
+    ///
+    /// Missing minor and patch components default to 0.
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version
+            .split(|c: char| !c.is_ascii_digit())
+            .take(3)
+            .map(|p| p.parse::<u32>().ok());
+
+        let major = parts.next()??;
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(Self(major, minor, patch))
+    }
+
+    /// Version of the running kernel, read from `/proc/version`
+    pub fn running() -> Option<Self> {
+        running_kernel_version().as_deref().and_then(Self::parse)
+    }
+
+    /// Major version number
+    pub fn major(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// Change to a watched profile directory, reported by `ProfileWatcher`
+#[cfg(feature = "watch")]
+#[derive(Debug, Clone)]
+pub enum ProfileUpdate {
+    /// A new profile file appeared
+    Added(IoctlProfile),
+    /// An existing profile file changed
+    Modified(IoctlProfile),
+    /// A profile file was deleted; carries the model name of the profile
+    Removed(String),
+}
+
+/// Watches a directory of `*.json` profiles for changes via inotify
+///
+/// Only works on Linux. Files that fail to parse (e.g. while still being
+/// written) keep their previously loaded version.
+#[cfg(feature = "watch")]
+pub struct ProfileWatcher {
+    dir: PathBuf,
+    inotify: Mutex<inotify::Inotify>,
+    // Loaded profiles per file, with their JSON form for change detection
+    loaded: Mutex<HashMap<PathBuf, (String, IoctlProfile)>>,
+}
+
+#[cfg(feature = "watch")]
+impl ProfileWatcher {
+    /// Start watching `dir` and load the profiles it currently contains
+    pub fn new(dir: &str) -> io::Result<Self> {
+        use inotify::{Inotify, WatchMask};
+
+        let inotify = Inotify::init()?;
+        inotify.watches().add(
+            dir,
+            WatchMask::CLOSE_WRITE | WatchMask::CREATE | WatchMask::DELETE
+                | WatchMask::MODIFY | WatchMask::MOVED_FROM | WatchMask::MOVED_TO,
+        )?;
+
+        let dir = PathBuf::from(dir);
+        let loaded = Self::scan_dir(&dir, &HashMap::new())?;
+
+        Ok(Self {
+            dir,
+            inotify: Mutex::new(inotify),
+            loaded: Mutex::new(loaded),
+        })
+    }
+
+    /// Profiles currently loaded from the directory
+    pub fn profiles(&self) -> Vec<IoctlProfile> {
+        let loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        loaded.values().map(|(_, profile)| profile.clone()).collect()
+    }
+
+    /// Return changes since the last poll without blocking
+    pub fn poll_updates(&self) -> Vec<ProfileUpdate> {
+        if !self.has_json_events() {
+            return Vec::new();
+        }
+
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        let Ok(current) = Self::scan_dir(&self.dir, &loaded) else {
+            return Vec::new();
+        };
+
+        let mut updates = Vec::new();
+        for (path, (json, profile)) in &current {
+            match loaded.get(path) {
+                None => updates.push(ProfileUpdate::Added(profile.clone())),
+                Some((old_json, _)) if old_json != json => updates.push(ProfileUpdate::Modified(profile.clone())),
+                Some(_) => {}
+            }
+        }
+        for (path, (_, profile)) in loaded.iter() {
+            if !current.contains_key(path) {
+                updates.push(ProfileUpdate::Removed(profile.model.clone()));
+            }
+        }
+
+        *loaded = current;
+        updates
+    }
+
+    /// Drain pending inotify events, reporting whether any touched a JSON file
+    fn has_json_events(&self) -> bool {
+        let mut inotify = self.inotify.lock().unwrap_or_else(|e| e.into_inner());
+        let mut buffer = [0u8; 4096];
+        let mut relevant = false;
+
+        while let Ok(events) = inotify.read_events(&mut buffer) {
+            let mut any = false;
+            for event in events {
+                any = true;
+                let is_json = event.name
+                    .map(|name| Path::new(name).extension() == Some("json".as_ref()))
+                    .unwrap_or(false);
+                relevant |= is_json;
+            }
+            if !any {
+                break;
+            }
+        }
+
+        relevant
+    }
+
+    /// Load all `*.json` profiles in `dir`, keeping `previous` entries for unparsable files
+    ///
+    /// A file whose `sha256sum` does not match counts as unparsable.
+    fn scan_dir(
+        dir: &Path,
+        previous: &HashMap<PathBuf, (String, IoctlProfile)>,
+    ) -> io::Result<HashMap<PathBuf, (String, IoctlProfile)>> {
+        let mut profiles = HashMap::new();
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension() != Some("json".as_ref()) {
+                continue;
+            }
+
+            let parsed = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|data| serde_json::from_str::<IoctlProfile>(&data).ok())
+                .filter(|profile| profile.sha256sum.as_ref().map_or(true, |sum| profile.verify_checksum(sum)));
+            match parsed {
+                Some(profile) => {
+                    let json = serde_json::to_string(&profile).unwrap_or_default();
+                    profiles.insert(path, (json, profile));
+                }
+                None => {
+                    if let Some(old) = previous.get(&path) {
+                        profiles.insert(path, old.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(profiles)
+    }
+}
+
+/// Read the running kernel version from `/proc/version`
+///
+/// Returns the release string, e.g. "5.10.43-android12-9".
+pub fn running_kernel_version() -> Option<String> {
+    let version = std::fs::read_to_string("/proc/version").ok()?;
+    // Format: "Linux version <release> (...)"
+    version.split_whitespace().nth(2).map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_with_min_kernel(min: Option<&str>) -> IoctlProfile {
+        IoctlProfile {
+            vendor: "Mali".to_string(),
+            model: "Test".to_string(),
+            version: default_profile_version(),
+            min_kernel_version: min.map(|s| s.to_string()),
+            compatible_kernels: Vec::new(),
+            deprecated: false,
+            superseded_by: None,
+            parent_profile: None,
+            sample_confidence: None,
+            sha256sum: None,
+            detection_ioctls: vec![],
+            version_ioctl: None,
+            gpu_info_ioctl: None,
+            features_ioctl: None,
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_kernel_compatibility() {
+        let mut profile = profile_with_min_kernel(None);
+        let kernel = |v: &str| KernelVersion::parse(v).unwrap();
+        assert_eq!(profile.kernel_compatibility(&kernel("5.10")), CompatibilityStatus::Untested);
+
+        profile.compatible_kernels = vec![
+            KernelVersionRange { min: "4.4".to_string(), max: Some("4.9".to_string()) },
+            KernelVersionRange { min: "6.1".to_string(), max: Some("6".to_string()) },
+        ];
+        assert_eq!(profile.kernel_compatibility(&kernel("4.9.118")), CompatibilityStatus::Compatible);
+        assert_eq!(profile.kernel_compatibility(&kernel("6.6.30-android15")), CompatibilityStatus::Compatible);
+        assert_eq!(profile.kernel_compatibility(&kernel("5.10")), CompatibilityStatus::Incompatible);
+        assert_eq!(profile.kernel_compatibility(&kernel("6.0.9")), CompatibilityStatus::Incompatible);
+        assert_eq!(profile.kernel_compatibility(&kernel("7.0")), CompatibilityStatus::Incompatible);
+
+        let open = KernelVersionRange { min: "5.4".to_string(), max: None };
+        assert!(open.contains(&kernel("6.12")));
+
+        let g720 = super::mali::load_mali_profiles().into_iter().find(|p| p.model == "Mali-G720").unwrap();
+        assert_eq!(g720.kernel_compatibility(&kernel("6.1.75")), CompatibilityStatus::Compatible);
+    }
+
+    #[test]
+    fn test_from_strace_output() {
+        let strace = "\
+openat(AT_FDCWD, \"/dev/mali0\", O_RDWR|O_CLOEXEC) = 3
+ioctl(3, 0x40108003, 0x7ff4321000) = 749
+1234  ioctl(3, MALI_IOCTL_GET_GPU_INFO, 0x7ff4321000) = 0
+ioctl(3, 0x40108003, 0x7ff4321010) = 749
+ioctl(3, _IOC(_IOC_READ|_IOC_WRITE, 0x80, 0x22, 0x18), 0x7ff4321020) = -1 EINVAL (Invalid argument)
+ioctl(3, UNKNOWN_SYMBOL, 0x7ff4321030) = 0
+";
+        let profile = IoctlProfile::from_strace_output(strace, "ARM", "Mali-G71").unwrap();
+        let ioctls: Vec<(&str, u32, u16)> = profile.detection_ioctls.iter()
+            .map(|d| (d.name.as_str(), d.cmd, d.buffer_size))
+            .collect();
+        assert_eq!(ioctls, vec![
+            ("VERSION_CHECK", 0x40108003, 16),
+            ("GET_GPU_INFO", 0x8004800c, 4),
+            ("unknown_80_22", 0xC0188022, 24),
+        ]);
+        assert_eq!(profile.metadata["directions"]["0x8004800c"], "read");
+        assert!(profile.validate().is_ok());
+
+        assert!(IoctlProfile::from_strace_output("close(3) = 0", "ARM", "Mali-G71").is_err());
+    }
+
+    #[test]
+    fn test_cmd_name() {
+        let profiles = load_mali_profiles();
+        assert_eq!(cmd_name(0x40108003, &profiles).as_deref(), Some("VERSION_CHECK"));
+        assert_eq!(cmd_name(0x8004800c, &profiles).as_deref(), Some("GET_GPU_INFO"));
+        assert_eq!(cmd_name(0x12345678, &profiles), None);
+        assert_eq!(cmd_name(0x40108003, &[]), None);
+    }
+
+    #[test]
+    fn test_parse_kernel_version() {
+        assert_eq!(KernelVersion::parse("4.4.177"), Some(KernelVersion(4, 4, 177)));
+        assert_eq!(KernelVersion::parse("5.10.43-android12-9"), Some(KernelVersion(5, 10, 43)));
+        assert_eq!(KernelVersion::parse("6.1"), Some(KernelVersion(6, 1, 0)));
+        assert_eq!(KernelVersion::parse("garbage"), None);
+    }
+
+    #[test]
+    fn test_confidence_by_kernel_distance() {
+        let mut profile = profile_with_min_kernel(None);
+        assert_eq!(profile.confidence(&KernelVersion(5, 10, 0)), 1.0);
+
+        profile.metadata = serde_json::json!({ "confirmed_kernel_version": "4.4.177" });
+        assert_eq!(profile.confidence(&KernelVersion(4, 14, 0)), 1.0);
+        assert!((profile.confidence(&KernelVersion(6, 1, 0)) - 0.8).abs() < 1e-9);
+        assert!((profile.confidence(&KernelVersion(14, 0, 0)) - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_is_applicable_for_kernel() {
+        let profile = profile_with_min_kernel(Some("5.4"));
+        assert!(profile.is_applicable_for_kernel("5.10.43-android12-9"));
+        assert!(profile.is_applicable_for_kernel("5.4.0"));
+        assert!(!profile.is_applicable_for_kernel("4.4.177"));
+
+        let profile = profile_with_min_kernel(None);
+        assert!(profile.is_applicable_for_kernel("3.18.0"));
+    }
+
+    #[test]
+    fn test_embedded_profiles_validate() {
+        for profile in load_mali_profiles() {
+            assert!(profile.validate().is_ok(), "{} failed validation", profile.model);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_small_buffer() {
+        let mut profile = profile_with_min_kernel(None);
+        assert!(profile.validate().is_err()); // no detection IOCTLs
+
+        profile.detection_ioctls.push(IoctlDefinition {
+            name: "VERSION_CHECK".to_string(),
+            cmd: 0x40108003,
+            buffer_size: 8,
+            parser: "parse_version_return_value".to_string(),
+            params: serde_json::json!({}),
+        });
+        assert!(profile.validate().is_err());
+
+        profile.detection_ioctls[0].buffer_size = 16;
+        assert!(profile.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_response() {
+        let mut def = IoctlDefinition {
+            name: "GET_PROPS_00".to_string(),
+            cmd: 0xC0048000,
+            buffer_size: 4,
+            parser: "parse_features_bitmask".to_string(),
+            params: serde_json::json!({ "expected_core_mask": "0x3" }),
+        };
+        assert_eq!(def.validate_response(&3u32.to_ne_bytes(), 0), ValidationResult::Valid);
+        assert_eq!(
+            def.validate_response(&0xfu32.to_ne_bytes(), 0),
+            ValidationResult::Mismatch { expected: "0x3".to_string(), actual: "0xf".to_string() }
+        );
+        assert_eq!(def.validate_response(&[], 0), ValidationResult::Inconclusive);
+
+        def.params = serde_json::json!({ "expected_return": 711 });
+        assert_eq!(def.validate_response(&[], 711), ValidationResult::Valid);
+        assert!(matches!(def.validate_response(&[], 749), ValidationResult::Mismatch { .. }));
+
+        def.params = serde_json::json!({});
+        assert_eq!(def.validate_response(&[], 0), ValidationResult::Inconclusive);
+    }
+
+    #[test]
+    fn test_merge_profiles() {
+        let detection = |cmd: u32| IoctlDefinition {
+            name: format!("IOCTL_{:08x}", cmd),
+            cmd,
+            buffer_size: 16,
+            parser: "parse_generic".to_string(),
+            params: serde_json::json!({}),
+        };
+
+        let mut generic = profile_with_min_kernel(None);
+        generic.model = "Generic Mali".to_string();
+        let mut g71 = profile_with_min_kernel(None);
+        g71.model = "Mali-G71".to_string();
+        g71.detection_ioctls = vec![detection(0xC0048000), detection(0x40108003)];
+        let mut g720 = profile_with_min_kernel(None);
+        g720.model = "Mali-G720".to_string();
+        g720.metadata = serde_json::json!({ "tier": "HighPerformance" });
+
+        let mut user_g71 = profile_with_min_kernel(Some("4.4"));
+        user_g71.model = "Mali-G71".to_string();
+        user_g71.detection_ioctls = vec![detection(0x40108003), detection(0x8004800c), detection(0x8004800c)];
+
+        let merged = merge_profiles(vec![generic, g71, g720], vec![user_g71]);
+
+        let models: Vec<&str> = merged.iter().map(|p| p.model.as_str()).collect();
+        assert_eq!(models, vec!["Mali-G720", "Mali-G71", "Generic Mali"]);
+
+        // User profile wins, built-in detection IOCTLs are added without duplicates
+        let g71 = &merged[1];
+        assert_eq!(g71.min_kernel_version.as_deref(), Some("4.4"));
+        let cmds: Vec<u32> = g71.detection_ioctls.iter().map(|d| d.cmd).collect();
+        assert_eq!(cmds, vec![0x40108003, 0x8004800c, 0xC0048000]);
+    }
+
+    fn inheriting(model: &str, parent: Option<&str>, cmds: &[(u32, u16)], metadata: serde_json::Value) -> IoctlProfile {
+        let mut profile = profile_with_min_kernel(None);
+        profile.model = model.to_string();
+        profile.parent_profile = parent.map(|p| p.to_string());
+        profile.detection_ioctls = cmds.iter()
+            .map(|&(cmd, buffer_size)| IoctlDefinition {
+                name: format!("IOCTL_{:08x}", cmd),
+                cmd,
+                buffer_size,
+                parser: "parse_generic".to_string(),
+                params: serde_json::json!({}),
+            })
+            .collect();
+        profile.metadata = metadata;
+        profile
+    }
+
+    #[test]
+    fn test_single_level_inheritance() {
+        let mut base = inheriting("Mali Base", None, &[(0xC0048000, 4)], serde_json::json!({
+            "architecture": "Bifrost", "core_count": 2,
+        }));
+        base.version_ioctl = base.detection_ioctls.first().cloned();
+        let child = inheriting("Mali-G71", Some("Mali Base"), &[(0xC0048000, 8), (0x40108003, 16)], serde_json::json!({
+            "core_count": 8,
+        }));
+
+        let resolved = resolve_profile_inheritance(&[base, child]).unwrap();
+        let child = &resolved[1];
+        let cmds: Vec<(u32, u16)> = child.detection_ioctls.iter().map(|d| (d.cmd, d.buffer_size)).collect();
+        assert_eq!(cmds, vec![(0xC0048000, 8), (0x40108003, 16)]);
+        assert_eq!(child.version_ioctl.as_ref().map(|v| v.cmd), Some(0xC0048000));
+        assert_eq!(child.metadata, serde_json::json!({ "architecture": "Bifrost", "core_count": 8 }));
+        assert!(resolved[0].parent_profile.is_none());
+    }
+
+    #[test]
+    fn test_two_level_inheritance() {
+        let root = inheriting("Mali", None, &[(0xC0048000, 4)], serde_json::json!({ "vendor_id": 1 }));
+        let bifrost = inheriting("Bifrost", Some("Mali"), &[(0x40108003, 16)], serde_json::json!({ "architecture": "Bifrost" }));
+        let g76 = inheriting("Mali-G76", Some("Bifrost"), &[(0x8004800c, 4)], serde_json::json!({ "core_count": 12 }));
+
+        // Order of the input does not matter
+        let resolved = resolve_profile_inheritance(&[g76, bifrost, root]).unwrap();
+        let g76 = &resolved[0];
+        let cmds: Vec<u32> = g76.detection_ioctls.iter().map(|d| d.cmd).collect();
+        assert_eq!(cmds, vec![0xC0048000, 0x40108003, 0x8004800c]);
+        assert_eq!(g76.metadata, serde_json::json!({ "vendor_id": 1, "architecture": "Bifrost", "core_count": 12 }));
+    }
+
+    #[test]
+    fn test_circular_inheritance_is_rejected() {
+        let a = inheriting("A", Some("C"), &[], serde_json::json!({}));
+        let b = inheriting("B", Some("A"), &[], serde_json::json!({}));
+        let c = inheriting("C", Some("B"), &[], serde_json::json!({}));
+        let err = resolve_profile_inheritance(&[a, b, c]).unwrap_err();
+        assert!(matches!(err, DiscoveryError::Profile(msg) if msg == "circular inheritance"));
+
+        let selfish = inheriting("Self", Some("Self"), &[], serde_json::json!({}));
+        assert!(resolve_profile_inheritance(&[selfish]).is_err());
+
+        let orphan = inheriting("Orphan", Some("Missing"), &[], serde_json::json!({}));
+        assert!(matches!(resolve_profile_inheritance(&[orphan]), Err(DiscoveryError::Profile(_))));
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_profile_watcher_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let watcher = ProfileWatcher::new(dir.path().to_str().unwrap()).unwrap();
+        assert!(watcher.poll_updates().is_empty());
+
+        let path = dir.path().join("g71.json");
+        let mut profile = profile_with_min_kernel(None);
+        std::fs::write(&path, serde_json::to_string(&profile).unwrap()).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let updates = watcher.poll_updates();
+        assert!(matches!(updates.as_slice(), [ProfileUpdate::Added(p)] if p.model == "Test"));
+
+        profile.deprecated = true;
+        std::fs::write(&path, serde_json::to_string(&profile).unwrap()).unwrap();
+        let updates = watcher.poll_updates();
+        assert!(matches!(updates.as_slice(), [ProfileUpdate::Modified(p)] if p.deprecated));
+
+        std::fs::remove_file(&path).unwrap();
+        let updates = watcher.poll_updates();
+        assert!(matches!(updates.as_slice(), [ProfileUpdate::Removed(model)] if model == "Test"));
+        assert!(watcher.profiles().is_empty());
+    }
+
+    #[test]
+    fn test_profile_defaults_from_json() {
+        let json = r#"{
+            "vendor": "Mali",
+            "model": "Old",
+            "detection_ioctls": [],
+            "version_ioctl": null,
+            "gpu_info_ioctl": null,
+            "features_ioctl": null,
+            "metadata": {}
+        }"#;
+        let profile: IoctlProfile = serde_json::from_str(json).unwrap();
+        assert_eq!(profile.version, "1.0.0");
+        assert!(!profile.deprecated);
+        assert!(profile.min_kernel_version.is_none());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut profile = load_mali_profiles().remove(0);
+        let checksum = profile.checksum();
+        assert_eq!(checksum.len(), 64);
+        assert!(profile.verify_checksum(&checksum.to_uppercase()));
+
+        // The stored checksum is not part of the digest
+        profile.sha256sum = Some(checksum.clone());
+        assert_eq!(profile.checksum(), checksum);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profile.json");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, serde_json::to_string_pretty(&profile).unwrap()).unwrap();
+        assert_eq!(IoctlProfile::from_json_file(path).unwrap().model, profile.model);
+
+        profile.model.push_str(" (tampered)");
+        std::fs::write(path, serde_json::to_string(&profile).unwrap()).unwrap();
+        let err = IoctlProfile::from_json_file(path).unwrap_err();
+        assert!(matches!(err, DiscoveryError::Profile(ref msg) if msg == "checksum mismatch"));
+
+        profile.sha256sum = None;
+        std::fs::write(path, serde_json::to_string(&profile).unwrap()).unwrap();
+        assert!(IoctlProfile::from_json_file(path).is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+//! Extension point for GPU vendors that iodisco does not know about
+//!
+//! Third-party crates register a [`VendorExtensions`] implementation once at
+//! startup; device detection and profile matching consult it afterwards.
+
+use std::sync::RwLock;
+
+use crate::api::GpuInfo;
+use crate::profiles::IoctlProfile;
+
+/// Device detection and identification for one additional GPU vendor
+pub trait VendorExtensions: Send + Sync {
+    /// Device node paths of this vendor
+    ///
+    /// A pattern ending in `*` matches every entry of its directory that
+    /// starts with the text before the `*`, e.g. `/dev/acme_gpu*`.
+    fn device_patterns(&self) -> &[&str];
+
+    /// Build a `GpuInfo` from the response of a profile's `gpu_info_ioctl`
+    fn identify(&self, raw_data: &[u8]) -> Option<GpuInfo>;
+
+    /// Profiles used to recognize this vendor's devices
+    fn profiles(&self) -> Vec<IoctlProfile>;
+}
+
+static EXTENSIONS: RwLock<Vec<Box<dyn VendorExtensions>>> = RwLock::new(Vec::new());
+
+/// Process-wide list of registered vendor extensions
+pub struct VendorRegistry;
+
+impl VendorRegistry {
+    /// Add an extension for all later device lookups
+    ///
+    /// The registry is process-global and has no way to remove an extension,
+    /// so every thread sees it from now on, `find_gpu_devices` included.
+    pub fn register(ext: Box<dyn VendorExtensions>) {
+        EXTENSIONS.write().unwrap_or_else(|e| e.into_inner()).push(ext);
+    }
+
+    /// Number of registered extensions
+    pub fn count() -> usize {
+        EXTENSIONS.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Existing device nodes matching any registered pattern
+    pub fn device_paths() -> Vec<String> {
+        let extensions = EXTENSIONS.read().unwrap_or_else(|e| e.into_inner());
+        let mut paths: Vec<String> = extensions.iter()
+            .flat_map(|ext| ext.device_patterns().iter().flat_map(|p| expand_pattern(p)))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Run `f` on every extension with a pattern matching `device`
+    #[cfg(feature = "discovery")]
+    pub(crate) fn for_device<T>(device: &str, mut f: impl FnMut(&dyn VendorExtensions) -> Option<T>) -> Option<T> {
+        let extensions = EXTENSIONS.read().unwrap_or_else(|e| e.into_inner());
+        extensions.iter()
+            .filter(|ext| ext.device_patterns().iter().any(|p| pattern_matches(p, device)))
+            .find_map(|ext| f(ext.as_ref()))
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix) && !path[prefix.len()..].contains('/'),
+        None => pattern == path,
+    }
+}
+
+fn expand_pattern(pattern: &str) -> Vec<String> {
+    let Some(prefix) = pattern.strip_suffix('*') else {
+        return if std::fs::metadata(pattern).is_ok() {
+            vec![pattern.to_string()]
+        } else {
+            Vec::new()
+        };
+    };
+
+    let dir = std::path::Path::new(prefix).parent().unwrap_or(std::path::Path::new("/"));
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().to_string_lossy().into_owned())
+        .filter(|path| pattern_matches(pattern, path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct AcmeGpu {
+        patterns: Vec<&'static str>,
+    }
+
+    impl VendorExtensions for AcmeGpu {
+        fn device_patterns(&self) -> &[&str] {
+            &self.patterns
+        }
+
+        fn identify(&self, raw_data: &[u8]) -> Option<GpuInfo> {
+            let _ = raw_data;
+            None
+        }
+
+        fn profiles(&self) -> Vec<IoctlProfile> {
+            Vec::new()
+        }
+    }
+
+    /// Drops every registration again, so the extension doesn't show up in
+    /// `find_gpu_devices` of later tests
+    struct ClearRegistry;
+
+    impl Drop for ClearRegistry {
+        fn drop(&mut self) {
+            EXTENSIONS.write().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+
+    #[test]
+    fn test_registry_finds_matching_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = dir.path().join("acme_gpu0");
+        std::fs::write(&node, b"").unwrap();
+        std::fs::write(dir.path().join("other0"), b"").unwrap();
+
+        // Leaked so the pattern outlives the registration
+        let pattern: &'static str = Box::leak(format!("{}/acme_gpu*", dir.path().display()).into_boxed_str());
+        let _clear = ClearRegistry;
+        VendorRegistry::register(Box::new(AcmeGpu { patterns: vec![pattern] }));
+        assert!(VendorRegistry::count() >= 1);
+
+        let node = node.to_string_lossy().into_owned();
+        assert_eq!(VendorRegistry::device_paths(), vec![node.clone()]);
+        #[cfg(feature = "discovery")]
+        {
+            assert!(VendorRegistry::for_device(&node, |_| Some(())).is_some());
+            assert!(VendorRegistry::for_device("/dev/mali0", |_| Some(())).is_none());
+            assert!(crate::discovery::find_gpu_devices().contains(&node));
+        }
+    }
+}
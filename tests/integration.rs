@@ -1,7 +1,5 @@
 #[cfg(test)]
 mod integration_tests {
-    use iodisco;
-    
     #[test]
     fn test_library_initialization() {
         iodisco::init();